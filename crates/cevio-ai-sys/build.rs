@@ -1,22 +1,62 @@
-use std::{env, path::PathBuf};
+use std::{env, fs, path::Path, path::PathBuf};
+
+#[path = "build/codegen.rs"]
+mod codegen;
+#[path = "build/idl.rs"]
+mod idl;
+
+/// CeVIO AIのメタデータ
+const AI_WINMD: &str = ".windows/winmd/CeVIO.Talk.RemoteService2.winmd";
+
+/// CeVIO AIのタイプライブラリのIDL
+const AI_IDL: &str = ".metadata/CeVIO.Talk.RemoteService2.IDL";
 
 fn main() {
-    println!("cargo:rerun-if-changed=.windows/winmd/CeVIO.Talk.RemoteService2.winmd");
+    println!("cargo:rerun-if-changed={AI_WINMD}");
+    println!("cargo:rerun-if-changed={AI_IDL}");
+    println!("cargo:rerun-if-changed=build");
     println!("cargo:rerun-if-changed=build.rs");
 
-    let out_path = PathBuf::from(env::var("OUT_DIR").unwrap()).join("bindings.rs");
+    let out_dir = PathBuf::from(env::var("OUT_DIR").unwrap());
+
+    // テストではIDLからのバインディングを実行時に生成し、winmdの結果と比較する
+    if env::var_os("CARGO_FEATURE_IDL").is_some() {
+        generate_from_idl(
+            AI_IDL,
+            "CeVIO.Talk.RemoteService2",
+            &out_dir.join("bindings_idl.rs"),
+        );
+    } else {
+        generate(
+            AI_WINMD,
+            "CeVIO.Talk.RemoteService2",
+            &out_dir.join("bindings.rs"),
+        );
+    }
+}
+
+/// IDLを解釈し、指定した名前空間のバインディングを生成します。
+fn generate_from_idl(idl: &str, namespace: &str, out_path: &Path) {
+    let source = fs::read_to_string(idl).unwrap_or_else(|e| panic!("failed to read {idl}: {e}"));
+    let library = idl::parse(&source).unwrap_or_else(|e| panic!("failed to parse {idl}: {e}"));
+    let bindings = codegen::generate(&library, namespace)
+        .unwrap_or_else(|e| panic!("failed to generate bindings from {idl}: {e}"));
+    fs::write(out_path, bindings).unwrap();
+}
 
+/// メタデータから指定した名前空間のバインディングを生成します。
+fn generate(winmd: &str, filter: &str, out_path: &Path) {
     let warnings = windows_bindgen::bindgen([
         "--in",
         "default",
-        ".windows/winmd/CeVIO.Talk.RemoteService2.winmd",
+        winmd,
         "--out",
         out_path.to_str().unwrap(),
         "--filter",
-        "CeVIO.Talk.RemoteService2",
+        filter,
         "--reference",
         "windows,skip-root,Windows",
-        "--no-allow"
+        "--no-allow",
     ]);
 
     warnings.iter().for_each(|warning| {
@@ -0,0 +1,242 @@
+//! IDLから読み取ったタイプライブラリのRustバインディング生成
+//!
+//! windows-bindgenが生成するバインディングのうち、COMオブジェクトを呼び出す側に必要な部分
+//! （インターフェース定義・メソッド・vtable・coclassのCLSID）だけを同じ形で出力します。
+
+use std::fmt::Write;
+
+use crate::idl::{Interface, Library, Method, Type};
+
+/// `namespace`（`CeVIO.Talk.RemoteService2`）のモジュールとしてバインディングを生成します。
+pub fn generate(library: &Library, namespace: &str) -> Result<String, String> {
+    let mut out = String::new();
+    writeln!(
+        out,
+        "// Bindings generated from the IDL of library `{}` ({:#034x})",
+        library.name, library.uuid
+    )
+    .unwrap();
+
+    let modules: Vec<&str> = namespace.split('.').collect();
+    for module in &modules {
+        writeln!(out, "pub mod {module} {{").unwrap();
+    }
+
+    let mut interfaces: Vec<&Interface> = library.interfaces.iter().collect();
+    interfaces.sort_by(|a, b| a.name.cmp(&b.name));
+    for interface in interfaces {
+        write_interface(&mut out, interface)?;
+    }
+
+    let mut coclasses: Vec<_> = library.coclasses.iter().collect();
+    coclasses.sort_by(|a, b| a.name.cmp(&b.name));
+    for coclass in coclasses {
+        writeln!(
+            out,
+            "pub const {}: windows_core::GUID = windows_core::GUID::from_u128({});",
+            coclass.name,
+            guid_literal(coclass.uuid)
+        )
+        .unwrap();
+    }
+
+    for _ in &modules {
+        writeln!(out, "}}").unwrap();
+    }
+    Ok(out)
+}
+
+/// `0xa409140f_b7a5_48ef_893e_8b971420e05a`形式のリテラル
+fn guid_literal(uuid: u128) -> String {
+    let hex = format!("{uuid:032x}");
+    format!(
+        "0x{}_{}_{}_{}_{}",
+        &hex[..8],
+        &hex[8..12],
+        &hex[12..16],
+        &hex[16..20],
+        &hex[20..]
+    )
+}
+
+/// 基底インターフェースのパス
+fn base_path(base: &str) -> Result<&'static str, String> {
+    match base {
+        "IDispatch" => Ok("windows::Win32::System::Com::IDispatch"),
+        "IUnknown" => Ok("windows_core::IUnknown"),
+        other => Err(format!("unsupported base interface `{other}`")),
+    }
+}
+
+/// vtableに置くABI上の型
+fn abi_type(ty: &Type) -> &'static str {
+    match ty {
+        Type::Bool => "windows::Win32::Foundation::VARIANT_BOOL",
+        Type::I32 => "i32",
+        Type::U32 => "u32",
+        Type::F64 => "f64",
+        Type::Bstr | Type::Interface(_) => "*mut core::ffi::c_void",
+    }
+}
+
+/// メソッドの引数の型と、ABIへの変換式
+fn param_type(ty: &Type, name: &str) -> (String, String) {
+    match ty {
+        Type::Bstr => (
+            "&windows_core::BSTR".to_string(),
+            format!("core::mem::transmute_copy({name})"),
+        ),
+        Type::Interface(interface) => (
+            format!("&{interface}"),
+            format!("core::mem::transmute_copy({name})"),
+        ),
+        other => (abi_type(other).to_string(), name.to_string()),
+    }
+}
+
+/// 戻り値の型と、`result__`からの変換
+fn return_type(ty: &Type) -> (String, &'static str) {
+    match ty {
+        Type::Bstr => (
+            "windows_core::BSTR".to_string(),
+            ".map(|| core::mem::transmute(result__))",
+        ),
+        Type::Interface(interface) => (
+            interface.clone(),
+            ".and_then(|| windows_core::Type::from_abi(result__))",
+        ),
+        other => (abi_type(other).to_string(), ".map(|| result__)"),
+    }
+}
+
+/// windows-bindgenと同じく、引数名は小文字にします。
+fn param_name(name: &str) -> String {
+    let name = name.to_lowercase();
+    match name.as_str() {
+        "type" | "ref" | "self" | "move" | "fn" | "mod" | "use" | "in" => format!("r#{name}"),
+        _ => name,
+    }
+}
+
+fn write_interface(out: &mut String, interface: &Interface) -> Result<(), String> {
+    let name = &interface.name;
+    let base = base_path(&interface.base)?;
+
+    writeln!(
+        out,
+        "windows_core::imp::define_interface!({name}, {name}_Vtbl, {});",
+        guid_literal(interface.uuid)
+    )
+    .unwrap();
+    if base != "windows_core::IUnknown" {
+        writeln!(
+            out,
+            "impl core::ops::Deref for {name} {{ type Target = {base}; \
+             fn deref(&self) -> &Self::Target {{ unsafe {{ core::mem::transmute(self) }} }} }}"
+        )
+        .unwrap();
+        writeln!(
+            out,
+            "windows_core::imp::interface_hierarchy!({name}, windows_core::IUnknown, {base});"
+        )
+        .unwrap();
+    } else {
+        writeln!(
+            out,
+            "windows_core::imp::interface_hierarchy!({name}, windows_core::IUnknown);"
+        )
+        .unwrap();
+    }
+
+    writeln!(out, "impl {name} {{").unwrap();
+    for method in &interface.methods {
+        write_method(out, method);
+    }
+    writeln!(out, "}}").unwrap();
+
+    writeln!(out, "#[repr(C)]\n#[doc(hidden)]\npub struct {name}_Vtbl {{").unwrap();
+    writeln!(out, "pub base__: {base}_Vtbl,").unwrap();
+    for method in &interface.methods {
+        let mut params = vec!["*mut core::ffi::c_void".to_string()];
+        params.extend(method.params.iter().map(|p| abi_type(&p.ty).to_string()));
+        if let Some(retval) = &method.retval {
+            params.push(format!("*mut {}", abi_type(retval)));
+        }
+        writeln!(
+            out,
+            "pub {}: unsafe extern \"system\" fn({}) -> windows_core::HRESULT,",
+            method.rust_name(),
+            params.join(", ")
+        )
+        .unwrap();
+    }
+    writeln!(out, "}}").unwrap();
+    writeln!(out, "impl windows_core::RuntimeName for {name} {{}}").unwrap();
+    Ok(())
+}
+
+fn write_method(out: &mut String, method: &Method) {
+    let rust_name = method.rust_name();
+
+    let mut signature = vec!["&self".to_string()];
+    let mut args = vec!["windows_core::Interface::as_raw(self)".to_string()];
+    for param in &method.params {
+        let name = param_name(&param.name);
+        let (ty, arg) = param_type(&param.ty, &name);
+        signature.push(format!("{name}: {ty}"));
+        args.push(arg);
+    }
+
+    let (ret, body) = match &method.retval {
+        Some(retval) => {
+            let (ret, convert) = return_type(retval);
+            args.push("&mut result__".to_string());
+            (
+                ret,
+                format!(
+                    "let mut result__ = core::mem::zeroed(); \
+                     (windows_core::Interface::vtable(self).{rust_name})({}){convert}",
+                    args.join(", ")
+                ),
+            )
+        }
+        None => (
+            "()".to_string(),
+            format!(
+                "(windows_core::Interface::vtable(self).{rust_name})({}).ok()",
+                args.join(", ")
+            ),
+        ),
+    };
+
+    writeln!(
+        out,
+        "pub unsafe fn {rust_name}({}) -> windows_core::Result<{ret}> {{ unsafe {{ {body} }} }}",
+        signature.join(", ")
+    )
+    .unwrap();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::idl::parse;
+
+    #[test]
+    fn formats_guid_like_windows_bindgen() {
+        assert_eq!(
+            guid_literal(0xa409140f_b7a5_48ef_893e_8b971420e05a),
+            "0xa409140f_b7a5_48ef_893e_8b971420e05a"
+        );
+    }
+
+    #[test]
+    fn rejects_unknown_base_interface() {
+        let library = parse(
+            "[uuid(7E3B8901-0A65-44A0-9A9A-5F9F822D0716)] library L {\
+             [uuid(A409140F-B7A5-48EF-893E-8B971420E05A)] interface IFoo : IBar { }; };",
+        )
+        .unwrap();
+        assert!(generate(&library, "L").is_err());
+    }
+}
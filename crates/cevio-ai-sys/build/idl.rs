@@ -0,0 +1,639 @@
+//! タイプライブラリのIDLパーサー
+//!
+//! OLE/COM Object Viewerが出力するIDLのうち、CeVIOのタイプライブラリで使われる
+//! 構文（`library`・`interface`・`coclass`と属性）だけを解釈します。
+
+use std::fmt;
+
+/// パースエラー
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// タイプライブラリ
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Library {
+    pub name: String,
+    pub uuid: u128,
+    pub interfaces: Vec<Interface>,
+    pub coclasses: Vec<CoClass>,
+}
+
+impl Library {
+    /// 名前からインターフェースを検索します。
+    #[cfg(test)]
+    pub fn interface(&self, name: &str) -> Option<&Interface> {
+        self.interfaces
+            .iter()
+            .find(|interface| interface.name == name)
+    }
+}
+
+/// インターフェース
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Interface {
+    pub name: String,
+    pub uuid: u128,
+    pub base: String,
+    /// vtableの並び順のメソッド
+    pub methods: Vec<Method>,
+}
+
+/// coclass
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CoClass {
+    pub name: String,
+    pub uuid: u128,
+}
+
+/// メソッドの種類
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MethodKind {
+    Method,
+    PropGet,
+    PropPut,
+}
+
+/// メソッド
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Method {
+    pub name: String,
+    pub kind: MethodKind,
+    /// `[in]`引数
+    pub params: Vec<Param>,
+    /// `[out, retval]`引数の型
+    pub retval: Option<Type>,
+}
+
+impl Method {
+    /// vtableのフィールド名・Rustのメソッド名
+    ///
+    /// windows-bindgenと同じく、`propput`には`Set`を付けます。
+    pub fn rust_name(&self) -> String {
+        match self.kind {
+            MethodKind::PropPut => format!("Set{}", self.name),
+            MethodKind::Method | MethodKind::PropGet => self.name.clone(),
+        }
+    }
+}
+
+/// `[in]`引数
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Param {
+    pub name: String,
+    pub ty: Type,
+}
+
+/// 引数・戻り値の型
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Type {
+    Bool,
+    I32,
+    U32,
+    F64,
+    Bstr,
+    Interface(String),
+}
+
+/// IDLを解釈します。
+pub fn parse(source: &str) -> Result<Library, ParseError> {
+    let tokens = tokenize(source)?;
+    Parser { tokens, pos: 0 }.file()
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    /// 識別子・数値・GUIDなど
+    Word(String),
+    Str(String),
+    Punct(char),
+}
+
+fn tokenize(source: &str) -> Result<Vec<(Token, usize)>, ParseError> {
+    let mut tokens = Vec::new();
+    let mut chars = source.chars().peekable();
+    let mut line = 1;
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\n' => line += 1,
+            c if c.is_whitespace() => {}
+            '/' if chars.peek() == Some(&'/') => {
+                for c in chars.by_ref() {
+                    if c == '\n' {
+                        line += 1;
+                        break;
+                    }
+                }
+            }
+            '/' if chars.peek() == Some(&'*') => {
+                chars.next();
+                let mut prev = '\0';
+                loop {
+                    let Some(c) = chars.next() else {
+                        return Err(ParseError {
+                            line,
+                            message: "unterminated comment".to_string(),
+                        });
+                    };
+                    if c == '\n' {
+                        line += 1;
+                    }
+                    if prev == '*' && c == '/' {
+                        break;
+                    }
+                    prev = c;
+                }
+            }
+            '"' => {
+                let mut s = String::new();
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some(c) => s.push(c),
+                        None => {
+                            return Err(ParseError {
+                                line,
+                                message: "unterminated string".to_string(),
+                            })
+                        }
+                    }
+                }
+                tokens.push((Token::Str(s), line));
+            }
+            c if is_word_char(c) => {
+                let mut s = String::from(c);
+                while let Some(&c) = chars.peek() {
+                    if !is_word_char(c) {
+                        break;
+                    }
+                    s.push(c);
+                    chars.next();
+                }
+                tokens.push((Token::Word(s), line));
+            }
+            '[' | ']' | '(' | ')' | '{' | '}' | ',' | ';' | ':' | '*' | '=' => {
+                tokens.push((Token::Punct(c), line));
+            }
+            c => {
+                return Err(ParseError {
+                    line,
+                    message: format!("unexpected character `{c}`"),
+                })
+            }
+        }
+    }
+
+    Ok(tokens)
+}
+
+/// GUID（`A409140F-B7A5-...`）やバージョン（`1.0`）も1語として扱います。
+const fn is_word_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.')
+}
+
+/// 属性（`uuid(...)`など）
+#[derive(Debug)]
+struct Attribute {
+    name: String,
+    args: Vec<Token>,
+}
+
+fn find<'a>(attributes: &'a [Attribute], name: &str) -> Option<&'a Attribute> {
+    attributes.iter().find(|attribute| attribute.name == name)
+}
+
+fn has(attributes: &[Attribute], name: &str) -> bool {
+    find(attributes, name).is_some()
+}
+
+struct Parser {
+    tokens: Vec<(Token, usize)>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos).map(|(token, _)| token)
+    }
+
+    fn line(&self) -> usize {
+        self.tokens
+            .get(self.pos)
+            .or_else(|| self.tokens.last())
+            .map_or(1, |(_, line)| *line)
+    }
+
+    fn error<T>(&self, message: impl Into<String>) -> Result<T, ParseError> {
+        Err(ParseError {
+            line: self.line(),
+            message: message.into(),
+        })
+    }
+
+    fn next(&mut self) -> Result<Token, ParseError> {
+        match self.tokens.get(self.pos) {
+            Some((token, _)) => {
+                self.pos += 1;
+                Ok(token.clone())
+            }
+            None => self.error("unexpected end of input"),
+        }
+    }
+
+    fn is_punct(&self, c: char) -> bool {
+        self.peek() == Some(&Token::Punct(c))
+    }
+
+    fn eat_punct(&mut self, c: char) -> bool {
+        let matched = self.is_punct(c);
+        if matched {
+            self.pos += 1;
+        }
+        matched
+    }
+
+    fn expect_punct(&mut self, c: char) -> Result<(), ParseError> {
+        if self.eat_punct(c) {
+            Ok(())
+        } else {
+            self.error(format!("expected `{c}`, found {:?}", self.peek()))
+        }
+    }
+
+    fn word(&mut self) -> Result<String, ParseError> {
+        match self.peek() {
+            Some(Token::Word(word)) => {
+                let word = word.clone();
+                self.pos += 1;
+                Ok(word)
+            }
+            other => self.error(format!("expected a name, found {other:?}")),
+        }
+    }
+
+    fn expect_word(&mut self, expected: &str) -> Result<(), ParseError> {
+        let word = self.word()?;
+        if word == expected {
+            Ok(())
+        } else {
+            self.error(format!("expected `{expected}`, found `{word}`"))
+        }
+    }
+
+    /// `[...]`があれば属性の一覧を読みます。
+    fn attributes(&mut self) -> Result<Vec<Attribute>, ParseError> {
+        let mut attributes = Vec::new();
+        if !self.eat_punct('[') {
+            return Ok(attributes);
+        }
+        // 末尾のカンマ（`version(3.0),]`）を許容する
+        while !self.eat_punct(']') {
+            let name = self.word()?;
+            let mut args = Vec::new();
+            if self.eat_punct('(') {
+                let mut depth = 1;
+                loop {
+                    let token = self.next()?;
+                    match token {
+                        Token::Punct('(') => depth += 1,
+                        Token::Punct(')') => {
+                            depth -= 1;
+                            if depth == 0 {
+                                break;
+                            }
+                        }
+                        _ => {}
+                    }
+                    args.push(token);
+                }
+            }
+            attributes.push(Attribute { name, args });
+            if !self.eat_punct(',') && !self.is_punct(']') {
+                return self.error("expected `,` or `]` in attribute list");
+            }
+        }
+        Ok(attributes)
+    }
+
+    fn uuid(&self, attributes: &[Attribute]) -> Result<u128, ParseError> {
+        let Some(attribute) = find(attributes, "uuid") else {
+            return self.error("missing uuid attribute");
+        };
+        match attribute.args.as_slice() {
+            [Token::Word(uuid)] => match parse_uuid(uuid) {
+                Some(uuid) => Ok(uuid),
+                None => self.error(format!("invalid uuid `{uuid}`")),
+            },
+            _ => self.error("uuid attribute must have one argument"),
+        }
+    }
+
+    fn file(&mut self) -> Result<Library, ParseError> {
+        let mut library = None;
+        while self.peek().is_some() {
+            let attributes = self.attributes()?;
+            let keyword = self.word()?;
+            match keyword.as_str() {
+                "import" | "importlib" => {
+                    let has_parens = self.eat_punct('(');
+                    match self.next()? {
+                        Token::Str(_) => {}
+                        _ => return self.error("expected a file name"),
+                    }
+                    if has_parens {
+                        self.expect_punct(')')?;
+                    }
+                    self.expect_punct(';')?;
+                }
+                "library" if library.is_none() => {
+                    library = Some(self.library(&attributes)?);
+                }
+                other => return self.error(format!("unsupported declaration `{other}`")),
+            }
+        }
+        match library {
+            Some(library) => Ok(library),
+            None => self.error("no library declaration"),
+        }
+    }
+
+    fn library(&mut self, attributes: &[Attribute]) -> Result<Library, ParseError> {
+        let uuid = self.uuid(attributes)?;
+        let name = self.word()?;
+        self.expect_punct('{')?;
+
+        let mut interfaces = Vec::new();
+        let mut coclasses = Vec::new();
+        while !self.eat_punct('}') {
+            let attributes = self.attributes()?;
+            let keyword = self.word()?;
+            match keyword.as_str() {
+                "importlib" => {
+                    self.expect_punct('(')?;
+                    self.next()?;
+                    self.expect_punct(')')?;
+                    self.expect_punct(';')?;
+                }
+                // 前方宣言
+                "interface" if !self.is_lookahead_body() => {
+                    self.word()?;
+                    self.expect_punct(';')?;
+                }
+                "interface" => interfaces.push(self.interface(&attributes)?),
+                "coclass" => coclasses.push(self.coclass(&attributes)?),
+                other => return self.error(format!("unsupported declaration `{other}`")),
+            }
+        }
+        self.eat_punct(';');
+
+        Ok(Library {
+            name,
+            uuid,
+            interfaces,
+            coclasses,
+        })
+    }
+
+    /// `interface Name`の後に本体（`: Base {`）が続くかどうか
+    fn is_lookahead_body(&self) -> bool {
+        matches!(
+            self.tokens.get(self.pos + 1),
+            Some((Token::Punct(':' | '{'), _))
+        )
+    }
+
+    fn interface(&mut self, attributes: &[Attribute]) -> Result<Interface, ParseError> {
+        let uuid = self.uuid(attributes)?;
+        let name = self.word()?;
+        self.expect_punct(':')?;
+        let base = self.word()?;
+        self.expect_punct('{')?;
+
+        let mut methods = Vec::new();
+        while !self.eat_punct('}') {
+            methods.push(self.method()?);
+        }
+        self.expect_punct(';')?;
+
+        Ok(Interface {
+            name,
+            uuid,
+            base,
+            methods,
+        })
+    }
+
+    fn method(&mut self) -> Result<Method, ParseError> {
+        let attributes = self.attributes()?;
+        let kind = if has(&attributes, "propget") {
+            MethodKind::PropGet
+        } else if has(&attributes, "propput") {
+            MethodKind::PropPut
+        } else if has(&attributes, "propputref") {
+            return self.error("propputref is not supported");
+        } else {
+            MethodKind::Method
+        };
+
+        self.expect_word("HRESULT")?;
+        let name = self.word()?;
+        self.expect_punct('(')?;
+
+        let mut params = Vec::new();
+        let mut retval = None;
+        if !self.eat_punct(')') {
+            loop {
+                let attributes = self.attributes()?;
+                let (words, pointers) = self.declarator()?;
+                if words == ["void"] && pointers == 0 {
+                    self.expect_punct(')')?;
+                    break;
+                }
+                let Some((param_name, type_words)) = words.split_last() else {
+                    return self.error("expected a parameter");
+                };
+
+                if has(&attributes, "retval") {
+                    if !has(&attributes, "out") || pointers == 0 {
+                        return self.error("retval must be an [out] pointer");
+                    }
+                    retval = Some(self.ty(type_words, pointers - 1)?);
+                } else if has(&attributes, "in") && !has(&attributes, "out") {
+                    params.push(Param {
+                        name: param_name.clone(),
+                        ty: self.ty(type_words, pointers)?,
+                    });
+                } else {
+                    return self.error(format!("unsupported parameter `{param_name}`"));
+                }
+
+                if self.eat_punct(')') {
+                    break;
+                }
+                self.expect_punct(',')?;
+            }
+        }
+        self.expect_punct(';')?;
+
+        Ok(Method {
+            name,
+            kind,
+            params,
+            retval,
+        })
+    }
+
+    /// 引数の型と名前（`unsigned long* pRetVal`）を語とポインタの数として読みます。
+    fn declarator(&mut self) -> Result<(Vec<String>, usize), ParseError> {
+        let mut words = Vec::new();
+        let mut pointers = 0;
+        loop {
+            match self.peek() {
+                Some(Token::Word(_)) => words.push(self.word()?),
+                Some(Token::Punct('*')) => {
+                    self.pos += 1;
+                    pointers += 1;
+                }
+                _ => return Ok((words, pointers)),
+            }
+        }
+    }
+
+    fn ty(&self, words: &[String], pointers: usize) -> Result<Type, ParseError> {
+        let words: Vec<&str> = words.iter().map(String::as_str).collect();
+        match (words.as_slice(), pointers) {
+            (["VARIANT_BOOL"], 0) => Ok(Type::Bool),
+            (["long"], 0) => Ok(Type::I32),
+            (["unsigned", "long"], 0) => Ok(Type::U32),
+            (["double"], 0) => Ok(Type::F64),
+            (["BSTR"], 0) => Ok(Type::Bstr),
+            ([name], 1) if name.starts_with('I') => Ok(Type::Interface((*name).to_string())),
+            _ => self.error(format!(
+                "unsupported type `{}{}`",
+                words.join(" "),
+                "*".repeat(pointers)
+            )),
+        }
+    }
+
+    fn coclass(&mut self, attributes: &[Attribute]) -> Result<CoClass, ParseError> {
+        let uuid = self.uuid(attributes)?;
+        let name = self.word()?;
+        self.expect_punct('{')?;
+        while !self.eat_punct('}') {
+            self.attributes()?;
+            self.expect_word("interface")?;
+            self.word()?;
+            self.expect_punct(';')?;
+        }
+        self.expect_punct(';')?;
+        Ok(CoClass { name, uuid })
+    }
+}
+
+/// `A409140F-B7A5-48EF-893E-8B971420E05A`形式のGUIDを数値に変換します。
+fn parse_uuid(s: &str) -> Option<u128> {
+    let groups: Vec<&str> = s.split('-').collect();
+    let lengths: Vec<usize> = groups.iter().map(|group| group.len()).collect();
+    if lengths != [8, 4, 4, 4, 12] {
+        return None;
+    }
+    u128::from_str_radix(&groups.concat(), 16).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &str = r#"
+        // comment
+        import "objidl.idl";
+
+        [uuid(7E3B8901-0A65-44A0-9A9A-5F9F822D0716), version(3.0),]
+        library Sample
+        {
+            interface IFoo;
+
+            [odl, uuid(A409140F-B7A5-48EF-893E-8B971420E05A), dual,
+             custom(0F21F359-AB84-41E8-9A78-36D110E6D2F9, "Sample.IFoo")]
+            interface IFoo : IDispatch {
+                [id(0x60020000), propget]
+                HRESULT Volume([out, retval] unsigned long* pRetVal);
+                [id(0x60020000), propput]
+                HRESULT Volume([in] unsigned long pRetVal);
+                /* block
+                   comment */
+                [id(0x60020001)]
+                HRESULT Speak([in] BSTR text, [out, retval] IFoo** pRetVal);
+                [id(0x60020002)]
+                HRESULT CloseHost([in] long mode);
+            };
+
+            [uuid(9FF8647A-C6C9-49E6-88B9-1FC19EC13C13), noncreatable]
+            coclass Foo {
+                [default] interface IFoo;
+            };
+        };
+    "#;
+
+    #[test]
+    fn parses_interfaces_in_vtable_order() {
+        let library = parse(SAMPLE).unwrap();
+        assert_eq!(library.name, "Sample");
+        assert_eq!(library.uuid, 0x7e3b8901_0a65_44a0_9a9a_5f9f822d0716);
+
+        let foo = library.interface("IFoo").unwrap();
+        assert_eq!(foo.uuid, 0xa409140f_b7a5_48ef_893e_8b971420e05a);
+        assert_eq!(foo.base, "IDispatch");
+        let names: Vec<String> = foo.methods.iter().map(Method::rust_name).collect();
+        assert_eq!(names, ["Volume", "SetVolume", "Speak", "CloseHost"]);
+
+        assert_eq!(foo.methods[0].retval, Some(Type::U32));
+        assert_eq!(
+            foo.methods[2].params,
+            [Param {
+                name: "text".to_string(),
+                ty: Type::Bstr
+            }]
+        );
+        assert_eq!(
+            foo.methods[2].retval,
+            Some(Type::Interface("IFoo".to_string()))
+        );
+        assert_eq!(foo.methods[3].retval, None);
+
+        assert_eq!(
+            library.coclasses,
+            [CoClass {
+                name: "Foo".to_string(),
+                uuid: 0x9ff8647a_c6c9_49e6_88b9_1fc19ec13c13
+            }]
+        );
+    }
+
+    #[test]
+    fn reports_unsupported_types_with_line() {
+        let source = "[uuid(7E3B8901-0A65-44A0-9A9A-5F9F822D0716)]\nlibrary L {\n\
+            [uuid(A409140F-B7A5-48EF-893E-8B971420E05A)]\ninterface IFoo : IDispatch {\n\
+            HRESULT Get([out, retval] SAFEARRAY(BSTR)* pRetVal);\n};\n};";
+        let error = parse(source).unwrap_err();
+        assert_eq!(error.line, 5);
+    }
+
+    #[test]
+    fn rejects_invalid_uuid() {
+        assert_eq!(parse_uuid("A409140F-B7A5-48EF-893E"), None);
+        assert!(parse("[uuid(1234)] library L { };").is_err());
+    }
+}
@@ -0,0 +1,254 @@
+//! COM配列インターフェースの安全なラッパー
+//!
+//! `IStringArray2`・`IPhonemeDataArray2`・`ITalkerComponentArray2`はいずれも
+//! `Length`と`At(index)`で要素を取得します。[`ComArray`]はこれらを共通に扱い、
+//! [`ComArrayIter`]で要素を順に取り出します。文字列と音素データは所有する型に変換して返します。
+//! `IDispatch`による遅延バインディングの配列のように、`windows_core::Error`以外のエラーを返す
+//! 配列にも実装できます。
+
+use std::iter::FusedIterator;
+
+use windows::Win32::Foundation::E_UNEXPECTED;
+use windows_core::{Error, Result};
+
+use crate::{IPhonemeDataArray2, IStringArray2, ITalkerComponent2, ITalkerComponentArray2};
+
+/// 音素データ
+#[derive(Debug, Clone, PartialEq)]
+pub struct Phoneme {
+    /// 音素
+    pub phoneme: String,
+
+    /// 開始時間（秒）
+    pub start_time: f64,
+
+    /// 終了時間（秒）
+    pub end_time: f64,
+}
+
+/// `Length`と`At(index)`を持つCOM配列
+pub trait ComArray {
+    /// 要素の型
+    type Item;
+
+    /// エラーの型
+    type Error;
+
+    /// 要素数を取得します。
+    fn len(&self) -> std::result::Result<usize, Self::Error>;
+
+    /// 指定した位置の要素を取得します。
+    fn at(&self, index: usize) -> std::result::Result<Self::Item, Self::Error>;
+
+    /// 配列が空かどうかを取得します。
+    fn is_empty(&self) -> std::result::Result<bool, Self::Error> {
+        Ok(self.len()? == 0)
+    }
+
+    /// 要素を順に取り出すイテレータを作成します。
+    ///
+    /// 要素数は作成時に1度だけ取得します。
+    fn iter(&self) -> std::result::Result<ComArrayIter<'_, Self>, Self::Error>
+    where
+        Self: Sized,
+    {
+        Ok(ComArrayIter {
+            array: self,
+            index: 0,
+            len: self.len()?,
+        })
+    }
+
+    /// すべての要素を取得します。
+    ///
+    /// 途中で失敗した場合は最初のエラーを返します。
+    fn to_vec(&self) -> std::result::Result<Vec<Self::Item>, Self::Error>
+    where
+        Self: Sized,
+    {
+        self.iter()?.collect()
+    }
+}
+
+/// [`ComArray`]の要素を順に取り出すイテレータ
+///
+/// 要素の取得に失敗した場合はそのエラーを返し、以降は`None`を返します。
+/// 途中で終わる可能性があるため、`size_hint`の上限は残りの要素数、下限は1（残りがない場合は0）です。
+#[derive(Debug)]
+pub struct ComArrayIter<'a, A> {
+    array: &'a A,
+    index: usize,
+    len: usize,
+}
+
+impl<A: ComArray> Iterator for ComArrayIter<'_, A> {
+    type Item = std::result::Result<A::Item, A::Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.index >= self.len {
+            return None;
+        }
+        let item = self.array.at(self.index);
+        self.index = if item.is_ok() {
+            self.index + 1
+        } else {
+            self.len
+        };
+        Some(item)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.len - self.index;
+        (remaining.min(1), Some(remaining))
+    }
+}
+
+impl<A: ComArray> FusedIterator for ComArrayIter<'_, A> {}
+
+/// `Length`の値を要素数に変換します。
+fn checked_len(len: i32) -> Result<usize> {
+    usize::try_from(len).map_err(|_| Error::new(E_UNEXPECTED, format!("negative length {len}")))
+}
+
+/// 位置を`At`の引数に変換します。
+fn checked_index(index: usize) -> Result<i32> {
+    i32::try_from(index)
+        .map_err(|_| Error::new(E_UNEXPECTED, format!("index {index} out of range")))
+}
+
+/// 配列インターフェースに[`ComArray`]を実装します。
+///
+/// `$convert`は`At`が返した値を要素の型に変換します。
+macro_rules! impl_com_array {
+    ($array:ty => $item:ty, |$value:ident| $convert:expr) => {
+        impl ComArray for $array {
+            type Item = $item;
+            type Error = Error;
+
+            fn len(&self) -> Result<usize> {
+                checked_len(unsafe { self.Length() }?)
+            }
+
+            fn at(&self, index: usize) -> Result<Self::Item> {
+                let $value = unsafe { self.At(checked_index(index)?) }?;
+                $convert
+            }
+        }
+    };
+}
+
+/// 音素データのインターフェースを[`Phoneme`]に変換します。
+macro_rules! read_phoneme {
+    ($data:expr) => {{
+        let data = $data;
+        unsafe {
+            Ok(Phoneme {
+                phoneme: data.Phoneme()?.to_string(),
+                start_time: data.StartTime()?,
+                end_time: data.EndTime()?,
+            })
+        }
+    }};
+}
+
+impl_com_array!(IStringArray2 => String, |value| Ok(value.to_string()));
+impl_com_array!(IPhonemeDataArray2 => Phoneme, |value| read_phoneme!(value));
+impl_com_array!(ITalkerComponentArray2 => ITalkerComponent2, |value| Ok(value));
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 指定した位置で失敗する配列
+    struct FakeArray {
+        items: Vec<u32>,
+        fail_at: Option<usize>,
+    }
+
+    impl ComArray for FakeArray {
+        type Item = u32;
+        type Error = Error;
+
+        fn len(&self) -> Result<usize> {
+            Ok(self.items.len())
+        }
+
+        fn at(&self, index: usize) -> Result<u32> {
+            if self.fail_at == Some(index) {
+                return Err(Error::from(E_UNEXPECTED));
+            }
+            Ok(self.items[index])
+        }
+    }
+
+    #[test]
+    fn iterates_all_items() {
+        let array = FakeArray {
+            items: vec![1, 2, 3],
+            fail_at: None,
+        };
+        let mut iter = array.iter().unwrap();
+        assert_eq!(iter.size_hint(), (1, Some(3)));
+        iter.next();
+        assert_eq!(iter.size_hint(), (1, Some(2)));
+        assert_eq!(array.to_vec().unwrap(), [1, 2, 3]);
+        assert!(!array.is_empty().unwrap());
+    }
+
+    #[test]
+    fn stops_after_error() {
+        let array = FakeArray {
+            items: vec![1, 2, 3],
+            fail_at: Some(1),
+        };
+        let mut iter = array.iter().unwrap();
+        assert_eq!(iter.next().unwrap().unwrap(), 1);
+        assert_eq!(iter.next().unwrap().unwrap_err().code(), E_UNEXPECTED);
+        // エラーの後は残りの要素を返さないため、残りは0になる
+        assert_eq!(iter.size_hint(), (0, Some(0)));
+        assert!(iter.next().is_none());
+
+        // 上限は実際に返される要素数（2）を下回らない
+        let iter = array.iter().unwrap();
+        let (lower, upper) = iter.size_hint();
+        let count = iter.count();
+        assert!(lower <= count && count <= upper.unwrap());
+
+        assert_eq!(array.to_vec().unwrap_err().code(), E_UNEXPECTED);
+    }
+
+    /// `windows_core::Error`以外のエラーを返す配列
+    struct Lookup(Vec<Option<&'static str>>);
+
+    impl ComArray for Lookup {
+        type Item = &'static str;
+        type Error = String;
+
+        fn len(&self) -> std::result::Result<usize, String> {
+            Ok(self.0.len())
+        }
+
+        fn at(&self, index: usize) -> std::result::Result<&'static str, String> {
+            self.0[index].ok_or_else(|| format!("missing {index}"))
+        }
+    }
+
+    #[test]
+    fn supports_other_error_types() {
+        assert_eq!(
+            Lookup(vec![Some("a"), Some("b")]).to_vec().unwrap(),
+            ["a", "b"]
+        );
+        assert_eq!(
+            Lookup(vec![Some("a"), None]).to_vec().unwrap_err(),
+            "missing 1"
+        );
+    }
+
+    #[test]
+    fn rejects_negative_length() {
+        assert_eq!(checked_len(2).unwrap(), 2);
+        assert_eq!(checked_len(-1).unwrap_err().code(), E_UNEXPECTED);
+        assert!(checked_index(usize::MAX).is_err());
+    }
+}
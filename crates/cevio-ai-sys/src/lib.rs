@@ -2,6 +2,8 @@
 //!
 //! このクレートは、日本語音声合成ソフトウェア CeVIO AI 用の生の Windows COM インターフェースバインディングを提供します。
 //! これらのバインディングは CeVIO.Talk.RemoteService2.winmd メタデータファイルから自動生成されます。
+//! `idl` フィーチャーを有効にすると、winmd の代わりに .metadata 内の IDL を
+//! ビルド時に解釈して生成します（MSBuild は不要です）。
 //!
 //! # プラットフォームサポート
 //!
@@ -9,12 +11,13 @@
 //!
 //! # 使用方法
 //!
-//! このクレートは生の COM インターフェースを公開します。より高レベルで安全な API については、代わりに `cevio-ai` クレートの使用を検討してください。
+//! このクレートは生の COM インターフェースを公開します。配列インターフェースは [`ComArray`] で安全に走査できます。より高レベルで安全な API については、代わりに `cevio-ai` クレートの使用を検討してください。
 //!
 //! # 安全性について
 //!
-//! このクレートが公開する COM インターフェースは本質的に unsafe です。`unsafe_send` フィーチャが有効な場合、
-//! 特定のインターフェースが `Mutex` でラップされることを前提として `Send` が実装されます。
+//! このクレートが公開する COM インターフェースは本質的に unsafe であり、`Send` ではありません。
+//! インターフェースは作成したスレッドでのみ使用してください。`cevio-ai` クレートは
+//! 専用のワーカースレッドにすべてのインターフェースを所有させることで、この制約を満たしています。
 
 mod bindings {
     #![allow(
@@ -25,9 +28,15 @@ mod bindings {
         clippy::all
     )]
     
+    #[cfg(not(feature = "idl"))]
     include!(concat!(env!("OUT_DIR"), "/bindings.rs"));
+    #[cfg(feature = "idl")]
+    include!(concat!(env!("OUT_DIR"), "/bindings_idl.rs"));
 }
 
+mod array;
+
+pub use array::*;
 pub use bindings::CeVIO::Talk::RemoteService2::{
     IPhonemeData2, IPhonemeDataArray2, IServiceControl2, IServiceControl2V40,
     IServiceControl2V40Part, ISpeakingState2, IStringArray2, ITalker2, ITalker2V40,
@@ -36,12 +45,158 @@ pub use bindings::CeVIO::Talk::RemoteService2::{
     TalkerComponent2, TalkerComponentCollection2,
 };
 
-// ITalker2V40及びIServiceControl2V40がMutexでラップされることを前提としてSendを実装する
-#[cfg(feature = "unsafe_send")]
-use std::marker::Send;
+#[cfg(test)]
+#[path = "../build/codegen.rs"]
+mod codegen;
+#[cfg(test)]
+#[path = "../build/idl.rs"]
+mod idl;
+
+/// IDLから生成したバインディングがwinmdから生成したものと一致することを確認します。
+#[cfg(all(test, not(feature = "idl")))]
+mod tests {
+    use windows_core::{Interface, GUID};
+
+    use super::{
+        codegen,
+        idl::{parse, Library},
+    };
+
+    const IDL: &str = include_str!("../.metadata/CeVIO.Talk.RemoteService2.IDL");
+    const WINMD_BINDINGS: &str = include_str!(concat!(env!("OUT_DIR"), "/bindings.rs"));
+
+    /// インターフェース名とwinmdのIID
+    macro_rules! interfaces {
+        ($($name:ident),* $(,)?) => {
+            [$((stringify!($name), <super::$name as Interface>::IID)),*]
+        };
+    }
+
+    /// コクラス名とwinmdのCLSID
+    macro_rules! coclasses {
+        ($($name:ident),* $(,)?) => {
+            [$((stringify!($name), super::$name)),*]
+        };
+    }
+
+    fn library() -> Library {
+        parse(IDL).unwrap()
+    }
+
+    fn idl_bindings() -> String {
+        codegen::generate(&library(), "CeVIO.Talk.RemoteService2").unwrap()
+    }
+
+    /// 比較のために空白と末尾のカンマを取り除きます。
+    fn normalize(code: &str) -> String {
+        let code: String = code.chars().filter(|c| !c.is_whitespace()).collect();
+        code.replace(",)", ")").trim_end_matches(',').to_string()
+    }
 
-#[cfg(feature = "unsafe_send")]
-unsafe impl Send for IServiceControl2V40 {}
+    /// vtableのフィールド名と型
+    fn vtable(bindings: &str, name: &str) -> Vec<(String, String)> {
+        let start = bindings
+            .find(&format!("pub struct {name}_Vtbl {{"))
+            .unwrap_or_else(|| panic!("{name}_Vtbl not found"));
+        let body = &bindings[start..];
+        let body = &body[body.find('{').unwrap() + 1..body.find('}').unwrap()];
+        body.split("pub ")
+            .filter_map(|field| field.split_once(':'))
+            .map(|(name, ty)| (name.trim().to_string(), normalize(ty)))
+            .collect()
+    }
 
-#[cfg(feature = "unsafe_send")]
-unsafe impl Send for ITalker2V40 {}
+    /// インターフェースのメソッドのシグネチャ（本体を除く）
+    fn signature(bindings: &str, interface: &str, method: &str) -> String {
+        let start = bindings
+            .find(&format!("impl {interface} {{"))
+            .unwrap_or_else(|| panic!("impl {interface} not found"));
+        let body = &bindings[start..];
+        let body = &body[body
+            .find(&format!("pub unsafe fn {method}("))
+            .unwrap_or_else(|| panic!("{interface}::{method} not found"))..];
+        normalize(&body[..body.find('{').unwrap()])
+    }
+
+    #[test]
+    fn interface_ids_match_winmd() {
+        let library = library();
+        let interfaces = interfaces!(
+            IPhonemeData2,
+            IPhonemeDataArray2,
+            IServiceControl2,
+            IServiceControl2V40,
+            IServiceControl2V40Part,
+            ISpeakingState2,
+            IStringArray2,
+            ITalker2,
+            ITalker2V40,
+            ITalker2V40Part,
+            ITalkerComponent2,
+            ITalkerComponentArray2,
+        );
+        assert_eq!(library.interfaces.len(), interfaces.len());
+
+        for (name, winmd) in interfaces {
+            let parsed = library.interface(name).unwrap();
+            assert_eq!(GUID::from_u128(parsed.uuid), winmd, "{name}");
+        }
+    }
+
+    #[test]
+    fn vtables_match_winmd() {
+        let idl = idl_bindings();
+        for interface in library().interfaces {
+            let name = &interface.name;
+            let expected = vtable(WINMD_BINDINGS, name);
+            assert_eq!(vtable(&idl, name), expected, "{name}");
+
+            let methods: Vec<String> = interface.methods.iter().map(|m| m.rust_name()).collect();
+            let names: Vec<String> = expected
+                .into_iter()
+                .map(|(name, _)| name)
+                .filter(|name| name != "base__")
+                .collect();
+            assert_eq!(methods, names, "{name}");
+        }
+    }
+
+    #[test]
+    fn method_signatures_match_winmd() {
+        let idl = idl_bindings();
+        for interface in library().interfaces {
+            for method in &interface.methods {
+                let name = method.rust_name();
+                assert_eq!(
+                    signature(&idl, &interface.name, &name),
+                    signature(WINMD_BINDINGS, &interface.name, &name),
+                    "{}::{name}",
+                    interface.name
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn class_ids_match_winmd() {
+        let library = library();
+        let coclasses = coclasses!(
+            PhonemeData2,
+            PhonemeDataArray2,
+            ServiceControl2,
+            ServiceControl2V40,
+            SpeakingState2,
+            StringArray2,
+            Talker2,
+            Talker2V40,
+            TalkerComponent2,
+            TalkerComponentCollection2,
+        );
+        assert_eq!(library.coclasses.len(), coclasses.len());
+
+        for (name, winmd) in coclasses {
+            let parsed = library.coclasses.iter().find(|c| c.name == name).unwrap();
+            assert_eq!(GUID::from_u128(parsed.uuid), winmd, "{name}");
+        }
+    }
+}
@@ -0,0 +1,407 @@
+//! 音素データの分析
+//!
+//! このモジュールは、`phonemes()`で取得した音素データから話す速さや間の長さを求める型と、
+//! 目標の話す速さになる`Speed`を探す`calibrate_speed`関数を提供します。
+//!
+//! | 値 | 内容 |
+//! |---|---|
+//! | 発話速度（`speech_rate`） | 発話区間（前後の無音を除く）1秒あたりのモーラ数 |
+//! | 調音速度（`articulation_rate`） | 有音区間（間を除く）1秒あたりのモーラ数 |
+//!
+//! ```rust,no_run
+//! use cevio_ai::*;
+//!
+//! fn main() -> Result<()> {
+//!     let cevio = CevioAI::new()?;
+//!     let phonemes = cevio.phonemes("こんにちは、今日はいい天気ですね。")?;
+//!
+//!     let stats = TimelineStats::from_phonemes(&phonemes);
+//!     println!("{:.1} mora/s, 間 {:.2}s", stats.speech_rate(), stats.pause_time);
+//!
+//!     // 1秒あたり8モーラで話す速さを探す
+//!     let calibration = calibrate_speed(&cevio, "こんにちは、今日はいい天気ですね。", 8.0)?;
+//!     println!("Speed = {}", calibration.speed);
+//!     Ok(())
+//! }
+//! ```
+
+use std::collections::BTreeMap;
+
+use crate::{
+    backend::Backend,
+    cevio::{Cast, PhonemeData},
+    error::{CevioAIError, Result},
+    parameter::Speed,
+    phoneme::MoraSegmenter,
+    speed_search::{self, Bracket},
+};
+
+/// 音素データの時間に関する統計
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct TimelineStats {
+    /// 全体の長さ（秒）
+    pub duration: f64,
+
+    /// 発話区間（最初の有音の開始から最後の有音の終了まで）の長さ（秒）
+    pub speaking_time: f64,
+
+    /// 有音の音素の長さの合計（秒）
+    pub voiced_time: f64,
+
+    /// 発話区間内の無音（`pau`・`sil`）の長さの合計（秒）
+    pub pause_time: f64,
+
+    /// 発話区間内の最も長い間（秒）
+    pub longest_pause: f64,
+
+    /// 発話区間内の間の数
+    pub pause_count: usize,
+
+    /// モーラ数
+    pub mora_count: usize,
+}
+
+impl TimelineStats {
+    /// 音素データから統計を計算します。
+    #[must_use]
+    pub fn from_phonemes(phonemes: &[PhonemeData]) -> Self {
+        let Some((first, last)) = phonemes.first().zip(phonemes.last()) else {
+            return Self::default();
+        };
+
+        let mut stats = Self {
+            duration: last.end_time() - first.start_time(),
+            mora_count: MoraSegmenter::new().segment(phonemes).len(),
+            ..Self::default()
+        };
+
+        let is_silent = |phoneme: &PhonemeData| phoneme.to_phoneme().is_silent();
+        let Some(start) = phonemes.iter().position(|phoneme| !is_silent(phoneme)) else {
+            return stats;
+        };
+        let end = phonemes
+            .iter()
+            .rposition(|phoneme| !is_silent(phoneme))
+            .unwrap_or(start);
+        let speaking = &phonemes[start..=end];
+        stats.speaking_time = speaking[speaking.len() - 1].end_time() - speaking[0].start_time();
+
+        // 連続する無音は1つの間として数える
+        let mut pause = 0.0;
+        for phoneme in speaking {
+            let length = phoneme.end_time() - phoneme.start_time();
+            if is_silent(phoneme) {
+                pause += length;
+                continue;
+            }
+            if pause > 0.0 {
+                stats.pause_count += 1;
+                stats.pause_time += pause;
+                stats.longest_pause = stats.longest_pause.max(pause);
+                pause = 0.0;
+            }
+            stats.voiced_time += length;
+        }
+
+        stats
+    }
+
+    /// 発話速度（発話区間1秒あたりのモーラ数）を取得します。
+    ///
+    /// 発話区間がない場合は0を返します。
+    #[must_use]
+    pub fn speech_rate(&self) -> f64 {
+        rate(self.mora_count, self.speaking_time)
+    }
+
+    /// 調音速度（有音区間1秒あたりのモーラ数）を取得します。
+    ///
+    /// 有音区間がない場合は0を返します。
+    #[must_use]
+    pub fn articulation_rate(&self) -> f64 {
+        rate(self.mora_count, self.voiced_time)
+    }
+}
+
+fn rate(count: usize, seconds: f64) -> f64 {
+    if seconds > 0.0 {
+        count as f64 / seconds
+    } else {
+        0.0
+    }
+}
+
+/// 音素ごとの長さの統計
+#[derive(Debug, Clone, PartialEq)]
+pub struct DurationStats {
+    /// 出現回数
+    pub count: usize,
+
+    /// 長さの平均（秒）
+    pub mean: f64,
+
+    /// 最も短い長さ（秒）
+    pub min: f64,
+
+    /// 最も長い長さ（秒）
+    pub max: f64,
+
+    /// ヒストグラムの階級の幅（秒）
+    pub bin_width: f64,
+
+    /// ヒストグラムの階級の番号と度数（番号`i`は`i * bin_width`以上`(i + 1) * bin_width`未満）
+    ///
+    /// 度数が0の階級は含みません。
+    pub histogram: BTreeMap<usize, usize>,
+}
+
+impl DurationStats {
+    /// 度数が1以上の各階級の下限（秒）と度数を、短い順に取得します。
+    pub fn bins(&self) -> impl Iterator<Item = (f64, usize)> + '_ {
+        self.histogram
+            .iter()
+            .map(|(&index, &count)| (index as f64 * self.bin_width, count))
+    }
+}
+
+/// 音素ごとの長さの統計とヒストグラムを計算します。
+///
+/// # Arguments
+///
+/// * `phonemes` - 音素データ
+/// * `bin_width` - ヒストグラムの階級の幅（秒）
+///
+/// # Errors
+///
+/// `bin_width`が正の数でない場合は`InvalidParameter`エラーを返します。
+pub fn phoneme_durations(
+    phonemes: &[PhonemeData],
+    bin_width: f64,
+) -> Result<BTreeMap<String, DurationStats>> {
+    if !(bin_width > 0.0 && bin_width.is_finite()) {
+        return Err(CevioAIError::InvalidParameter(format!(
+            "Bin width must be positive, got {bin_width}"
+        )));
+    }
+
+    let mut durations: BTreeMap<String, DurationStats> = BTreeMap::new();
+    for phoneme in phonemes {
+        let length = (phoneme.end_time() - phoneme.start_time()).max(0.0);
+        // 境界上の長さが浮動小数点の誤差で1つ下の階級に入らないようにする。
+        // 非常に長い区間はusize::MAXの階級にまとめられる
+        let bin = (length / bin_width + 1e-9).floor() as usize;
+
+        let stats = durations
+            .entry(phoneme.phoneme().to_string())
+            .or_insert_with(|| DurationStats {
+                count: 0,
+                mean: 0.0,
+                min: f64::INFINITY,
+                max: 0.0,
+                bin_width,
+                histogram: BTreeMap::new(),
+            });
+        stats.count += 1;
+        stats.mean += (length - stats.mean) / stats.count as f64;
+        stats.min = stats.min.min(length);
+        stats.max = stats.max.max(length);
+        *stats.histogram.entry(bin).or_insert(0) += 1;
+    }
+
+    Ok(durations)
+}
+
+/// `calibrate_speed`の結果
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SpeedCalibration {
+    /// 目標に最も近い話す速さ
+    pub speed: Speed,
+
+    /// その話す速さでの発話速度（1秒あたりのモーラ数）
+    pub rate: f64,
+}
+
+/// 目標の発話速度に最も近くなる話す速さ（`Speed`）を二分探索で求めます。
+///
+/// 現在のキャストで`text`の音素データを取得し、発話速度（`TimelineStats::speech_rate`）を
+/// 目標と比較します。探索後、話す速さは元の値に戻されます。
+///
+/// # Arguments
+///
+/// * `backend` - 使用するバックエンド
+/// * `text` - 調整に使用するセリフ
+/// * `target_mora_per_sec` - 目標の発話速度（1秒あたりのモーラ数）
+///
+/// # Errors
+///
+/// 目標が正の数でない場合や、セリフにモーラが含まれない場合は`InvalidParameter`エラーを返します。
+pub fn calibrate_speed<B: Backend + ?Sized>(
+    backend: &B,
+    text: &str,
+    target_mora_per_sec: f64,
+) -> Result<SpeedCalibration> {
+    speed_search::preserving_speed(backend, |_| {
+        search_speed(target_mora_per_sec, |speed| {
+            backend.apply_cast(&Cast {
+                speed: Some(speed),
+                ..Cast::default()
+            })?;
+            Ok(TimelineStats::from_phonemes(&backend.phonemes(text)?).speech_rate())
+        })
+    })
+}
+
+/// 話す速さに対して単調増加する発話速度から、目標に最も近い話す速さを探します。
+fn search_speed(
+    target: f64,
+    mut rate_at: impl FnMut(Speed) -> Result<f64>,
+) -> Result<SpeedCalibration> {
+    if !(target > 0.0 && target.is_finite()) {
+        return Err(CevioAIError::InvalidParameter(format!(
+            "Target rate must be positive, got {target}"
+        )));
+    }
+
+    let slowest = Speed::new(Speed::MIN_VALUE).unwrap_or_default();
+    let bracket = speed_search::search(
+        slowest,
+        |speed| {
+            let rate = rate_at(speed)?;
+            if rate <= 0.0 {
+                return Err(CevioAIError::InvalidParameter(
+                    "Calibration text has no morae".to_string(),
+                ));
+            }
+            Ok(rate)
+        },
+        |&rate| rate >= target,
+    )?;
+
+    let (speed, rate) = match bracket {
+        Bracket::Start(measured) | Bracket::Exhausted(measured) => measured,
+        Bracket::Between { below, above } => {
+            if (above.1 - target).abs() < (below.1 - target).abs() {
+                above
+            } else {
+                below
+            }
+        }
+    };
+    Ok(SpeedCalibration { speed, rate })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 「こんにちは、ふゆ」
+    fn greeting() -> Vec<PhonemeData> {
+        PhonemeData::sequence(&[
+            ("sil", 0.1),
+            ("k", 0.05),
+            ("o", 0.1),
+            ("N", 0.08),
+            ("n", 0.04),
+            ("i", 0.08),
+            ("ch", 0.06),
+            ("i", 0.07),
+            ("w", 0.05),
+            ("a", 0.12),
+            ("pau", 0.2),
+            ("f", 0.06),
+            ("u", 0.1),
+            ("y", 0.04),
+            ("u", 0.15),
+            ("sil", 0.1),
+        ])
+    }
+
+    #[test]
+    fn timeline_stats() {
+        let stats = TimelineStats::from_phonemes(&greeting());
+
+        assert!((stats.duration - 1.4).abs() < 1e-9);
+        assert!((stats.speaking_time - 1.2).abs() < 1e-9);
+        assert!((stats.voiced_time - 1.0).abs() < 1e-9);
+        assert!((stats.pause_time - 0.2).abs() < 1e-9);
+        assert!((stats.longest_pause - 0.2).abs() < 1e-9);
+        assert_eq!(stats.pause_count, 1);
+        assert_eq!(stats.mora_count, 7);
+        assert!((stats.speech_rate() - 7.0 / 1.2).abs() < 1e-9);
+        assert!((stats.articulation_rate() - 7.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn timeline_stats_without_speech() {
+        assert_eq!(TimelineStats::from_phonemes(&[]), TimelineStats::default());
+
+        let stats = TimelineStats::from_phonemes(&PhonemeData::sequence(&[("sil", 0.5)]));
+        assert!((stats.duration - 0.5).abs() < 1e-9);
+        assert_eq!(stats.speech_rate(), 0.0);
+        assert_eq!(stats.pause_count, 0);
+    }
+
+    #[test]
+    fn duration_histograms() {
+        let durations = phoneme_durations(&greeting(), 0.05).unwrap();
+
+        let i = &durations["i"];
+        assert_eq!(i.count, 2);
+        assert!((i.mean - 0.075).abs() < 1e-9);
+        assert!((i.min - 0.07).abs() < 1e-9);
+        assert!((i.max - 0.08).abs() < 1e-9);
+        assert_eq!(i.histogram, BTreeMap::from([(1, 2)]));
+
+        let u: Vec<_> = durations["u"].bins().collect();
+        assert_eq!(u.len(), 2);
+        assert!((u[1].0 - 0.15).abs() < 1e-9);
+        assert_eq!(u[1].1, 1);
+        assert_eq!(durations["sil"].count, 2);
+        assert!(phoneme_durations(&greeting(), 0.0).is_err());
+    }
+
+    #[test]
+    fn long_intervals_do_not_allocate_empty_bins() {
+        let phonemes = [
+            PhonemeData::new("a".to_string(), 0.0, 0.1),
+            PhonemeData::new("a".to_string(), 0.0, 3600.0),
+            PhonemeData::new("a".to_string(), 0.0, f64::MAX),
+        ];
+        let durations = phoneme_durations(&phonemes, 1e-9).unwrap();
+
+        let a = &durations["a"];
+        assert_eq!(a.count, 3);
+        assert_eq!(a.histogram.len(), 3);
+        assert_eq!(a.histogram.get(&usize::MAX), Some(&1));
+    }
+
+    #[test]
+    fn searches_speed() {
+        // 話す速さ50で10モーラ/秒、100で20モーラ/秒
+        let rate = |speed: Speed| Ok(10.0 * 2f64.powf((f64::from(speed.get()) - 50.0) / 50.0));
+
+        let calibration = search_speed(12.0, rate).unwrap();
+        assert_eq!(calibration.speed.get(), 63);
+
+        assert_eq!(search_speed(1.0, rate).unwrap().speed.get(), 0);
+        assert_eq!(search_speed(100.0, rate).unwrap().speed.get(), 100);
+        assert!(search_speed(0.0, rate).is_err());
+        assert!(search_speed(10.0, |_| Ok(0.0)).is_err());
+    }
+
+    #[cfg(feature = "fake")]
+    #[test]
+    fn calibrates_fake_backend() {
+        use crate::{cevio::CastBuilder, fake::FakeBackend};
+
+        let backend = FakeBackend::new();
+        backend
+            .apply_cast(&CastBuilder::default().with_defaults().build().unwrap())
+            .unwrap();
+
+        let calibration = calibrate_speed(&backend, "こんにちは", 12.0).unwrap();
+
+        assert_eq!(calibration.speed.get(), 63);
+        assert_eq!(backend.current_cast().unwrap().speed.unwrap().get(), 50);
+    }
+}
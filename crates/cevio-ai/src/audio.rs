@@ -0,0 +1,264 @@
+//! 音声データ関連の型定義
+//!
+//! このモジュールは、CeVIO AIが出力するWAVデータをメモリ上で扱うための型を提供します。
+//! CeVIO AIの出力形式（48kHz・16bit・モノラル）を含む、リニアPCM 16bitのWAVに対応しています。
+
+use std::path::Path;
+
+use crate::error::{CevioAIError, Result};
+
+/// PCM音声バッファ
+///
+/// 16bitリニアPCMのサンプル列を保持します。
+/// 複数チャンネルの場合、サンプルはインターリーブされた状態で格納されます。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AudioBuffer {
+    sample_rate: u32,
+    channels: u16,
+    samples: Vec<i16>,
+}
+
+impl AudioBuffer {
+    /// 新しい`AudioBuffer`を作成します。
+    ///
+    /// # Arguments
+    ///
+    /// * `sample_rate` - サンプリングレート（Hz）
+    /// * `channels` - チャンネル数
+    /// * `samples` - インターリーブされたサンプル列
+    ///
+    /// # Errors
+    ///
+    /// サンプリングレートまたはチャンネル数が0の場合、
+    /// もしくはサンプル数がチャンネル数で割り切れない場合は `CevioAIError::InvalidWave` を返します。
+    pub fn new(sample_rate: u32, channels: u16, samples: Vec<i16>) -> Result<Self> {
+        if sample_rate == 0 || channels == 0 {
+            return Err(CevioAIError::InvalidWave(format!(
+                "sample rate and channels must be non-zero, got {sample_rate}Hz/{channels}ch"
+            )));
+        }
+        if !samples.len().is_multiple_of(usize::from(channels)) {
+            return Err(CevioAIError::InvalidWave(format!(
+                "sample count {} is not a multiple of {channels} channels",
+                samples.len()
+            )));
+        }
+        Ok(Self {
+            sample_rate,
+            channels,
+            samples,
+        })
+    }
+
+    /// WAV形式のバイト列から`AudioBuffer`を作成します。
+    ///
+    /// # Errors
+    ///
+    /// RIFF/WAVEとして解釈できない場合や、16bitリニアPCM以外の形式の場合は
+    /// `CevioAIError::InvalidWave` を返します。
+    pub fn from_wav_bytes(bytes: &[u8]) -> Result<Self> {
+        if bytes.len() < 12 || &bytes[0..4] != b"RIFF" || &bytes[8..12] != b"WAVE" {
+            return Err(CevioAIError::InvalidWave("missing RIFF/WAVE header".into()));
+        }
+
+        let mut format = None;
+        let mut data = None;
+        let mut pos = 12;
+
+        while pos + 8 <= bytes.len() {
+            let id = &bytes[pos..pos + 4];
+            let size = u32::from_le_bytes([
+                bytes[pos + 4],
+                bytes[pos + 5],
+                bytes[pos + 6],
+                bytes[pos + 7],
+            ]) as usize;
+            let body_start = pos + 8;
+            let body_end = body_start
+                .checked_add(size)
+                .filter(|&end| end <= bytes.len())
+                .ok_or_else(|| CevioAIError::InvalidWave("truncated chunk".into()))?;
+            let body = &bytes[body_start..body_end];
+
+            match id {
+                b"fmt " => {
+                    if body.len() < 16 {
+                        return Err(CevioAIError::InvalidWave("fmt chunk too short".into()));
+                    }
+                    let audio_format = u16::from_le_bytes([body[0], body[1]]);
+                    let channels = u16::from_le_bytes([body[2], body[3]]);
+                    let sample_rate = u32::from_le_bytes([body[4], body[5], body[6], body[7]]);
+                    let bits_per_sample = u16::from_le_bytes([body[14], body[15]]);
+                    if audio_format != 1 || bits_per_sample != 16 {
+                        return Err(CevioAIError::InvalidWave(format!(
+                            "unsupported format {audio_format} with {bits_per_sample} bits per sample"
+                        )));
+                    }
+                    format = Some((sample_rate, channels));
+                }
+                b"data" => data = Some(body),
+                _ => {}
+            }
+
+            // チャンクは2バイト境界に揃えられる
+            pos = body_end + (size & 1);
+        }
+
+        let (sample_rate, channels) =
+            format.ok_or_else(|| CevioAIError::InvalidWave("missing fmt chunk".into()))?;
+        let data = data.ok_or_else(|| CevioAIError::InvalidWave("missing data chunk".into()))?;
+
+        let samples = data
+            .chunks_exact(2)
+            .map(|pair| i16::from_le_bytes([pair[0], pair[1]]))
+            .collect();
+
+        Self::new(sample_rate, channels, samples)
+    }
+
+    /// 別のバッファのサンプルを末尾に追加します。
+    ///
+    /// # Errors
+    ///
+    /// サンプリングレートまたはチャンネル数が異なる場合は `CevioAIError::InvalidWave` を返します。
+    pub fn append(&mut self, other: &Self) -> Result<()> {
+        if self.sample_rate != other.sample_rate || self.channels != other.channels {
+            return Err(CevioAIError::InvalidWave(format!(
+                "cannot append {}Hz/{}ch to {}Hz/{}ch",
+                other.sample_rate, other.channels, self.sample_rate, self.channels
+            )));
+        }
+        self.samples.extend_from_slice(&other.samples);
+        Ok(())
+    }
+
+    /// WAVファイルを読み込みます。
+    ///
+    /// # Errors
+    ///
+    /// ファイルの読み込みに失敗した場合は `CevioAIError::Io` を、
+    /// 形式が不正な場合は `CevioAIError::InvalidWave` を返します。
+    pub fn from_wav_file<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Self::from_wav_bytes(&std::fs::read(path)?)
+    }
+
+    /// WAV形式のバイト列に変換します。
+    #[must_use]
+    pub fn to_wav_bytes(&self) -> Vec<u8> {
+        let data_len = (self.samples.len() * 2) as u32;
+        let block_align = self.channels * 2;
+        let byte_rate = self.sample_rate * u32::from(block_align);
+
+        let mut bytes = Vec::with_capacity(44 + data_len as usize);
+        bytes.extend_from_slice(b"RIFF");
+        bytes.extend_from_slice(&(36 + data_len).to_le_bytes());
+        bytes.extend_from_slice(b"WAVE");
+        bytes.extend_from_slice(b"fmt ");
+        bytes.extend_from_slice(&16u32.to_le_bytes());
+        bytes.extend_from_slice(&1u16.to_le_bytes());
+        bytes.extend_from_slice(&self.channels.to_le_bytes());
+        bytes.extend_from_slice(&self.sample_rate.to_le_bytes());
+        bytes.extend_from_slice(&byte_rate.to_le_bytes());
+        bytes.extend_from_slice(&block_align.to_le_bytes());
+        bytes.extend_from_slice(&16u16.to_le_bytes());
+        bytes.extend_from_slice(b"data");
+        bytes.extend_from_slice(&data_len.to_le_bytes());
+        for sample in &self.samples {
+            bytes.extend_from_slice(&sample.to_le_bytes());
+        }
+        bytes
+    }
+
+    /// WAVファイルとして書き出します。
+    ///
+    /// # Errors
+    ///
+    /// ファイルの書き込みに失敗した場合は `CevioAIError::Io` を返します。
+    pub fn write_wav_file<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        Ok(std::fs::write(path, self.to_wav_bytes())?)
+    }
+
+    /// サンプリングレート（Hz）を取得します。
+    #[must_use]
+    pub const fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    /// チャンネル数を取得します。
+    #[must_use]
+    pub const fn channels(&self) -> u16 {
+        self.channels
+    }
+
+    /// インターリーブされたサンプル列を取得します。
+    #[must_use]
+    pub fn samples(&self) -> &[i16] {
+        &self.samples
+    }
+
+    /// 1チャンネルあたりのサンプル数（フレーム数）を取得します。
+    #[must_use]
+    pub fn frames(&self) -> usize {
+        self.samples.len() / usize::from(self.channels)
+    }
+
+    /// 長さ（秒）を取得します。
+    #[must_use]
+    pub fn duration(&self) -> f64 {
+        self.frames() as f64 / f64::from(self.sample_rate)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wav_round_trip() {
+        let buffer = AudioBuffer::new(48_000, 1, vec![0, 1, -1, i16::MAX, i16::MIN]).unwrap();
+        let bytes = buffer.to_wav_bytes();
+
+        assert_eq!(bytes.len(), 44 + 10);
+        assert_eq!(AudioBuffer::from_wav_bytes(&bytes).unwrap(), buffer);
+    }
+
+    #[test]
+    fn skips_unknown_chunks() {
+        let buffer = AudioBuffer::new(44_100, 2, vec![1, 2, 3, 4]).unwrap();
+        let mut bytes = buffer.to_wav_bytes();
+        // fmtチャンクの直後に奇数長のLISTチャンクを挿入する
+        let list = [b"LIST".as_slice(), &3u32.to_le_bytes(), b"abc\0"].concat();
+        bytes.splice(36..36, list);
+
+        let parsed = AudioBuffer::from_wav_bytes(&bytes).unwrap();
+        assert_eq!(parsed, buffer);
+        assert_eq!(parsed.frames(), 2);
+    }
+
+    #[test]
+    fn append_requires_same_format() {
+        let mut buffer = AudioBuffer::new(48_000, 1, vec![1, 2]).unwrap();
+        buffer
+            .append(&AudioBuffer::new(48_000, 1, vec![3]).unwrap())
+            .unwrap();
+        assert_eq!(buffer.samples(), &[1, 2, 3]);
+
+        assert!(buffer
+            .append(&AudioBuffer::new(44_100, 1, vec![4]).unwrap())
+            .is_err());
+    }
+
+    #[test]
+    fn rejects_non_pcm16() {
+        let mut bytes = AudioBuffer::new(48_000, 1, vec![0; 4])
+            .unwrap()
+            .to_wav_bytes();
+        bytes[34] = 8;
+
+        assert!(matches!(
+            AudioBuffer::from_wav_bytes(&bytes),
+            Err(CevioAIError::InvalidWave(_))
+        ));
+        assert!(AudioBuffer::from_wav_bytes(b"not a wave").is_err());
+    }
+}
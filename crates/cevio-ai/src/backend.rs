@@ -0,0 +1,171 @@
+//! CeVIO AI操作の抽象化
+//!
+//! このモジュールは、CeVIO AIへの操作をまとめた`Backend`トレイトを提供します。
+//! `CevioAI`はこのトレイトを実装しており、`fake`フィーチャを有効にすると
+//! CeVIO AIを必要としないテスト用の`FakeBackend`も利用できます。
+//!
+//! COMオブジェクトを保持する`Component`や`SpeakingState`の代わりに、
+//! `ComponentValue`や完了まで待機する`speak_and_wait`を使用するため、
+//! アプリケーション側のロジックをCeVIO AIから切り離してテストできます。
+
+use crate::{
+    audio::AudioBuffer,
+    cast_name::CastName,
+    cevio::{Cast, CevioAI, CloseMode, ComponentValue, PhonemeData},
+    error::{CevioAIError, Result},
+};
+
+/// CeVIO AIへの操作
+///
+/// 各メソッドは`CevioAI`の同名メソッドと同じ意味を持ちます。
+pub trait Backend {
+    /// CeVIO AIを起動します。
+    fn start(&self, no_wait: bool) -> Result<()>;
+
+    /// CeVIO AIに終了を要求します。
+    fn close(&self, mode: CloseMode) -> Result<()>;
+
+    /// CeVIO AIが起動中かどうかを取得します。
+    fn is_host_started(&self) -> Result<bool>;
+
+    /// CeVIO AIのバージョンを取得します。
+    fn host_version(&self) -> Result<String>;
+
+    /// CeVIO AIのCOMインターフェースのバージョンを取得します。
+    fn interface_version(&self) -> Result<String>;
+
+    /// 利用可能なキャスト名を取得します。
+    fn available_casts(&self) -> Result<Vec<String>>;
+
+    /// キャスト名を利用可能なキャストの一覧と照合します。
+    ///
+    /// 既定の実装は`available_casts`の結果と照合します。
+    fn validate_cast(&self, name: &str) -> Result<CastName> {
+        CastName::validate(name, &self.available_casts()?)
+    }
+
+    /// 現在のキャストと音声パラメータを取得します。
+    fn current_cast(&self) -> Result<Cast>;
+
+    /// キャスト設定を一括で適用します。
+    fn apply_cast(&self, cast: &Cast) -> Result<()>;
+
+    /// 現在のキャストの感情パラメータとその値を取得します。
+    fn component_values(&self) -> Result<Vec<ComponentValue>>;
+
+    /// 感情パラメータの値を名前で一括設定します。
+    fn apply_components(&self, values: &[(String, u8)]) -> Result<()>;
+
+    /// 指定したセリフを再生し、再生終了まで待ちます。
+    ///
+    /// # Returns
+    ///
+    /// 再生が成功した場合は`true`、それ以外の場合は`false`
+    fn speak_and_wait(&self, text: &str) -> Result<bool>;
+
+    /// 再生を停止します。
+    fn stop(&self) -> Result<bool>;
+
+    /// 指定したセリフの長さ（秒）を取得します。
+    fn text_duration(&self, text: &str) -> Result<f64>;
+
+    /// 指定したセリフの音素単位のデータを取得します。
+    fn phonemes(&self, text: &str) -> Result<Vec<PhonemeData>>;
+
+    /// 指定したセリフを合成し、音声データとして取得します。
+    fn synthesize(&self, text: &str) -> Result<AudioBuffer>;
+
+    /// 操作のタイムアウトにより使用不能になっているかどうかを取得します。
+    ///
+    /// 既定の実装は常に`false`を返します。
+    fn is_poisoned(&self) -> bool {
+        false
+    }
+
+    /// 同じ設定で新しいバックエンドを作成します。
+    ///
+    /// 使用不能になったバックエンドの代わりに使用します。
+    /// 既定の実装は`CevioAIError::Unsupported`を返します。
+    fn rebuild(&self) -> Result<Self>
+    where
+        Self: Sized,
+    {
+        Err(CevioAIError::Unsupported("rebuild".to_string()))
+    }
+}
+
+impl Backend for CevioAI {
+    fn start(&self, no_wait: bool) -> Result<()> {
+        Self::start(self, no_wait)
+    }
+
+    fn close(&self, mode: CloseMode) -> Result<()> {
+        Self::close(self, mode)
+    }
+
+    fn is_host_started(&self) -> Result<bool> {
+        Self::is_host_started(self)
+    }
+
+    fn host_version(&self) -> Result<String> {
+        Self::host_version(self)
+    }
+
+    fn interface_version(&self) -> Result<String> {
+        Self::interface_version(self)
+    }
+
+    fn available_casts(&self) -> Result<Vec<String>> {
+        Self::available_casts(self)
+    }
+
+    fn validate_cast(&self, name: &str) -> Result<CastName> {
+        Self::validate_cast(self, name)
+    }
+
+    fn current_cast(&self) -> Result<Cast> {
+        Self::current_cast(self)
+    }
+
+    fn apply_cast(&self, cast: &Cast) -> Result<()> {
+        Self::apply_cast(self, cast)
+    }
+
+    fn component_values(&self) -> Result<Vec<ComponentValue>> {
+        Self::component_values(self)
+    }
+
+    fn apply_components(&self, values: &[(String, u8)]) -> Result<()> {
+        Self::apply_components(self, values)
+    }
+
+    fn speak_and_wait(&self, text: &str) -> Result<bool> {
+        let state = self.speak(text)?;
+        state.wait()?;
+        state.is_succeeded()
+    }
+
+    fn stop(&self) -> Result<bool> {
+        Self::stop(self)
+    }
+
+    fn text_duration(&self, text: &str) -> Result<f64> {
+        Self::text_duration(self, text)
+    }
+
+    fn phonemes(&self, text: &str) -> Result<Vec<PhonemeData>> {
+        Self::phonemes(self, text)
+    }
+
+    fn synthesize(&self, text: &str) -> Result<AudioBuffer> {
+        Self::synthesize(self, text)
+    }
+
+    fn is_poisoned(&self) -> bool {
+        Self::is_poisoned(self)
+    }
+
+    fn rebuild(&self) -> Result<Self> {
+        Self::rebuild(self)
+    }
+}
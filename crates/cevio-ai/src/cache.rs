@@ -0,0 +1,725 @@
+//! 合成結果のキャッシュ
+//!
+//! このモジュールは、合成結果（WAV・音素データ・セリフの長さ）をディスクに保存し、
+//! 同じ条件での再合成を省略するための`SynthesisCache`を提供します。
+//!
+//! キャッシュのキーは、正規化したセリフ・キャスト・5つの音声パラメータ・感情パラメータの値・
+//! CeVIO AIのバージョンから計算されるSHA-256ハッシュです。
+//! `SynthesisCache::synthesize`などは、指定された設定を適用した後のCeVIO AIの実際の値から
+//! キーを計算するため、省略したパラメータや感情が前回の合成の値を引き継いでも
+//! 別の音声を返すことはありません。`CacheKey::new`と`get_wave`などを直接使用すれば、
+//! CeVIO AIが起動していない環境でもキャッシュ済みの結果を取得できます。
+//!
+//! ## 使用例
+//!
+//! ```rust,no_run
+//! use cevio_ai::*;
+//!
+//! fn main() -> Result<()> {
+//!     let cevio = CevioAI::new()?;
+//!     let cache = SynthesisCache::new("cache", 512 * 1024 * 1024)?
+//!         .with_host_version(cevio.host_version()?);
+//!
+//!     let cast = CastBuilder::default()
+//!         .cast("さとうささら")
+//!         .with_defaults()
+//!         .build()?;
+//!
+//!     // 2回目以降はディスクから読み込まれる
+//!     let audio = cache.synthesize(&cevio, "こんにちは", &cast, &[("元気", 80)])?;
+//!     audio.write_wav_file("hello.wav")?;
+//!     Ok(())
+//! }
+//! ```
+
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use parking_lot::Mutex;
+use sha2::{Digest, Sha256};
+
+use crate::{
+    audio::AudioBuffer,
+    backend::Backend,
+    cevio::{Cast, PhonemeData},
+    error::Result,
+};
+
+const WAVE_EXTENSION: &str = "wav";
+const PHONEMES_EXTENSION: &str = "phonemes";
+const DURATION_EXTENSION: &str = "duration";
+const TEMP_EXTENSION: &str = "tmp";
+
+/// キャッシュのキー
+///
+/// 合成結果に影響するすべての条件から計算されるハッシュ値です。
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CacheKey(String);
+
+impl CacheKey {
+    /// 合成条件からキーを計算します。
+    ///
+    /// セリフは前後の空白を取り除き、連続する空白を1つの半角スペースにまとめてから使用します。
+    /// 感情パラメータは名前順に並べ替えるため、指定順序はキーに影響しません。
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - セリフ
+    /// * `cast` - キャスト設定
+    /// * `components` - 感情の名前と値の組
+    /// * `host_version` - CeVIO AIのバージョン
+    #[must_use]
+    pub fn new<S: AsRef<str>>(
+        text: &str,
+        cast: &Cast,
+        components: &[(S, u8)],
+        host_version: Option<&str>,
+    ) -> Self {
+        let mut components: Vec<(&str, u8)> = components
+            .iter()
+            .map(|(name, value)| (name.as_ref(), *value))
+            .collect();
+        components.sort_unstable();
+
+        let fields = [
+            "v1".to_string(),
+            normalize_text(text),
+            cast.cast.clone().unwrap_or_default(),
+            format_parameter(cast.volume.map(|v| v.get())),
+            format_parameter(cast.speed.map(|v| v.get())),
+            format_parameter(cast.tone.map(|v| v.get())),
+            format_parameter(cast.tone_scale.map(|v| v.get())),
+            format_parameter(cast.alpha.map(|v| v.get())),
+            components
+                .iter()
+                .map(|(name, value)| format!("{name}={value}"))
+                .collect::<Vec<_>>()
+                .join(","),
+            host_version.unwrap_or_default().to_string(),
+        ];
+
+        let mut hasher = Sha256::new();
+        for field in &fields {
+            hasher.update(field.as_bytes());
+            // 区切り文字としてNULを使用し、フィールドの境界を曖昧にしない
+            hasher.update([0]);
+        }
+
+        Self(
+            hasher
+                .finalize()
+                .iter()
+                .map(|byte| format!("{byte:02x}"))
+                .collect(),
+        )
+    }
+
+    /// 16進数表記のハッシュ値を取得します。
+    #[must_use]
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+fn normalize_text(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+fn owned_components<S: AsRef<str>>(components: &[(S, u8)]) -> Vec<(String, u8)> {
+    components
+        .iter()
+        .map(|(name, value)| (name.as_ref().to_string(), *value))
+        .collect()
+}
+
+fn format_parameter(value: Option<u8>) -> String {
+    value.map_or_else(|| "-".to_string(), |value| value.to_string())
+}
+
+/// 合成結果のディスクキャッシュ
+///
+/// エントリは`<キー>.wav`・`<キー>.phonemes`・`<キー>.duration`としてディレクトリに保存されます。
+/// 合計サイズが上限を超えた場合、最後に参照された時刻が古いエントリから削除されます（LRU）。
+///
+/// # Note
+///
+/// キャスト名・パラメータ・感情を省略した場合、合成にはCeVIO AIの現在値が使用されます。
+/// `synthesize`・`phonemes`・`text_duration`は現在値を問い合わせてキーに含めますが、
+/// `key`や`CacheKey::new`は指定された値だけから計算するため、
+/// 直接使用する場合はキャスト名・すべてのパラメータ・すべての感情を指定してください。
+#[derive(Debug)]
+pub struct SynthesisCache {
+    dir: PathBuf,
+    max_size: u64,
+    host_version: Option<String>,
+    lock: Mutex<()>,
+}
+
+impl SynthesisCache {
+    /// キャッシュを作成します。
+    ///
+    /// ディレクトリが存在しない場合は作成します。
+    ///
+    /// # Arguments
+    ///
+    /// * `dir` - キャッシュディレクトリ
+    /// * `max_size` - キャッシュの合計サイズの上限（バイト）
+    ///
+    /// # Errors
+    ///
+    /// ディレクトリの作成に失敗した場合は `CevioAIError::Io` を返します。
+    pub fn new<P: Into<PathBuf>>(dir: P, max_size: u64) -> Result<Self> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir)?;
+        Ok(Self {
+            dir,
+            max_size,
+            host_version: None,
+            lock: Mutex::new(()),
+        })
+    }
+
+    /// キーに含めるCeVIO AIのバージョンを設定します。
+    ///
+    /// CeVIO AIの更新で合成結果が変わる可能性があるため、
+    /// `CevioAI::host_version()` の値を設定することを推奨します。
+    #[must_use]
+    pub fn with_host_version<S: Into<String>>(mut self, host_version: S) -> Self {
+        self.host_version = Some(host_version.into());
+        self
+    }
+
+    /// キャッシュディレクトリを取得します。
+    #[must_use]
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// 合成条件からキーを計算します。
+    #[must_use]
+    pub fn key<S: AsRef<str>>(&self, text: &str, cast: &Cast, components: &[(S, u8)]) -> CacheKey {
+        CacheKey::new(text, cast, components, self.host_version.as_deref())
+    }
+
+    /// キャッシュ済みの音声データを取得します。
+    pub fn get_wave(&self, key: &CacheKey) -> Result<Option<AudioBuffer>> {
+        let Some(bytes) = self.read(key, WAVE_EXTENSION)? else {
+            return Ok(None);
+        };
+        match AudioBuffer::from_wav_bytes(&bytes) {
+            Ok(buffer) => Ok(Some(buffer)),
+            Err(_) => self.discard(key, WAVE_EXTENSION),
+        }
+    }
+
+    /// 音声データをキャッシュに保存します。
+    pub fn put_wave(&self, key: &CacheKey, buffer: &AudioBuffer) -> Result<()> {
+        self.write(key, WAVE_EXTENSION, &buffer.to_wav_bytes())
+    }
+
+    /// キャッシュ済みの音素データを取得します。
+    pub fn get_phonemes(&self, key: &CacheKey) -> Result<Option<Vec<PhonemeData>>> {
+        let Some(bytes) = self.read(key, PHONEMES_EXTENSION)? else {
+            return Ok(None);
+        };
+        match decode_phonemes(&bytes) {
+            Some(phonemes) => Ok(Some(phonemes)),
+            None => self.discard(key, PHONEMES_EXTENSION),
+        }
+    }
+
+    /// 音素データをキャッシュに保存します。
+    pub fn put_phonemes(&self, key: &CacheKey, phonemes: &[PhonemeData]) -> Result<()> {
+        self.write(
+            key,
+            PHONEMES_EXTENSION,
+            encode_phonemes(phonemes).as_bytes(),
+        )
+    }
+
+    /// キャッシュ済みのセリフの長さ（秒）を取得します。
+    pub fn get_duration(&self, key: &CacheKey) -> Result<Option<f64>> {
+        let Some(bytes) = self.read(key, DURATION_EXTENSION)? else {
+            return Ok(None);
+        };
+        match std::str::from_utf8(&bytes)
+            .ok()
+            .and_then(|s| s.trim().parse().ok())
+        {
+            Some(duration) => Ok(Some(duration)),
+            None => self.discard(key, DURATION_EXTENSION),
+        }
+    }
+
+    /// セリフの長さ（秒）をキャッシュに保存します。
+    pub fn put_duration(&self, key: &CacheKey, duration: f64) -> Result<()> {
+        self.write(key, DURATION_EXTENSION, duration.to_string().as_bytes())
+    }
+
+    /// キャッシュを利用してセリフを合成します。
+    ///
+    /// キャスト設定と感情パラメータを適用し、CeVIO AIの実際の値からキーを計算します。
+    /// キャッシュに存在しない場合のみ `Backend::synthesize()` を呼び出し、結果を保存します。
+    pub fn synthesize<B: Backend + ?Sized, S: AsRef<str>>(
+        &self,
+        backend: &B,
+        text: &str,
+        cast: &Cast,
+        components: &[(S, u8)],
+    ) -> Result<AudioBuffer> {
+        let key = self.resolve_key(backend, text, cast, components)?;
+        if let Some(buffer) = self.get_wave(&key)? {
+            return Ok(buffer);
+        }
+
+        let buffer = backend.synthesize(text)?;
+        self.put_wave(&key, &buffer)?;
+        Ok(buffer)
+    }
+
+    /// キャッシュを利用して音素データを取得します。
+    ///
+    /// キーは`synthesize`と同様に計算します。
+    /// キャッシュに存在しない場合のみ `Backend::phonemes()` を呼び出し、結果を保存します。
+    pub fn phonemes<B: Backend + ?Sized, S: AsRef<str>>(
+        &self,
+        backend: &B,
+        text: &str,
+        cast: &Cast,
+        components: &[(S, u8)],
+    ) -> Result<Vec<PhonemeData>> {
+        let key = self.resolve_key(backend, text, cast, components)?;
+        if let Some(phonemes) = self.get_phonemes(&key)? {
+            return Ok(phonemes);
+        }
+
+        let phonemes = backend.phonemes(text)?;
+        self.put_phonemes(&key, &phonemes)?;
+        Ok(phonemes)
+    }
+
+    /// キャッシュを利用してセリフの長さ（秒）を取得します。
+    ///
+    /// キーは`synthesize`と同様に計算します。
+    /// キャッシュに存在しない場合のみ `Backend::text_duration()` を呼び出し、結果を保存します。
+    pub fn text_duration<B: Backend + ?Sized, S: AsRef<str>>(
+        &self,
+        backend: &B,
+        text: &str,
+        cast: &Cast,
+        components: &[(S, u8)],
+    ) -> Result<f64> {
+        let key = self.resolve_key(backend, text, cast, components)?;
+        if let Some(duration) = self.get_duration(&key)? {
+            return Ok(duration);
+        }
+
+        let duration = backend.text_duration(text)?;
+        self.put_duration(&key, duration)?;
+        Ok(duration)
+    }
+
+    /// キャスト設定と感情パラメータを適用し、適用後の実際の値からキーを計算します。
+    ///
+    /// 省略された値はCeVIO AIの現在値で補われるため、キーは合成される音声と一致します。
+    fn resolve_key<B: Backend + ?Sized, S: AsRef<str>>(
+        &self,
+        backend: &B,
+        text: &str,
+        cast: &Cast,
+        components: &[(S, u8)],
+    ) -> Result<CacheKey> {
+        backend.apply_cast(cast)?;
+        backend.apply_components(&owned_components(components))?;
+
+        let cast = backend.current_cast()?;
+        let components: Vec<(String, u8)> = backend
+            .component_values()?
+            .into_iter()
+            .map(|component| (component.name, component.value))
+            .collect();
+        Ok(self.key(text, &cast, &components))
+    }
+
+    /// キャッシュの合計サイズ（バイト）を取得します。
+    pub fn size(&self) -> Result<u64> {
+        Ok(self.entries()?.values().map(|entry| entry.size).sum())
+    }
+
+    /// すべてのエントリを削除します。
+    ///
+    /// キャッシュが作成したファイル（書き込み途中の一時ファイルを含む）だけを削除し、
+    /// ディレクトリ内のその他のファイルには触れません。
+    pub fn clear(&self) -> Result<()> {
+        let _guard = self.lock.lock();
+        for entry in fs::read_dir(&self.dir)? {
+            let path = entry?.path();
+            let is_temp = path.extension().and_then(|ext| ext.to_str()) == Some(TEMP_EXTENSION);
+            let target = if is_temp {
+                path.with_extension("")
+            } else {
+                path.clone()
+            };
+            if entry_key(&target).is_some() && path.is_file() {
+                fs::remove_file(path)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn path(&self, key: &CacheKey, extension: &str) -> PathBuf {
+        self.dir.join(key.as_str()).with_extension(extension)
+    }
+
+    fn read(&self, key: &CacheKey, extension: &str) -> Result<Option<Vec<u8>>> {
+        let path = self.path(key, extension);
+        match fs::read(&path) {
+            Ok(bytes) => {
+                // 参照時刻を更新してLRUの順序に反映する。読み取り専用のファイルや、
+                // 読み込み後に他のプロセスが削除した場合でも、読み込んだ内容はそのまま返す
+                let _ = File::options()
+                    .write(true)
+                    .open(&path)
+                    .and_then(|file| file.set_modified(SystemTime::now()));
+                Ok(Some(bytes))
+            }
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(error) => Err(error.into()),
+        }
+    }
+
+    fn write(&self, key: &CacheKey, extension: &str, bytes: &[u8]) -> Result<()> {
+        let _guard = self.lock.lock();
+
+        // 書き込み途中のファイルを読まないよう、一時ファイルを経由する
+        let temp = self
+            .dir
+            .join(format!("{}.{extension}.{TEMP_EXTENSION}", key.as_str()));
+        fs::write(&temp, bytes)?;
+        fs::rename(&temp, self.path(key, extension))?;
+
+        self.evict()
+    }
+
+    fn discard<T>(&self, key: &CacheKey, extension: &str) -> Result<Option<T>> {
+        let _ = fs::remove_file(self.path(key, extension));
+        Ok(None)
+    }
+
+    fn entries(&self) -> Result<HashMap<String, Entry>> {
+        let mut entries: HashMap<String, Entry> = HashMap::new();
+
+        for dir_entry in fs::read_dir(&self.dir)? {
+            let dir_entry = dir_entry?;
+            let path = dir_entry.path();
+            let Some(stem) = entry_key(&path) else {
+                continue;
+            };
+            let metadata = dir_entry.metadata()?;
+            if !metadata.is_file() {
+                continue;
+            }
+
+            let modified = metadata.modified()?;
+            let entry = entries.entry(stem.to_string()).or_insert(Entry {
+                size: 0,
+                last_access: modified,
+                paths: Vec::new(),
+            });
+            entry.size += metadata.len();
+            entry.last_access = entry.last_access.max(modified);
+            entry.paths.push(path);
+        }
+
+        Ok(entries)
+    }
+
+    fn evict(&self) -> Result<()> {
+        let mut entries: Vec<Entry> = self.entries()?.into_values().collect();
+        let mut total: u64 = entries.iter().map(|entry| entry.size).sum();
+        if total <= self.max_size {
+            return Ok(());
+        }
+
+        entries.sort_by_key(|entry| entry.last_access);
+        for entry in entries {
+            if total <= self.max_size {
+                break;
+            }
+            for path in &entry.paths {
+                fs::remove_file(path)?;
+            }
+            total -= entry.size;
+        }
+
+        Ok(())
+    }
+}
+
+/// キャッシュが作成したファイルであれば、そのキーを返します。
+///
+/// 既存のディレクトリを指定された場合に他のファイルを削除しないよう、
+/// `<64桁の16進数>.<wav|phonemes|duration>`の形式のファイルだけを対象にします。
+fn entry_key(path: &Path) -> Option<&str> {
+    let extension = path.extension()?.to_str()?;
+    if ![WAVE_EXTENSION, PHONEMES_EXTENSION, DURATION_EXTENSION].contains(&extension) {
+        return None;
+    }
+    let stem = path.file_stem()?.to_str()?;
+    (stem.len() == 64 && stem.bytes().all(|byte| byte.is_ascii_hexdigit())).then_some(stem)
+}
+
+struct Entry {
+    size: u64,
+    last_access: SystemTime,
+    paths: Vec<PathBuf>,
+}
+
+fn encode_phonemes(phonemes: &[PhonemeData]) -> String {
+    phonemes
+        .iter()
+        .map(|data| {
+            format!(
+                "{}\t{}\t{}\n",
+                data.phoneme(),
+                data.start_time(),
+                data.end_time()
+            )
+        })
+        .collect()
+}
+
+fn decode_phonemes(bytes: &[u8]) -> Option<Vec<PhonemeData>> {
+    std::str::from_utf8(bytes)
+        .ok()?
+        .lines()
+        .map(|line| {
+            let mut fields = line.split('\t');
+            let phoneme = fields.next()?.to_string();
+            let start_time = fields.next()?.parse().ok()?;
+            let end_time = fields.next()?.parse().ok()?;
+            Some(PhonemeData::new(phoneme, start_time, end_time))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::{CastBuilder, Speed, Volume};
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("cevio-ai-cache-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    fn set_last_access(cache: &SynthesisCache, key: &CacheKey, ago: Duration) {
+        let time = SystemTime::now() - ago;
+        for extension in [WAVE_EXTENSION, PHONEMES_EXTENSION, DURATION_EXTENSION] {
+            if let Ok(file) = File::options().write(true).open(cache.path(key, extension)) {
+                file.set_modified(time).unwrap();
+            }
+        }
+    }
+
+    #[test]
+    fn key_normalizes_text_and_component_order() {
+        let cast = CastBuilder::default()
+            .cast("さとうささら")
+            .with_defaults()
+            .build()
+            .unwrap();
+
+        let a = CacheKey::new(
+            " こんにちは　 世界 ",
+            &cast,
+            &[("元気", 80), ("普通", 20)],
+            None,
+        );
+        let b = CacheKey::new(
+            "こんにちは 世界",
+            &cast,
+            &[("普通", 20), ("元気", 80)],
+            None,
+        );
+        assert_eq!(a, b);
+        assert_eq!(a.as_str().len(), 64);
+    }
+
+    #[test]
+    fn key_depends_on_every_condition() {
+        let cast = CastBuilder::default().cast("さとうささら").build().unwrap();
+        let base = CacheKey::new("こんにちは", &cast, &[("元気", 80)], Some("9.1"));
+
+        let louder = CastBuilder::default()
+            .cast("さとうささら")
+            .volume(Volume::new(80).unwrap())
+            .build()
+            .unwrap();
+        let faster = CastBuilder::default()
+            .cast("さとうささら")
+            .speed(Speed::new(80).unwrap())
+            .build()
+            .unwrap();
+
+        let others = [
+            CacheKey::new("こんばんは", &cast, &[("元気", 80)], Some("9.1")),
+            CacheKey::new("こんにちは", &louder, &[("元気", 80)], Some("9.1")),
+            CacheKey::new("こんにちは", &faster, &[("元気", 80)], Some("9.1")),
+            CacheKey::new("こんにちは", &cast, &[("元気", 81)], Some("9.1")),
+            CacheKey::new("こんにちは", &cast, &[("元気", 80)], Some("9.2")),
+            CacheKey::new::<&str>("こんにちは", &cast, &[], Some("9.1")),
+        ];
+        for other in &others {
+            assert_ne!(&base, other);
+        }
+    }
+
+    #[test]
+    fn stores_and_loads_entries() -> Result<()> {
+        let dir = temp_dir("store");
+        let cache = SynthesisCache::new(&dir, u64::MAX)?.with_host_version("9.1");
+        let key = cache.key::<&str>("こんにちは", &Cast::default(), &[]);
+
+        assert!(cache.get_wave(&key)?.is_none());
+        assert!(cache.get_phonemes(&key)?.is_none());
+        assert!(cache.get_duration(&key)?.is_none());
+
+        let buffer = AudioBuffer::new(48_000, 1, vec![0, 100, -100])?;
+        let phonemes = vec![
+            PhonemeData::new("sil".into(), 0.0, 0.1),
+            PhonemeData::new("k".into(), 0.1, 0.15),
+            PhonemeData::new("o".into(), 0.15, 0.3),
+        ];
+        cache.put_wave(&key, &buffer)?;
+        cache.put_phonemes(&key, &phonemes)?;
+        cache.put_duration(&key, 0.3)?;
+
+        assert_eq!(cache.get_wave(&key)?, Some(buffer));
+        assert_eq!(cache.get_phonemes(&key)?, Some(phonemes));
+        assert_eq!(cache.get_duration(&key)?, Some(0.3));
+
+        cache.clear()?;
+        assert_eq!(cache.size()?, 0);
+        fs::remove_dir_all(dir)?;
+        Ok(())
+    }
+
+    #[test]
+    fn corrupted_entry_is_a_miss() -> Result<()> {
+        let dir = temp_dir("corrupted");
+        let cache = SynthesisCache::new(&dir, u64::MAX)?;
+        let key = cache.key::<&str>("こんにちは", &Cast::default(), &[]);
+
+        fs::write(cache.path(&key, WAVE_EXTENSION), b"garbage")?;
+        fs::write(cache.path(&key, PHONEMES_EXTENSION), b"a\tnot-a-number\t1")?;
+
+        assert!(cache.get_wave(&key)?.is_none());
+        assert!(cache.get_phonemes(&key)?.is_none());
+        assert_eq!(cache.size()?, 0);
+        fs::remove_dir_all(dir)?;
+        Ok(())
+    }
+
+    #[test]
+    fn evicts_least_recently_used() -> Result<()> {
+        let dir = temp_dir("evict");
+        let buffer = AudioBuffer::new(48_000, 1, vec![0; 100])?;
+        let entry_size = buffer.to_wav_bytes().len() as u64;
+        let cache = SynthesisCache::new(&dir, entry_size * 2)?;
+
+        let keys: Vec<CacheKey> = ["a", "b", "c"]
+            .iter()
+            .map(|text| cache.key::<&str>(text, &Cast::default(), &[]))
+            .collect();
+
+        cache.put_wave(&keys[0], &buffer)?;
+        cache.put_wave(&keys[1], &buffer)?;
+        set_last_access(&cache, &keys[0], Duration::from_secs(20));
+        set_last_access(&cache, &keys[1], Duration::from_secs(10));
+
+        // "a"を参照して最新にする
+        assert!(cache.get_wave(&keys[0])?.is_some());
+
+        cache.put_wave(&keys[2], &buffer)?;
+        assert!(cache.get_wave(&keys[0])?.is_some());
+        assert!(cache.get_wave(&keys[1])?.is_none());
+        assert!(cache.get_wave(&keys[2])?.is_some());
+        assert_eq!(cache.size()?, entry_size * 2);
+
+        fs::remove_dir_all(dir)?;
+        Ok(())
+    }
+
+    #[cfg(feature = "fake")]
+    #[test]
+    fn key_uses_the_effective_settings() -> Result<()> {
+        use crate::fake::FakeBackend;
+
+        let dir = temp_dir("effective");
+        let cache = SynthesisCache::new(&dir, u64::MAX)?;
+        let backend = FakeBackend::new();
+        let text = "こんにちは";
+        let speed = |value| {
+            CastBuilder::default()
+                .speed(Speed::new(value).unwrap())
+                .build()
+                .unwrap()
+        };
+
+        // 同じ指定でも、ホストの現在値が違えば別のエントリになる
+        backend.apply_cast(&speed(100))?;
+        let fast = cache.synthesize::<_, &str>(&backend, text, &Cast::default(), &[])?;
+        backend.apply_cast(&speed(0))?;
+        let slow = cache.synthesize::<_, &str>(&backend, text, &Cast::default(), &[])?;
+        assert!(slow.duration() > fast.duration());
+
+        // 省略した感情は現在値で補われる
+        let tsuzumi = CastBuilder::default().cast("すずきつづみ").build().unwrap();
+        cache.synthesize(&backend, text, &tsuzumi, &[("照れ", 100)])?;
+        let cool = cache.synthesize(&backend, text, &Cast::default(), &[("クール", 100)])?;
+        let with_both = cache.key(
+            text,
+            &backend.current_cast()?,
+            &[("クール", 100), ("照れ", 100)],
+        );
+        assert_eq!(cache.get_wave(&with_both)?, Some(cool));
+
+        fs::remove_dir_all(dir)?;
+        Ok(())
+    }
+
+    #[test]
+    fn leaves_other_files_alone() -> Result<()> {
+        let dir = temp_dir("foreign");
+        let buffer = AudioBuffer::new(48_000, 1, vec![0; 100])?;
+        let cache = SynthesisCache::new(&dir, 0)?;
+        let key = cache.key::<&str>("こんにちは", &Cast::default(), &[]);
+
+        let foreign = ["notes.txt", "voice.wav", "README"];
+        for name in foreign {
+            fs::write(dir.join(name), b"keep me")?;
+        }
+        fs::write(dir.join(format!("{}.wav.tmp", key.as_str())), b"partial")?;
+
+        // 上限0でも自分のエントリだけが削除される
+        cache.put_wave(&key, &buffer)?;
+        assert!(cache.get_wave(&key)?.is_none());
+        assert_eq!(cache.size()?, 0);
+
+        cache.clear()?;
+        for name in foreign {
+            assert!(dir.join(name).exists(), "{name} was removed");
+        }
+        assert!(!dir.join(format!("{}.wav.tmp", key.as_str())).exists());
+
+        fs::remove_dir_all(dir)?;
+        Ok(())
+    }
+}
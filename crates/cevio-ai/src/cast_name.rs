@@ -0,0 +1,384 @@
+//! 検証済みのキャスト名
+//!
+//! このモジュールは、利用可能なキャストの一覧と照合したキャスト名`CastName`を提供します。
+//! 一覧にない名前は`CevioAIError::UnknownCast`になり、表記ゆれ（カタカナ・ローマ字）と
+//! 編集距離から推定した候補が添えられます。
+
+use std::fmt;
+
+#[cfg(feature = "serde")]
+use serde::Serialize;
+
+use crate::error::{CevioAIError, Result};
+
+/// 候補として返すキャスト名の最大数
+const MAX_SUGGESTIONS: usize = 3;
+
+/// ローマ字とひらがなの対応
+const ROMAJI: &[(&str, &str)] = &[
+    ("bya", "びゃ"),
+    ("byo", "びょ"),
+    ("byu", "びゅ"),
+    ("cha", "ちゃ"),
+    ("che", "ちぇ"),
+    ("chi", "ち"),
+    ("cho", "ちょ"),
+    ("chu", "ちゅ"),
+    ("dya", "ぢゃ"),
+    ("dyo", "ぢょ"),
+    ("dyu", "ぢゅ"),
+    ("gya", "ぎゃ"),
+    ("gyo", "ぎょ"),
+    ("gyu", "ぎゅ"),
+    ("hya", "ひゃ"),
+    ("hyo", "ひょ"),
+    ("hyu", "ひゅ"),
+    ("kya", "きゃ"),
+    ("kyo", "きょ"),
+    ("kyu", "きゅ"),
+    ("mya", "みゃ"),
+    ("myo", "みょ"),
+    ("myu", "みゅ"),
+    ("nya", "にゃ"),
+    ("nyo", "にょ"),
+    ("nyu", "にゅ"),
+    ("pya", "ぴゃ"),
+    ("pyo", "ぴょ"),
+    ("pyu", "ぴゅ"),
+    ("rya", "りゃ"),
+    ("ryo", "りょ"),
+    ("ryu", "りゅ"),
+    ("sha", "しゃ"),
+    ("she", "しぇ"),
+    ("shi", "し"),
+    ("sho", "しょ"),
+    ("shu", "しゅ"),
+    ("sya", "しゃ"),
+    ("syo", "しょ"),
+    ("syu", "しゅ"),
+    ("tsu", "つ"),
+    ("tya", "ちゃ"),
+    ("tyo", "ちょ"),
+    ("tyu", "ちゅ"),
+    ("zya", "じゃ"),
+    ("zyo", "じょ"),
+    ("zyu", "じゅ"),
+    ("ba", "ば"),
+    ("be", "べ"),
+    ("bi", "び"),
+    ("bo", "ぼ"),
+    ("bu", "ぶ"),
+    ("da", "だ"),
+    ("de", "で"),
+    ("di", "ぢ"),
+    ("do", "ど"),
+    ("du", "づ"),
+    ("fa", "ふぁ"),
+    ("fe", "ふぇ"),
+    ("fi", "ふぃ"),
+    ("fo", "ふぉ"),
+    ("fu", "ふ"),
+    ("ga", "が"),
+    ("ge", "げ"),
+    ("gi", "ぎ"),
+    ("go", "ご"),
+    ("gu", "ぐ"),
+    ("ha", "は"),
+    ("he", "へ"),
+    ("hi", "ひ"),
+    ("ho", "ほ"),
+    ("hu", "ふ"),
+    ("ja", "じゃ"),
+    ("je", "じぇ"),
+    ("ji", "じ"),
+    ("jo", "じょ"),
+    ("ju", "じゅ"),
+    ("ka", "か"),
+    ("ke", "け"),
+    ("ki", "き"),
+    ("ko", "こ"),
+    ("ku", "く"),
+    ("ma", "ま"),
+    ("me", "め"),
+    ("mi", "み"),
+    ("mo", "も"),
+    ("mu", "む"),
+    ("na", "な"),
+    ("ne", "ね"),
+    ("ni", "に"),
+    ("no", "の"),
+    ("nu", "ぬ"),
+    ("pa", "ぱ"),
+    ("pe", "ぺ"),
+    ("pi", "ぴ"),
+    ("po", "ぽ"),
+    ("pu", "ぷ"),
+    ("ra", "ら"),
+    ("re", "れ"),
+    ("ri", "り"),
+    ("ro", "ろ"),
+    ("ru", "る"),
+    ("sa", "さ"),
+    ("se", "せ"),
+    ("si", "し"),
+    ("so", "そ"),
+    ("su", "す"),
+    ("ta", "た"),
+    ("te", "て"),
+    ("ti", "ち"),
+    ("to", "と"),
+    ("tu", "つ"),
+    ("wa", "わ"),
+    ("wo", "を"),
+    ("ya", "や"),
+    ("yo", "よ"),
+    ("yu", "ゆ"),
+    ("za", "ざ"),
+    ("ze", "ぜ"),
+    ("zi", "じ"),
+    ("zo", "ぞ"),
+    ("zu", "ず"),
+    ("a", "あ"),
+    ("e", "え"),
+    ("i", "い"),
+    ("o", "お"),
+    ("u", "う"),
+];
+
+/// 利用可能なキャストの一覧と照合したキャスト名
+///
+/// `CevioAI::validate_cast`などの検証からのみ作成できます。
+///
+/// # Example
+///
+/// ```no_run
+/// use cevio_ai::CevioAI;
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let cevio = CevioAI::new()?;
+/// let cast = cevio.validate_cast("さとうささら")?;
+/// cevio.set_cast(&cast)?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize), serde(transparent))]
+pub struct CastName(String);
+
+impl CastName {
+    /// キャスト名を一覧と照合します。
+    ///
+    /// # Errors
+    ///
+    /// 一覧にない場合は候補を添えた `CevioAIError::UnknownCast` を返します。
+    pub(crate) fn validate(requested: &str, casts: &[String]) -> Result<Self> {
+        if casts.iter().any(|cast| cast == requested) {
+            return Ok(Self(requested.to_string()));
+        }
+        Err(CevioAIError::UnknownCast {
+            requested: requested.to_string(),
+            suggestions: suggestions(requested, casts),
+        })
+    }
+
+    /// キャスト名を取得します。
+    #[must_use]
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl AsRef<str> for CastName {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for CastName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl From<CastName> for String {
+    fn from(name: CastName) -> Self {
+        name.0
+    }
+}
+
+/// 一覧から`requested`に近いキャスト名を、近い順に返します。
+///
+/// 表記を揃えた上で、一方が他方を含むものを優先し、次に編集距離が名前の長さの1/3以下のものを返します。
+fn suggestions(requested: &str, casts: &[String]) -> Vec<String> {
+    let requested = normalize(requested);
+    let length = requested.chars().count();
+    if length == 0 {
+        return Vec::new();
+    }
+    let threshold = (length / 3).max(1);
+
+    let mut scored: Vec<(bool, usize, &String)> = casts
+        .iter()
+        .filter_map(|cast| {
+            let normalized = normalize(cast);
+            let contains =
+                length >= 2 && (normalized.contains(&requested) || requested.contains(&normalized));
+            let distance = edit_distance(&requested, &normalized);
+            (contains || distance <= threshold).then_some((!contains, distance, cast))
+        })
+        .collect();
+    scored.sort();
+    scored
+        .into_iter()
+        .take(MAX_SUGGESTIONS)
+        .map(|(_, _, cast)| cast.clone())
+        .collect()
+}
+
+/// 比較のために表記を揃えます。
+///
+/// 英字を小文字に、全角英数字を半角に、カタカナとローマ字をひらがなにし、
+/// 空白と区切り記号を取り除きます。「づ」「ぢ」は「ず」「じ」とみなします。
+fn normalize(name: &str) -> String {
+    let folded: String = name
+        .chars()
+        .filter_map(|c| {
+            let c = match c {
+                // 全角英数字
+                '\u{FF01}'..='\u{FF5E}' => char::from_u32(c as u32 - 0xFEE0).unwrap_or(c),
+                // カタカナ（ァ～ヶ）
+                '\u{30A1}'..='\u{30F6}' => char::from_u32(c as u32 - 0x60).unwrap_or(c),
+                _ => c,
+            };
+            (!c.is_whitespace() && !matches!(c, '・' | '_' | '-' | '.' | '\''))
+                .then(|| c.to_ascii_lowercase())
+        })
+        .collect();
+    romaji_to_hiragana(&folded)
+        .replace('づ', "ず")
+        .replace('ぢ', "じ")
+}
+
+/// ローマ字をひらがなにします（ヘボン式・訓令式）。ローマ字以外の文字はそのまま残します。
+fn romaji_to_hiragana(text: &str) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let mut kana = String::with_capacity(text.len());
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        let next = chars.get(i + 1).copied();
+
+        if !c.is_ascii_lowercase() {
+            kana.push(c);
+            i += 1;
+            continue;
+        }
+
+        // 促音（"tt" → "っt"）
+        if next == Some(c) && !matches!(c, 'a' | 'i' | 'u' | 'e' | 'o' | 'n') {
+            kana.push('っ');
+            i += 1;
+            continue;
+        }
+
+        let matched = (1..=3).rev().find_map(|len| {
+            let syllable: String = chars.get(i..i + len)?.iter().collect();
+            ROMAJI
+                .iter()
+                .find(|(romaji, _)| *romaji == syllable)
+                .map(|(_, kana)| (len, *kana))
+        });
+        match matched {
+            Some((len, syllable)) => {
+                kana.push_str(syllable);
+                i += len;
+            }
+            // 撥音（母音と"y"が続かない"n"）
+            None if c == 'n' => {
+                kana.push('ん');
+                i += 1;
+            }
+            None => {
+                kana.push(c);
+                i += 1;
+            }
+        }
+    }
+
+    kana
+}
+
+/// 文字単位のレーベンシュタイン距離
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    let mut current = vec![0; b.len() + 1];
+
+    for (i, ca) in a.chars().enumerate() {
+        current[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != *cb);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+
+    previous[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn casts() -> Vec<String> {
+        ["さとうささら", "すずきつづみ", "タカハシ", "小春六花", "IA"]
+            .iter()
+            .map(|cast| (*cast).to_string())
+            .collect()
+    }
+
+    fn unknown(requested: &str) -> Vec<String> {
+        match CastName::validate(requested, &casts()) {
+            Err(CevioAIError::UnknownCast {
+                requested: name,
+                suggestions,
+            }) => {
+                assert_eq!(name, requested);
+                suggestions
+            }
+            other => panic!("expected UnknownCast, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn accepts_exact_name() {
+        let name = CastName::validate("タカハシ", &casts()).unwrap();
+        assert_eq!(name.as_str(), "タカハシ");
+    }
+
+    #[test]
+    fn suggests_from_romaji_and_katakana() {
+        assert_eq!(unknown("sasara"), ["さとうささら"]);
+        assert_eq!(unknown("Satou Sasara"), ["さとうささら"]);
+        assert_eq!(unknown("サトウササラ"), ["さとうささら"]);
+        assert_eq!(unknown("suzuki tsuzumi"), ["すずきつづみ"]);
+        assert_eq!(unknown("takahashi"), ["タカハシ"]);
+    }
+
+    #[test]
+    fn suggests_from_typos() {
+        assert_eq!(unknown("さとうさらら"), ["さとうささら"]);
+        assert_eq!(unknown("小春六華"), ["小春六花"]);
+        assert_eq!(unknown("ＩＡ"), ["IA"]);
+        assert!(unknown("ずんだもん").is_empty());
+    }
+
+    #[test]
+    fn converts_romaji() {
+        assert_eq!(romaji_to_hiragana("kinkakuji"), "きんかくじ");
+        assert_eq!(romaji_to_hiragana("kitte"), "きって");
+        assert_eq!(romaji_to_hiragana("shunna"), "しゅんな");
+        assert_eq!(edit_distance("さとう", "さと"), 1);
+    }
+}
@@ -5,12 +5,13 @@
 //! 音声合成、パラメータ制御、キャスト管理などの機能を提供します。
 
 use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
-use std::time::Duration;
-
-use parking_lot::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
 
 use derive_builder::Builder;
+use parking_lot::Mutex;
 use windows::{
     core::BSTR,
     Win32::{
@@ -23,13 +24,19 @@ use windows::{
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    com_manager::ComGuard,
+    audio::AudioBuffer,
+    cast_name::CastName,
+    com_manager::{ApartmentModel, ComGuard},
     error::{CevioAIError, Result},
+    late_bound::LateBoundHandler,
     parameter::{Alpha, Speed, Tone, ToneScale, VoicePreset, Volume},
+    worker::{
+        respond, ComponentEntry, Handle, HandleTable, Handler, Request, TalkerParameter, Worker,
+    },
 };
 use cevio_ai_sys::{
-    IServiceControl2V40, ISpeakingState2, ITalker2V40, ITalkerComponent2, ServiceControl2V40,
-    Talker2V40,
+    ComArray, IServiceControl2, IServiceControl2V40, ISpeakingState2, ITalker2, ITalker2V40,
+    ITalkerComponent2, ServiceControl2, ServiceControl2V40, Talker2, Talker2V40,
 };
 
 /// CeVIO AI初期化設定
@@ -88,12 +95,59 @@ pub struct CevioAIConfig {
     pub initial_alpha: Option<Alpha>,
 
     /// COM操作のタイムアウト時間（デフォルト: 5秒）
+    ///
+    /// 超えた場合は`CevioAIError::Timeout`を返し、インスタンスは使用不能になります。
+    /// `CevioAI::rebuild`で同じ設定のインスタンスを作り直せます。
+    /// 初期化とホストの起動を待つ`start(false)`には60秒以上が適用されます。
+    /// `synthesize`・`phonemes`・`text_duration`などの合成を伴う操作には、セリフ1文字につき0.2秒が加算されます。
     pub operation_timeout: Option<Duration>,
+
+    /// ワーカースレッドのCOMアパートメントモデル（デフォルト: MTA）
+    pub apartment: ApartmentModel,
+
+    /// 接続する製品（デフォルト: CeVIO AI）
+    pub product: Product,
+
+    /// COMインターフェースの呼び出し方式（デフォルト: vtable）
+    pub binding: Binding,
+}
+
+/// 接続する製品
+///
+/// どちらの製品でも`Cast`・`Component`・`PhonemeData`などの型は共通です。
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Product {
+    /// CeVIO AI（`CeVIO.Talk.RemoteService2`）
+    #[default]
+    AI,
+
+    /// CeVIO Creative Studio 7（`CeVIO.Talk.RemoteService`）
+    ///
+    /// タイプライブラリのメタデータがないためvtableのバインディングは提供しておらず、
+    /// `Binding::LateBound`が必要です。
+    CreativeStudio,
+}
+
+/// COMインターフェースの呼び出し方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Binding {
+    /// winmdから生成したバインディングでvtableを直接呼び出します。
+    #[default]
+    Vtable,
+
+    /// `IDispatch`でメンバー名から呼び出します。
+    ///
+    /// vtableの並びに依存しないため、未知のバージョンのホストでも安全に呼び出せます。
+    /// 呼び出しごとのオーバーヘッドはvtableより大きくなります。
+    LateBound,
 }
 
 /// CeVIO AI終了モード
 ///
 /// CeVIO AIに終了を要求する際の処理モードを指定します。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum CloseMode {
     /// 編集中の場合、保存や終了キャンセルが可能
     ///
@@ -143,7 +197,11 @@ impl HostStartResult {
 ///
 /// # Thread Safety
 ///
-/// 内部でArcとMutexを使用しているため、スレッド間で安全に共有できます。
+/// COMオブジェクトはすべて専用のワーカースレッドが所有し、
+/// このハンドルはチャネル経由で操作を依頼します。
+/// `Send + Sync`であり、`clone()`したハンドル同士は同じワーカースレッドを共有します。
+/// 最後のハンドル（および`Component`・`SpeakingState`）が破棄されると、
+/// ワーカースレッド上でCOMオブジェクトが解放されます。
 ///
 /// # Example
 ///
@@ -172,35 +230,92 @@ impl HostStartResult {
 /// ```
 #[derive(Clone)]
 pub struct CevioAI {
-    service: Arc<Mutex<IServiceControl2V40>>,
-    talker: Arc<Mutex<ITalker2V40>>,
-    _com_guard: Arc<ComGuard>,
+    worker: Arc<Worker>,
+    capabilities: Capabilities,
+    config: Arc<CevioAIConfig>,
+    /// `validate_cast`で照合するキャスト名の一覧
+    casts: Arc<Mutex<Option<Vec<String>>>>,
 }
 
+/// `CevioAIConfig::operation_timeout`を指定しない場合のタイムアウト時間
+pub const DEFAULT_OPERATION_TIMEOUT: Duration = Duration::from_secs(5);
+
 impl CevioAI {
     /// CeVIO AIインスタンスを作成します。
     ///
-    /// COM初期化とCeVIO AIのCOMオブジェクトを作成します。
+    /// ワーカースレッドを起動し、そのスレッド上でCOM初期化とCeVIO AIのCOMオブジェクトを作成します。
     /// CeVIO AIが起動していない場合でもインスタンスは作成されます。
     ///
+    /// V40インターフェースが登録されていない古いホストでは、従来のインターフェースを使用します。
+    /// 使用できる機能は `capabilities()` で確認できます。
+    ///
     /// # Errors
     ///
+    /// - ワーカースレッドの起動に失敗した場合
     /// - COM初期化に失敗した場合
     /// - COMオブジェクトの作成に失敗した場合
     pub fn new() -> Result<Self> {
-        let com_guard = ComGuard::new()?;
+        Self::spawn(CevioAIConfig::default())
+    }
 
-        unsafe {
-            let service: IServiceControl2V40 =
-                CoCreateInstance(&ServiceControl2V40, None, CLSCTX_INPROC_SERVER)?;
-            let talker: ITalker2V40 = CoCreateInstance(&Talker2V40, None, CLSCTX_INPROC_SERVER)?;
-
-            Ok(Self {
-                service: Arc::new(Mutex::new(service)),
-                talker: Arc::new(Mutex::new(talker)),
-                _com_guard: Arc::new(com_guard),
-            })
-        }
+    /// 設定のアパートメントモデルでワーカースレッドを起動し、設定の製品に接続します。
+    fn spawn(config: CevioAIConfig) -> Result<Self> {
+        let (apartment, product) = (config.apartment, config.product);
+        let timeout = config
+            .operation_timeout
+            .unwrap_or(DEFAULT_OPERATION_TIMEOUT);
+        let worker = match (config.binding, product) {
+            (Binding::Vtable, Product::AI) => {
+                Worker::spawn(timeout, apartment, move || ComHandler::new(apartment))?
+            }
+            (Binding::Vtable, Product::CreativeStudio) => {
+                return Err(CevioAIError::Unsupported(
+                    "CeVIO Creative Studio 7 requires Binding::LateBound".to_string(),
+                ))
+            }
+            (Binding::LateBound, _) => Worker::spawn(timeout, apartment, move || {
+                LateBoundHandler::new(apartment, product)
+            })?,
+        };
+        Self::from_worker(worker, config)
+    }
+
+    /// 起動済みのワーカースレッドから、対応機能を問い合わせてインスタンスを作成します。
+    fn from_worker(worker: Worker, config: CevioAIConfig) -> Result<Self> {
+        let capabilities = worker.call(|reply| Request::Capabilities { reply })?;
+        Ok(Self {
+            worker: Arc::new(worker),
+            capabilities,
+            config: Arc::new(config),
+            casts: Arc::default(),
+        })
+    }
+
+    /// 操作のタイムアウトにより使用不能になっているかどうかを返します。
+    ///
+    /// 使用不能なインスタンス（複製を含む）の操作はすべて`CevioAIError::Poisoned`を返します。
+    #[must_use]
+    pub fn is_poisoned(&self) -> bool {
+        self.worker.is_poisoned()
+    }
+
+    /// 作成時と同じ設定で新しいインスタンスを作成します。
+    ///
+    /// タイムアウトにより使用不能になったインスタンスの代わりに使用します。
+    /// 新しいワーカースレッドでCOMオブジェクトを作り直し、設定の初期キャストとパラメータを再び適用します。
+    /// 元のインスタンスから取得した`Component`や`SpeakingState`は引き続き使用できません。
+    ///
+    /// # Errors
+    ///
+    /// `with_config`と同じです。
+    pub fn rebuild(&self) -> Result<Self> {
+        Self::with_config((*self.config).clone())
+    }
+
+    /// 接続先のCeVIO AIが対応している機能を取得します。
+    #[must_use]
+    pub const fn capabilities(&self) -> Capabilities {
+        self.capabilities
     }
 
     /// 設定を指定してCeVIO AIインスタンスを作成します。
@@ -213,18 +328,20 @@ impl CevioAI {
     ///
     /// # Errors
     ///
-    /// - インスタンス作成に失敗した場合
+    /// - インスタンス作成に失敗した場合（CeVIO AIが未インストールの場合は
+    ///   `ComErrorKind::NotInstalled`の`CevioAIError::Com`）
     /// - CeVIO AI起動に失敗した場合（`start_host`が`true`の場合）
     /// - パラメータ設定に失敗した場合
+    /// - `Product::CreativeStudio`に`Binding::Vtable`を指定した場合は`CevioAIError::Unsupported`
     pub fn with_config(config: CevioAIConfig) -> Result<Self> {
-        let cevio = Self::new()?;
+        let cevio = Self::spawn(config.clone())?;
 
         if config.start_host {
             cevio.start(config.no_wait)?;
         }
 
         if let Some(cast) = config.initial_cast {
-            cevio.set_cast(&cast)?;
+            cevio.set_cast(&cevio.validate_cast(&cast)?)?;
         }
 
         if let Some(volume) = config.initial_volume {
@@ -266,7 +383,9 @@ impl CevioAI {
     /// - `CevioAIError::ProcessStartFailed` - プロセスの起動に失敗
     /// - `CevioAIError::AppTerminated` - アプリケーション起動後、エラーにより終了
     pub fn start(&self, no_wait: bool) -> Result<()> {
-        let result = unsafe { self.service.lock().StartHost(VARIANT_BOOL::from(no_wait)) }?;
+        let result = self
+            .worker
+            .call(|reply| Request::StartHost { no_wait, reply })?;
         match HostStartResult::from_i32(result) {
             HostStartResult::Succeeded => Ok(()),
             HostStartResult::NotRegistered => Err(CevioAIError::InstallUnknown),
@@ -282,8 +401,43 @@ impl CevioAI {
     ///
     /// * `mode` - 終了モード
     pub fn close(&self, mode: CloseMode) -> Result<()> {
-        unsafe { self.service.lock().CloseHost(mode as i32) }?;
-        Ok(())
+        self.worker.call(|reply| Request::CloseHost { mode, reply })
+    }
+
+    /// CeVIO AIのバージョンを取得します。
+    ///
+    /// # Returns
+    ///
+    /// バージョン文字列（例："9.1.16.0"）
+    ///
+    /// # Errors
+    ///
+    /// 従来のインターフェースで接続している場合は `CevioAIError::Unsupported` を返します。
+    pub fn host_version(&self) -> Result<String> {
+        self.worker.call(|reply| Request::HostVersion { reply })
+    }
+
+    /// CeVIO AIのCOMインターフェースのバージョンを取得します。
+    ///
+    /// # Returns
+    ///
+    /// バージョン文字列
+    ///
+    /// # Errors
+    ///
+    /// 従来のインターフェースで接続している場合は `CevioAIError::Unsupported` を返します。
+    pub fn interface_version(&self) -> Result<String> {
+        self.worker
+            .call(|reply| Request::InterfaceVersion { reply })
+    }
+
+    /// CeVIO AIが起動中かどうかを取得します。
+    ///
+    /// # Returns
+    ///
+    /// 起動中の場合は`true`、それ以外の場合は`false`
+    pub fn is_host_started(&self) -> Result<bool> {
+        self.worker.call(|reply| Request::IsHostStarted { reply })
     }
 
     /// 現在の音量を取得します。
@@ -292,7 +446,7 @@ impl CevioAI {
     ///
     /// 音の大きさ（0～100）
     pub fn volume(&self) -> Result<Volume> {
-        let value = unsafe { self.talker.lock().Volume() }? as u8;
+        let value = self.parameter(TalkerParameter::Volume)?;
         Ok(Volume::new(value).expect("CeVIO returned invalid volume"))
     }
 
@@ -302,7 +456,7 @@ impl CevioAI {
     ///
     /// 話す速さ（0～100）
     pub fn speed(&self) -> Result<Speed> {
-        let value = unsafe { self.talker.lock().Speed() }? as u8;
+        let value = self.parameter(TalkerParameter::Speed)?;
         Ok(Speed::new(value).expect("CeVIO returned invalid speed"))
     }
 
@@ -312,7 +466,7 @@ impl CevioAI {
     ///
     /// 音の高さ（0～100）
     pub fn tone(&self) -> Result<Tone> {
-        let value = unsafe { self.talker.lock().Tone() }? as u8;
+        let value = self.parameter(TalkerParameter::Tone)?;
         Ok(Tone::new(value).expect("CeVIO returned invalid tone"))
     }
 
@@ -321,8 +475,12 @@ impl CevioAI {
     /// # Returns
     ///
     /// 抑揚（0～100）
+    ///
+    /// # Errors
+    ///
+    /// 従来のインターフェースで接続している場合は `CevioAIError::Unsupported` を返します。
     pub fn tone_scale(&self) -> Result<ToneScale> {
-        let value = unsafe { self.talker.lock().ToneScale() }? as u8;
+        let value = self.parameter(TalkerParameter::ToneScale)?;
         Ok(ToneScale::new(value).expect("CeVIO returned invalid tone scale"))
     }
 
@@ -332,17 +490,34 @@ impl CevioAI {
     ///
     /// 声質（0～100）
     pub fn alpha(&self) -> Result<Alpha> {
-        let value = unsafe { self.talker.lock().Alpha() }? as u8;
+        let value = self.parameter(TalkerParameter::Alpha)?;
         Ok(Alpha::new(value).expect("CeVIO returned invalid alpha"))
     }
 
+    /// 音声パラメータの値を取得します。
+    fn parameter(&self, parameter: TalkerParameter) -> Result<u8> {
+        let value = self
+            .worker
+            .call(|reply| Request::Parameter { parameter, reply })?;
+        Ok(value as u8)
+    }
+
+    /// 音声パラメータの値を設定します。
+    fn set_parameter(&self, parameter: TalkerParameter, value: u8) -> Result<()> {
+        self.worker.call(|reply| Request::SetParameter {
+            parameter,
+            value: u32::from(value),
+            reply,
+        })
+    }
+
     /// 音量を設定します。
     ///
     /// # Arguments
     ///
     /// * `volume` - 音の大きさ（0～100）
     fn set_volume(&self, volume: Volume) -> Result<()> {
-        Ok(unsafe { self.talker.lock().SetVolume(u32::from(volume.get())) }?)
+        self.set_parameter(TalkerParameter::Volume, volume.get())
     }
 
     /// 話す速さを設定します。
@@ -351,7 +526,7 @@ impl CevioAI {
     ///
     /// * `speed` - 話す速さ（0～100）
     fn set_speed(&self, speed: Speed) -> Result<()> {
-        Ok(unsafe { self.talker.lock().SetSpeed(u32::from(speed.get())) }?)
+        self.set_parameter(TalkerParameter::Speed, speed.get())
     }
 
     /// 音の高さを設定します。
@@ -360,7 +535,7 @@ impl CevioAI {
     ///
     /// * `tone` - 音の高さ（0～100）
     fn set_tone(&self, tone: Tone) -> Result<()> {
-        Ok(unsafe { self.talker.lock().SetTone(u32::from(tone.get())) }?)
+        self.set_parameter(TalkerParameter::Tone, tone.get())
     }
 
     /// 抑揚を設定します。
@@ -369,7 +544,7 @@ impl CevioAI {
     ///
     /// * `tone_scale` - 抑揚（0～100）
     fn set_tone_scale(&self, tone_scale: ToneScale) -> Result<()> {
-        Ok(unsafe { self.talker.lock().SetToneScale(u32::from(tone_scale.get())) }?)
+        self.set_parameter(TalkerParameter::ToneScale, tone_scale.get())
     }
 
     /// 声質を設定します。
@@ -378,7 +553,7 @@ impl CevioAI {
     ///
     /// * `alpha` - 声質（0～100）
     fn set_alpha(&self, alpha: Alpha) -> Result<()> {
-        Ok(unsafe { self.talker.lock().SetAlpha(u32::from(alpha.get())) }?)
+        self.set_parameter(TalkerParameter::Alpha, alpha.get())
     }
 
     /// 現在のキャストの感情パラメータマップを取得します。
@@ -393,22 +568,33 @@ impl CevioAI {
     ///
     /// - 『さとうささら』→ "普通", "元気", "怒り", "哀しみ"
     pub fn components(&self) -> Result<Vec<Component>> {
-        let talker_components = unsafe { self.talker.lock().Components() }?;
-
-        let len = unsafe { talker_components.Length() }?;
-        let mut components = Vec::with_capacity(len as usize);
-
-        for i in 0..len {
-            let component = unsafe {
-                let talker_component = talker_components.At(i)?;
-                let id = talker_component.Id()?.to_string();
-                let name = talker_component.Name()?.to_string();
-                Component::new(talker_component, &id, &name)
-            };
-            components.push(component);
-        }
+        let entries = self.worker.call(|reply| Request::Components { reply })?;
+        Ok(entries
+            .into_iter()
+            .map(|entry| Component {
+                remote: Arc::new(Remote::new(&self.worker, entry.handle)),
+                id: entry.id,
+                name: entry.name,
+            })
+            .collect())
+    }
 
-        Ok(components)
+    /// 現在のキャストの感情パラメータとその値を取得します。
+    ///
+    /// # Returns
+    ///
+    /// 感情パラメータの値のリスト
+    pub fn component_values(&self) -> Result<Vec<ComponentValue>> {
+        self.components()?
+            .into_iter()
+            .map(|component| {
+                Ok(ComponentValue {
+                    value: component.value()?,
+                    id: component.id,
+                    name: component.name,
+                })
+            })
+            .collect()
     }
 
     /// 現在のキャストを取得します。
@@ -417,36 +603,85 @@ impl CevioAI {
     ///
     /// 現在設定されているキャスト名
     pub fn cast(&self) -> Result<String> {
-        Ok(unsafe { self.talker.lock().Cast() }?.to_string())
+        self.worker.call(|reply| Request::Cast { reply })
+    }
+
+    /// 現在のキャストと音声パラメータを取得します。
+    ///
+    /// # Returns
+    ///
+    /// すべての項目が設定されたキャスト設定。
+    /// 抑揚に対応していないホストでは`tone_scale`のみ`None`になります。
+    pub fn current_cast(&self) -> Result<Cast> {
+        let tone_scale = if self.capabilities.has_tone_scale {
+            Some(self.tone_scale()?)
+        } else {
+            None
+        };
+
+        Ok(Cast {
+            cast: Some(self.cast()?),
+            volume: Some(self.volume()?),
+            speed: Some(self.speed()?),
+            tone: Some(self.tone()?),
+            tone_scale,
+            alpha: Some(self.alpha()?),
+        })
     }
 
     /// キャストを設定します。
     ///
     /// # Arguments
     ///
-    /// * `cast` - キャスト名
-    fn set_cast(&self, cast: &str) -> Result<()> {
-        Ok(unsafe { self.talker.lock().SetCast(&BSTR::from(cast)) }?)
+    /// * `cast` - `validate_cast`で照合したキャスト名
+    pub fn set_cast(&self, cast: &CastName) -> Result<()> {
+        self.worker.call(|reply| Request::SetCast {
+            cast: cast.to_string(),
+            reply,
+        })
     }
 
     /// 利用可能なキャスト名を取得します。
     ///
     /// キャストの取り揃えは、インストールされている音源によります。
+    /// 取得した一覧は`validate_cast`での照合に使用されます。
     ///
     /// # Returns
     ///
     /// 利用可能なキャスト名のリスト
     pub fn available_casts(&self) -> Result<Vec<String>> {
-        let strings = unsafe { self.talker.lock().AvailableCasts() }?;
-
-        let len = unsafe { strings.Length() }?;
-        let mut casts = Vec::with_capacity(len as usize);
+        let casts = self
+            .worker
+            .call(|reply| Request::AvailableCasts { reply })?;
+        *self.casts.lock() = Some(casts.clone());
+        Ok(casts)
+    }
 
-        for i in 0..len {
-            casts.push(unsafe { strings.At(i) }?.to_string());
+    /// キャスト名を利用可能なキャストの一覧と照合します。
+    ///
+    /// 一覧は最初の照合時に取得して保持します。音源を追加した後は`available_casts`で更新してください。
+    ///
+    /// # Errors
+    ///
+    /// 一覧にない場合は、表記ゆれや入力ミスから推定した候補を添えた`CevioAIError::UnknownCast`を返します。
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use cevio_ai::{CevioAI, CevioAIError};
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let cevio = CevioAI::new()?;
+    /// if let Err(CevioAIError::UnknownCast { suggestions, .. }) = cevio.validate_cast("sasara") {
+    ///     println!("候補: {suggestions:?}"); // ["さとうささら"]
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn validate_cast(&self, name: &str) -> Result<CastName> {
+        if let Some(casts) = self.casts.lock().as_deref() {
+            return CastName::validate(name, casts);
         }
-
-        Ok(casts)
+        CastName::validate(name, &self.available_casts()?)
     }
 
     /// 指定したセリフの再生を開始します。
@@ -474,8 +709,13 @@ impl CevioAI {
     /// # }
     /// ```
     pub fn speak(&self, text: &str) -> Result<SpeakingState> {
-        let speak_state = unsafe { self.talker.lock().Speak(&BSTR::from(text)) }?;
-        Ok(SpeakingState::new(speak_state))
+        let handle = self.worker.call(|reply| Request::Speak {
+            text: text.to_string(),
+            reply,
+        })?;
+        Ok(SpeakingState {
+            remote: Remote::new(&self.worker, handle),
+        })
     }
 
     /// 再生を停止します。
@@ -484,7 +724,7 @@ impl CevioAI {
     ///
     /// 成功した場合は`true`、それ以外の場合は`false`
     pub fn stop(&self) -> Result<bool> {
-        Ok(unsafe { self.talker.lock().Stop() }?.as_bool())
+        self.worker.call(|reply| Request::Stop { reply })
     }
 
     /// 指定したセリフの長さを取得します。
@@ -497,7 +737,10 @@ impl CevioAI {
     ///
     /// 長さ（単位は秒）
     pub fn text_duration(&self, text: &str) -> Result<f64> {
-        Ok(unsafe { self.talker.lock().GetTextDuration(&BSTR::from(text)) }?)
+        self.worker.call(|reply| Request::TextDuration {
+            text: text.to_string(),
+            reply,
+        })
     }
 
     /// 指定したセリフの音素単位のデータを取得します。
@@ -512,22 +755,10 @@ impl CevioAI {
     ///
     /// 音素単位のデータのリスト
     pub fn phonemes(&self, text: &str) -> Result<Vec<PhonemeData>> {
-        let phoneme_datas = unsafe { self.talker.lock().GetPhonemes(&BSTR::from(text)) }?;
-
-        let len = unsafe { phoneme_datas.Length() }?;
-        let mut phonemes = Vec::with_capacity(len as usize);
-
-        for i in 0..len {
-            unsafe {
-                let data = phoneme_datas.At(i)?;
-                let phoneme = data.Phoneme()?.to_string();
-                let start_time = data.StartTime()?;
-                let end_time = data.EndTime()?;
-                phonemes.push(PhonemeData::new(phoneme, start_time, end_time));
-            }
-        }
-
-        Ok(phonemes)
+        self.worker.call(|reply| Request::Phonemes {
+            text: text.to_string(),
+            reply,
+        })
     }
 
     /// 指定したセリフをWAVファイルとして出力します。
@@ -546,13 +777,42 @@ impl CevioAI {
     ///
     /// 成功した場合は`true`、それ以外の場合は`false`
     pub fn output_wave_to_file<P: AsRef<Path>>(&self, text: &str, path: P) -> Result<bool> {
-        let path_str = path.as_ref().to_string_lossy();
-        Ok(unsafe {
-            self.talker
-                .lock()
-                .OutputWaveToFile(&BSTR::from(text), &BSTR::from(path_str.as_ref()))
-        }?
-        .as_bool())
+        self.worker.call(|reply| Request::OutputWaveToFile {
+            text: text.to_string(),
+            path: path.as_ref().to_path_buf(),
+            reply,
+        })
+    }
+
+    /// 指定したセリフを合成し、音声データとして取得します。
+    ///
+    /// 一時ファイルにWAVを出力し、読み込んだ後に削除します。
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - セリフ
+    ///
+    /// # Errors
+    ///
+    /// - `CevioAIError::WaveOutputFailed` - CeVIO AIがWAVの出力に失敗した場合
+    /// - `CevioAIError::Io` - 一時ファイルの読み込みに失敗した場合
+    pub fn synthesize(&self, text: &str) -> Result<AudioBuffer> {
+        static COUNTER: AtomicU64 = AtomicU64::new(0);
+
+        let path = std::env::temp_dir().join(format!(
+            "cevio-ai-{}-{}.wav",
+            std::process::id(),
+            COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+
+        if !self.output_wave_to_file(text, &path)? {
+            let _ = std::fs::remove_file(&path);
+            return Err(CevioAIError::WaveOutputFailed);
+        }
+
+        let buffer = AudioBuffer::from_wav_file(&path);
+        let _ = std::fs::remove_file(&path);
+        buffer
     }
 
     /// キャスト設定を一括で適用します。
@@ -560,9 +820,14 @@ impl CevioAI {
     /// # Arguments
     ///
     /// * `cast` - 適用するキャスト設定
+    ///
+    /// # Errors
+    ///
+    /// - キャスト名が利用可能なキャストにない場合は `CevioAIError::UnknownCast`
+    /// - 抑揚に対応していないホストで`tone_scale`を指定した場合は `CevioAIError::Unsupported`
     pub fn apply_cast(&self, cast: &Cast) -> Result<()> {
         if let Some(ref cast) = cast.cast {
-            self.set_cast(cast)?;
+            self.set_cast(&self.validate_cast(cast)?)?;
         }
 
         if let Some(volume) = cast.volume {
@@ -587,6 +852,363 @@ impl CevioAI {
 
         Ok(())
     }
+
+    /// 感情パラメータの値を名前で一括設定します。
+    ///
+    /// # Arguments
+    ///
+    /// * `values` - 感情の名前と値（0～100）の組
+    ///
+    /// # Errors
+    ///
+    /// 現在のキャストに存在しない感情が指定された場合、
+    /// または値が100を超える場合は `CevioAIError::InvalidParameter` を返します。
+    pub fn apply_components<S: AsRef<str>>(&self, values: &[(S, u8)]) -> Result<()> {
+        if values.is_empty() {
+            return Ok(());
+        }
+
+        let components = self.components()?;
+        for (name, value) in values {
+            let name = name.as_ref();
+            let component = components
+                .iter()
+                .find(|component| component.name == name)
+                .ok_or_else(|| {
+                    CevioAIError::InvalidParameter(format!("Unknown component: {name}"))
+                })?;
+            component.set_value(*value)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// 制御インターフェース
+enum Service {
+    V40(IServiceControl2V40),
+    Legacy(IServiceControl2),
+}
+
+/// トークインターフェース
+enum Talker {
+    V40(ITalker2V40),
+    Legacy(ITalker2),
+}
+
+/// V40と従来のインターフェースで共通のメソッドを呼び出します。
+macro_rules! dispatch {
+    ($kind:ident, $value:expr, |$inner:ident| $body:expr) => {
+        match $value {
+            $kind::V40($inner) => $body,
+            $kind::Legacy($inner) => $body,
+        }
+    };
+}
+
+/// COMオブジェクトを作成できなかったエラーに、V40のクラス名を添えます。
+fn creation_error(source: windows::core::Error, class: &str) -> CevioAIError {
+    CevioAIError::from(source).with_context("CreateInstance", vec![class.to_string()])
+}
+
+/// ワーカースレッド上でCOMオブジェクトを所有し、リクエストを処理するハンドラ
+struct ComHandler {
+    service: Service,
+    talker: Talker,
+    components: HandleTable<ITalkerComponent2>,
+    states: HandleTable<ISpeakingState2>,
+    // COMオブジェクトの解放後にCOMを終了させるため、最後に宣言する
+    _com_guard: ComGuard,
+}
+
+impl ComHandler {
+    /// COMを初期化し、CeVIO AIのCOMオブジェクトを作成します。
+    fn new(apartment: ApartmentModel) -> Result<Self> {
+        let com_guard = ComGuard::new(apartment)?;
+        let (service, talker) = Self::create_ai()?;
+
+        Ok(Self {
+            service,
+            talker,
+            components: HandleTable::default(),
+            states: HandleTable::default(),
+            _com_guard: com_guard,
+        })
+    }
+
+    /// CeVIO AIのCOMオブジェクトを作成します。
+    ///
+    /// V40のクラスが登録されていない場合は従来のクラスを作成します。
+    /// どちらも作成できない場合はV40のエラーを返します。
+    fn create_ai() -> Result<(Service, Talker)> {
+        unsafe {
+            let service: windows::core::Result<IServiceControl2V40> =
+                CoCreateInstance(&ServiceControl2V40, None, CLSCTX_INPROC_SERVER);
+            let service = match service {
+                Ok(service) => Service::V40(service),
+                Err(e) => Service::Legacy(
+                    CoCreateInstance(&ServiceControl2, None, CLSCTX_INPROC_SERVER).map_err(
+                        |_| creation_error(e, "CeVIO.Talk.RemoteService2.ServiceControl2V40"),
+                    )?,
+                ),
+            };
+
+            let talker: windows::core::Result<ITalker2V40> =
+                CoCreateInstance(&Talker2V40, None, CLSCTX_INPROC_SERVER);
+            let talker = match talker {
+                Ok(talker) => Talker::V40(talker),
+                Err(e) => Talker::Legacy(
+                    CoCreateInstance(&Talker2, None, CLSCTX_INPROC_SERVER)
+                        .map_err(|_| creation_error(e, "CeVIO.Talk.RemoteService2.Talker2V40"))?,
+                ),
+            };
+
+            Ok((service, talker))
+        }
+    }
+
+    const fn capabilities(&self) -> Capabilities {
+        Capabilities {
+            has_tone_scale: !matches!(self.talker, Talker::Legacy(_)),
+            has_host_version: !matches!(self.service, Service::Legacy(_)),
+        }
+    }
+
+    fn start_host(&self, no_wait: bool) -> Result<i32> {
+        let no_wait = VARIANT_BOOL::from(no_wait);
+        Ok(dispatch!(Service, &self.service, |service| unsafe {
+            service.StartHost(no_wait)
+        })?)
+    }
+
+    fn close(&self, mode: CloseMode) -> Result<()> {
+        dispatch!(Service, &self.service, |service| unsafe {
+            service.CloseHost(mode as i32)
+        })?;
+        Ok(())
+    }
+
+    fn host_version(&self) -> Result<String> {
+        match &self.service {
+            Service::V40(service) => Ok(unsafe { service.HostVersion() }?.to_string()),
+            Service::Legacy(_) => Err(CevioAIError::Unsupported("HostVersion".to_string())),
+        }
+    }
+
+    fn interface_version(&self) -> Result<String> {
+        match &self.service {
+            Service::V40(service) => Ok(unsafe { service.InterfaceVersion() }?.to_string()),
+            Service::Legacy(_) => Err(CevioAIError::Unsupported("InterfaceVersion".to_string())),
+        }
+    }
+
+    fn is_host_started(&self) -> Result<bool> {
+        Ok(dispatch!(Service, &self.service, |service| unsafe {
+            service.IsHostStarted()
+        })?
+        .as_bool())
+    }
+
+    fn parameter(&self, parameter: TalkerParameter) -> Result<u32> {
+        Ok(unsafe {
+            match (parameter, &self.talker) {
+                (TalkerParameter::ToneScale, Talker::V40(talker)) => talker.ToneScale(),
+                (TalkerParameter::ToneScale, Talker::Legacy(_)) => {
+                    return Err(CevioAIError::Unsupported("ToneScale".to_string()));
+                }
+                (TalkerParameter::Volume, talker) => dispatch!(Talker, talker, |t| t.Volume()),
+                (TalkerParameter::Speed, talker) => dispatch!(Talker, talker, |t| t.Speed()),
+                (TalkerParameter::Tone, talker) => dispatch!(Talker, talker, |t| t.Tone()),
+                (TalkerParameter::Alpha, talker) => dispatch!(Talker, talker, |t| t.Alpha()),
+            }
+        }?)
+    }
+
+    fn set_parameter(&self, parameter: TalkerParameter, value: u32) -> Result<()> {
+        Ok(unsafe {
+            match (parameter, &self.talker) {
+                (TalkerParameter::ToneScale, Talker::V40(talker)) => talker.SetToneScale(value),
+                (TalkerParameter::ToneScale, Talker::Legacy(_)) => {
+                    return Err(CevioAIError::Unsupported("ToneScale".to_string()));
+                }
+                (TalkerParameter::Volume, talker) => {
+                    dispatch!(Talker, talker, |t| t.SetVolume(value))
+                }
+                (TalkerParameter::Speed, talker) => {
+                    dispatch!(Talker, talker, |t| t.SetSpeed(value))
+                }
+                (TalkerParameter::Tone, talker) => dispatch!(Talker, talker, |t| t.SetTone(value)),
+                (TalkerParameter::Alpha, talker) => {
+                    dispatch!(Talker, talker, |t| t.SetAlpha(value))
+                }
+            }
+        }?)
+    }
+
+    fn cast(&self) -> Result<String> {
+        Ok(dispatch!(Talker, &self.talker, |talker| unsafe { talker.Cast() })?.to_string())
+    }
+
+    fn set_cast(&self, cast: &str) -> Result<()> {
+        let cast = BSTR::from(cast);
+        Ok(dispatch!(Talker, &self.talker, |talker| unsafe {
+            talker.SetCast(&cast)
+        })?)
+    }
+
+    fn available_casts(&self) -> Result<Vec<String>> {
+        dispatch!(Talker, &self.talker, |talker| Ok(unsafe {
+            talker.AvailableCasts()
+        }?
+        .to_vec()?))
+    }
+
+    fn components(&mut self) -> Result<Vec<ComponentEntry>> {
+        dispatch!(Talker, &self.talker, |talker| {
+            let talker_components = unsafe { talker.Components() }?;
+
+            let mut entries = Vec::with_capacity(talker_components.len()?);
+            for component in talker_components.iter()? {
+                let component = component?;
+                let (id, name) =
+                    unsafe { (component.Id()?.to_string(), component.Name()?.to_string()) };
+                entries.push(ComponentEntry {
+                    handle: self.components.insert(component),
+                    id,
+                    name,
+                });
+            }
+
+            Ok(entries)
+        })
+    }
+
+    fn speak(&mut self, text: &str) -> Result<Handle> {
+        let text = BSTR::from(text);
+        let state = dispatch!(Talker, &self.talker, |talker| unsafe {
+            talker.Speak(&text)
+        })?;
+        Ok(self.states.insert(state))
+    }
+
+    fn stop(&self) -> Result<bool> {
+        Ok(dispatch!(Talker, &self.talker, |talker| unsafe { talker.Stop() })?.as_bool())
+    }
+
+    fn text_duration(&self, text: &str) -> Result<f64> {
+        let text = BSTR::from(text);
+        Ok(dispatch!(Talker, &self.talker, |talker| unsafe {
+            talker.GetTextDuration(&text)
+        })?)
+    }
+
+    fn phonemes(&self, text: &str) -> Result<Vec<PhonemeData>> {
+        let text = BSTR::from(text);
+        dispatch!(Talker, &self.talker, |talker| {
+            let phoneme_datas = unsafe { talker.GetPhonemes(&text) }?;
+
+            Ok(phoneme_datas
+                .to_vec()?
+                .into_iter()
+                .map(|data| PhonemeData::new(data.phoneme, data.start_time, data.end_time))
+                .collect())
+        })
+    }
+
+    fn output_wave_to_file(&self, text: &str, path: &Path) -> Result<bool> {
+        let text = BSTR::from(text);
+        let path = BSTR::from(path.to_string_lossy().as_ref());
+        Ok(dispatch!(Talker, &self.talker, |talker| unsafe {
+            talker.OutputWaveToFile(&text, &path)
+        })?
+        .as_bool())
+    }
+}
+
+impl Handler for ComHandler {
+    fn handle(&mut self, request: Request) {
+        match request {
+            Request::Capabilities { reply } => respond(reply, Ok(self.capabilities())),
+            Request::StartHost { no_wait, reply } => respond(reply, self.start_host(no_wait)),
+            Request::CloseHost { mode, reply } => respond(reply, self.close(mode)),
+            Request::HostVersion { reply } => respond(reply, self.host_version()),
+            Request::InterfaceVersion { reply } => respond(reply, self.interface_version()),
+            Request::IsHostStarted { reply } => respond(reply, self.is_host_started()),
+            Request::Parameter { parameter, reply } => respond(reply, self.parameter(parameter)),
+            Request::SetParameter {
+                parameter,
+                value,
+                reply,
+            } => respond(reply, self.set_parameter(parameter, value)),
+            Request::Cast { reply } => respond(reply, self.cast()),
+            Request::SetCast { cast, reply } => respond(reply, self.set_cast(&cast)),
+            Request::AvailableCasts { reply } => respond(reply, self.available_casts()),
+            Request::Components { reply } => respond(reply, self.components()),
+            Request::ComponentValue { component, reply } => respond(
+                reply,
+                self.components
+                    .get(component)
+                    .and_then(|component| Ok(unsafe { component.Value() }?)),
+            ),
+            Request::SetComponentValue {
+                component,
+                value,
+                reply,
+            } => respond(
+                reply,
+                self.components
+                    .get(component)
+                    .and_then(|component| Ok(unsafe { component.SetValue(value) }?)),
+            ),
+            Request::Speak { text, reply } => respond(reply, self.speak(&text)),
+            Request::Stop { reply } => respond(reply, self.stop()),
+            Request::IsCompleted { state, reply } => respond(
+                reply,
+                self.states
+                    .get(state)
+                    .and_then(|state| Ok(unsafe { state.IsCompleted() }?.as_bool())),
+            ),
+            Request::IsSucceeded { state, reply } => respond(
+                reply,
+                self.states
+                    .get(state)
+                    .and_then(|state| Ok(unsafe { state.IsSucceeded() }?.as_bool())),
+            ),
+            Request::TextDuration { text, reply } => respond(reply, self.text_duration(&text)),
+            Request::Phonemes { text, reply } => respond(reply, self.phonemes(&text)),
+            Request::OutputWaveToFile { text, path, reply } => {
+                respond(reply, self.output_wave_to_file(&text, &path));
+            }
+            Request::Release { handle } => {
+                self.components.remove(handle);
+                self.states.remove(handle);
+            }
+        }
+    }
+}
+
+/// ワーカースレッド上のCOMオブジェクトへの参照
+///
+/// 破棄されるとワーカースレッドに解放を要求します。
+#[derive(Debug)]
+struct Remote {
+    worker: Arc<Worker>,
+    handle: Handle,
+}
+
+impl Remote {
+    fn new(worker: &Arc<Worker>, handle: Handle) -> Self {
+        Self {
+            worker: Arc::clone(worker),
+            handle,
+        }
+    }
+}
+
+impl Drop for Remote {
+    fn drop(&mut self) {
+        self.worker.release(self.handle);
+    }
 }
 
 /// キャスト設定
@@ -673,9 +1295,12 @@ impl CastBuilder {
 ///
 /// キャストの感情を制御するパラメータです。
 /// 各キャストで利用可能な感情は異なります。
+///
+/// COMオブジェクトはワーカースレッド上に保持されるため、
+/// スレッド間で受け渡すことができます。
 #[derive(Debug, Clone)]
 pub struct Component {
-    inner: ITalkerComponent2,
+    remote: Arc<Remote>,
 
     /// 識別子
     pub id: String,
@@ -685,21 +1310,18 @@ pub struct Component {
 }
 
 impl Component {
-    fn new(component: ITalkerComponent2, id: &str, name: &str) -> Self {
-        Self {
-            inner: component,
-            id: id.to_string(),
-            name: name.to_string(),
-        }
-    }
-
     /// 感情の値を取得します。
     ///
     /// # Returns
     ///
     /// 感情の値（0～100）
     pub fn value(&self) -> Result<u8> {
-        Ok(unsafe { self.inner.Value() }? as u8)
+        let component = self.remote.handle;
+        let value = self
+            .remote
+            .worker
+            .call(|reply| Request::ComponentValue { component, reply })?;
+        Ok(value as u8)
     }
 
     /// 感情の値を設定します。
@@ -717,21 +1339,57 @@ impl Component {
                 "Component value must be 0-100, got {value}"
             )));
         }
-        Ok(unsafe { self.inner.SetValue(u32::from(value)) }?)
+        let component = self.remote.handle;
+        self.remote.worker.call(|reply| Request::SetComponentValue {
+            component,
+            value: u32::from(value),
+            reply,
+        })
     }
 }
 
+/// 感情パラメータの値
+///
+/// `Component`から取得した値を保持する、COMオブジェクトに依存しない構造体です。
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ComponentValue {
+    /// 識別子
+    pub id: String,
+
+    /// 感情の名前（例："普通", "元気", "怒り", "哀しみ"）
+    pub name: String,
+
+    /// 感情の値（0～100）
+    pub value: u8,
+}
+
+/// 接続先のCeVIO AIが対応している機能
+///
+/// V40インターフェースが登録されていない古いホストでは従来のインターフェースで接続するため、
+/// 一部の機能が使用できません。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Capabilities {
+    /// 抑揚（`tone_scale`）を取得・設定できるか
+    pub has_tone_scale: bool,
+
+    /// `host_version`・`interface_version`を取得できるか
+    pub has_host_version: bool,
+}
+
 /// 再生状態
 ///
 /// 音声の再生状態を管理し、再生の完了を待機できます。
+/// スレッド間で受け渡すことができます。
+#[derive(Debug)]
 pub struct SpeakingState {
-    state: ISpeakingState2,
+    remote: Remote,
 }
 
 impl SpeakingState {
-    const fn new(state: ISpeakingState2) -> Self {
-        Self { state }
-    }
+    /// 再生完了を確認する間隔
+    const POLL_INTERVAL: Duration = Duration::from_millis(10);
 
     /// 再生が完了したかどうかを取得します。
     ///
@@ -739,7 +1397,10 @@ impl SpeakingState {
     ///
     /// 完了した場合は`true`（失敗を含む）、それ以外の場合は`false`
     pub fn is_completed(&self) -> Result<bool> {
-        Ok(unsafe { self.state.IsCompleted() }?.as_bool())
+        let state = self.remote.handle;
+        self.remote
+            .worker
+            .call(|reply| Request::IsCompleted { state, reply })
     }
 
     /// 再生が成功したかどうかを取得します。
@@ -748,14 +1409,21 @@ impl SpeakingState {
     ///
     /// 成功した場合は`true`、それ以外の場合は`false`
     pub fn is_succeeded(&self) -> Result<bool> {
-        Ok(unsafe { self.state.IsSucceeded() }?.as_bool())
+        let state = self.remote.handle;
+        self.remote
+            .worker
+            .call(|reply| Request::IsSucceeded { state, reply })
     }
 
     /// 再生終了を待ちます。
     ///
     /// 再生が完了するまでブロックします。
+    /// 待機中もワーカースレッドを占有しないよう、呼び出し側のスレッドで完了を確認します。
     pub fn wait(&self) -> Result<()> {
-        Ok(unsafe { self.state.Wait() }?)
+        while !self.is_completed()? {
+            thread::sleep(Self::POLL_INTERVAL);
+        }
+        Ok(())
     }
 
     /// 再生終了を待ちます（タイムアウト付き）。
@@ -764,7 +1432,19 @@ impl SpeakingState {
     ///
     /// * `seconds` - 最大待機時間（秒）。0未満は無制限。
     pub fn wait_timeout(&self, seconds: f64) -> Result<()> {
-        Ok(unsafe { self.state.Wait_2(seconds) }?)
+        let Ok(timeout) = Duration::try_from_secs_f64(seconds) else {
+            return self.wait();
+        };
+
+        let deadline = Instant::now() + timeout;
+        while !self.is_completed()? {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                break;
+            }
+            thread::sleep(remaining.min(Self::POLL_INTERVAL));
+        }
+        Ok(())
     }
 }
 
@@ -772,11 +1452,17 @@ impl SpeakingState {
 ///
 /// 音声の音素単位のタイミング情報を表します。
 /// リップシンクなどの同期処理に利用できます。
+///
+/// `serde`フィーチャを有効にした場合、`phoneme`・`start_time`・`end_time`
+/// のフィールド名でシリアライズされます。
 #[derive(Debug, Clone, PartialEq)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct PhonemeData {
+    /// 音素
     phoneme: String,
+    /// 開始時間（秒）
     start_time: f64,
+    /// 終了時間（秒）
     end_time: f64,
 }
 
@@ -808,3 +1494,117 @@ impl PhonemeData {
         self.end_time
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 抑揚とバージョン取得に対応していない従来のホストを模したワーカー
+    struct LegacyHost;
+
+    impl Handler for LegacyHost {
+        fn handle(&mut self, request: Request) {
+            match request {
+                Request::Capabilities { reply } => respond(
+                    reply,
+                    Ok(Capabilities {
+                        has_tone_scale: false,
+                        has_host_version: false,
+                    }),
+                ),
+                Request::Cast { reply } => respond(reply, Ok("さとうささら".to_string())),
+                Request::Parameter {
+                    parameter: TalkerParameter::ToneScale,
+                    reply,
+                } => respond(
+                    reply,
+                    Err(CevioAIError::Unsupported("ToneScale".to_string())),
+                ),
+                Request::Parameter { reply, .. } => respond(reply, Ok(50)),
+                Request::AvailableCasts { reply } => {
+                    respond(reply, Ok(vec!["さとうささら".to_string()]));
+                }
+                Request::SetCast { reply, .. } => respond(reply, Ok(())),
+                Request::Stop { reply } => {
+                    // 応答しないホストを模す
+                    thread::sleep(Duration::from_millis(500));
+                    respond(reply, Ok(true));
+                }
+                other => panic!("unexpected request: {other:?}"),
+            }
+        }
+    }
+
+    #[test]
+    fn current_cast_on_legacy_host() -> Result<()> {
+        let worker = Worker::spawn(DEFAULT_OPERATION_TIMEOUT, ApartmentModel::default(), || {
+            Ok(LegacyHost)
+        })?;
+        let cevio = CevioAI::from_worker(worker, CevioAIConfig::default())?;
+        assert!(!cevio.capabilities().has_tone_scale);
+        assert!(matches!(
+            cevio.tone_scale(),
+            Err(CevioAIError::Unsupported(_))
+        ));
+
+        let cast = cevio.current_cast()?;
+        assert_eq!(cast.cast.as_deref(), Some("さとうささら"));
+        assert_eq!(cast.volume, Some(Volume::new(50).unwrap()));
+        assert_eq!(cast.tone_scale, None);
+        Ok(())
+    }
+
+    #[test]
+    fn apply_cast_validates_name() -> Result<()> {
+        let worker = Worker::spawn(DEFAULT_OPERATION_TIMEOUT, ApartmentModel::default(), || {
+            Ok(LegacyHost)
+        })?;
+        let cevio = CevioAI::from_worker(worker, CevioAIConfig::default())?;
+
+        let typo = CastBuilder::default().cast("sasara").build()?;
+        let Err(CevioAIError::UnknownCast { suggestions, .. }) = cevio.apply_cast(&typo) else {
+            panic!("expected UnknownCast");
+        };
+        assert_eq!(suggestions, ["さとうささら"]);
+
+        let cast = cevio.validate_cast("さとうささら")?;
+        cevio.set_cast(&cast)?;
+        Ok(())
+    }
+
+    #[test]
+    fn timeout_poisons_instance() -> Result<()> {
+        let worker = Worker::spawn(Duration::from_millis(50), ApartmentModel::default(), || {
+            Ok(LegacyHost)
+        })?;
+        let cevio = CevioAI::from_worker(worker, CevioAIConfig::default())?;
+        let clone = cevio.clone();
+
+        let result = cevio.stop();
+        let Err(CevioAIError::Timeout { operation, elapsed }) = result else {
+            panic!("expected Timeout, got {result:?}");
+        };
+        assert_eq!(operation, "Stop");
+        assert!(elapsed >= Duration::from_millis(50));
+
+        assert!(clone.is_poisoned());
+        assert!(matches!(clone.cast(), Err(CevioAIError::Poisoned)));
+        // 応答しないワーカースレッドの終了は待たない
+        let start = Instant::now();
+        drop((cevio, clone));
+        assert!(start.elapsed() < Duration::from_millis(400));
+        Ok(())
+    }
+
+    #[test]
+    fn creative_studio_requires_late_binding() {
+        let config = CevioAIConfigBuilder::default()
+            .product(Product::CreativeStudio)
+            .build()
+            .unwrap();
+        assert!(matches!(
+            CevioAI::with_config(config),
+            Err(CevioAIError::Unsupported(_))
+        ));
+    }
+}
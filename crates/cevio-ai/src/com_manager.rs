@@ -1,62 +1,214 @@
-use windows::{
-    core::Result,
-    Win32::{
-        Foundation::{S_FALSE, S_OK},
-        System::Com::{CoInitializeEx, CoUninitialize, COINIT_MULTITHREADED},
+//! COMの初期化管理
+//!
+//! COMの初期化はスレッドごとの状態であるため、参照カウントもスレッドごとに管理します。
+//! このモジュールが初期化に成功したスレッドでのみ`CoUninitialize`を呼び出します。
+
+use std::cell::RefCell;
+use std::marker::PhantomData;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use windows::Win32::{
+    Foundation::{RPC_E_CHANGED_MODE, S_FALSE, S_OK},
+    System::Com::{
+        CoInitializeEx, CoUninitialize, COINIT, COINIT_APARTMENTTHREADED, COINIT_MULTITHREADED,
     },
+    UI::WindowsAndMessaging::{DispatchMessageW, PeekMessageW, TranslateMessage, MSG, PM_REMOVE},
 };
 
-use parking_lot::Mutex;
+use crate::error::{CevioAIError, Result};
+
+/// COMのアパートメントモデル
+///
+/// CeVIO AIのCOMオブジェクトを所有するワーカースレッドで使用するモデルを指定します。
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum ApartmentModel {
+    /// マルチスレッドアパートメント（MTA）
+    #[default]
+    MultiThreaded,
+
+    /// シングルスレッドアパートメント（STA）
+    ///
+    /// ワーカースレッドはリクエストを待つ間もウィンドウメッセージを処理します。
+    SingleThreaded,
+}
+
+impl ApartmentModel {
+    const fn flags(self) -> COINIT {
+        match self {
+            Self::MultiThreaded => COINIT_MULTITHREADED,
+            Self::SingleThreaded => COINIT_APARTMENTTHREADED,
+        }
+    }
+}
 
-static COM_MANAGER: std::sync::LazyLock<Mutex<ComManager>> =
-    std::sync::LazyLock::new(|| Mutex::new(ComManager::new()));
+thread_local! {
+    static THREAD_STATE: RefCell<ThreadState> = const { RefCell::new(ThreadState::new()) };
+}
 
-struct ComManager {
+/// スレッドごとのCOM初期化状態
+#[derive(Debug)]
+struct ThreadState {
+    /// このモジュールが初期化したアパートメントモデル
+    model: Option<ApartmentModel>,
     ref_count: usize,
 }
 
-impl ComManager {
+impl ThreadState {
     const fn new() -> Self {
-        Self { ref_count: 0 }
+        Self {
+            model: None,
+            ref_count: 0,
+        }
     }
 
-    fn initialize(&mut self) -> Result<()> {
-        if self.ref_count == 0 {
-            unsafe {
-                let hr = CoInitializeEx(None, COINIT_MULTITHREADED);
-                // S_OKまたはS_FALSE（既に初期化済み）の場合は成功
-                if hr != S_OK && hr != S_FALSE {
-                    return Err(hr.into());
-                }
+    /// 参照カウントを増やし、最初の1回だけ`initialize`を呼び出します。
+    ///
+    /// 初期化済みのモデルと異なるモデルが要求された場合は失敗します。
+    fn acquire(
+        &mut self,
+        model: ApartmentModel,
+        initialize: impl FnOnce(ApartmentModel) -> Result<()>,
+    ) -> Result<()> {
+        match self.model {
+            Some(current) if current != model => {
+                return Err(CevioAIError::ApartmentChanged(model));
+            }
+            Some(_) => {}
+            None => {
+                initialize(model)?;
+                self.model = Some(model);
             }
         }
         self.ref_count += 1;
         Ok(())
     }
 
-    fn uninitialize(&mut self) {
-        if self.ref_count > 0 {
-            self.ref_count -= 1;
-            if self.ref_count == 0 {
-                unsafe {
-                    CoUninitialize();
-                }
-            }
+    /// 参照カウントを減らし、0になった場合に`uninitialize`を呼び出します。
+    fn release(&mut self, uninitialize: impl FnOnce()) {
+        if self.ref_count == 0 {
+            return;
+        }
+        self.ref_count -= 1;
+        if self.ref_count == 0 {
+            self.model = None;
+            uninitialize();
         }
     }
 }
 
-pub struct ComGuard;
+/// 現在のスレッドでCOMを初期化します。
+fn initialize(model: ApartmentModel) -> Result<()> {
+    let hr = unsafe { CoInitializeEx(None, model.flags()) };
+    // S_OKまたはS_FALSE（同じモデルで初期化済み）の場合は成功。どちらもCoUninitializeと対になる
+    if hr == S_OK || hr == S_FALSE {
+        Ok(())
+    } else if hr == RPC_E_CHANGED_MODE {
+        Err(CevioAIError::ApartmentChanged(model))
+    } else {
+        Err(windows::core::Error::from(hr).into())
+    }
+}
+
+/// 現在のスレッドのメッセージキューにあるメッセージをすべて処理します。
+///
+/// STAでは他のアパートメントからの呼び出しやCOMの内部処理がウィンドウメッセージとして届くため、
+/// STAのスレッドはメッセージを処理し続ける必要があります。
+pub(crate) fn pump_messages() {
+    let mut message = MSG::default();
+    unsafe {
+        while PeekMessageW(&mut message, None, 0, 0, PM_REMOVE).as_bool() {
+            let _ = TranslateMessage(&message);
+            DispatchMessageW(&message);
+        }
+    }
+}
+
+/// COM初期化のガード
+///
+/// 作成したスレッドで破棄される必要があるため、`Send`ではありません。
+pub(crate) struct ComGuard {
+    _not_send: PhantomData<*const ()>,
+}
 
 impl ComGuard {
-    pub fn new() -> Result<Self> {
-        COM_MANAGER.lock().initialize()?;
-        Ok(Self)
+    /// 現在のスレッドで、指定したアパートメントモデルによりCOMを初期化します。
+    ///
+    /// # Errors
+    ///
+    /// - スレッドが別のアパートメントモデルで初期化済みの場合は `CevioAIError::ApartmentChanged`
+    /// - その他の理由で初期化に失敗した場合は `CevioAIError::Windows`
+    pub(crate) fn new(model: ApartmentModel) -> Result<Self> {
+        THREAD_STATE.with_borrow_mut(|state| state.acquire(model, initialize))?;
+        Ok(Self {
+            _not_send: PhantomData,
+        })
     }
 }
 
 impl Drop for ComGuard {
     fn drop(&mut self) {
-        COM_MANAGER.lock().uninitialize();
+        let _ = THREAD_STATE.try_with(|state| {
+            state.borrow_mut().release(|| unsafe { CoUninitialize() });
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn initializes_once_per_thread() {
+        let mut state = ThreadState::new();
+        let mut calls = Vec::new();
+
+        for _ in 0..2 {
+            state
+                .acquire(ApartmentModel::SingleThreaded, |model| {
+                    calls.push(format!("init {model:?}"));
+                    Ok(())
+                })
+                .unwrap();
+        }
+        state.release(|| calls.push("uninit".to_string()));
+        assert_eq!(calls, ["init SingleThreaded"]);
+
+        state.release(|| calls.push("uninit".to_string()));
+        state.release(|| calls.push("uninit".to_string()));
+        assert_eq!(calls, ["init SingleThreaded", "uninit"]);
+        assert!(state.model.is_none());
+    }
+
+    #[test]
+    fn rejects_different_model() {
+        let mut state = ThreadState::new();
+        state
+            .acquire(ApartmentModel::MultiThreaded, |_| Ok(()))
+            .unwrap();
+
+        let result = state.acquire(ApartmentModel::SingleThreaded, |_| Ok(()));
+        assert!(matches!(
+            result,
+            Err(CevioAIError::ApartmentChanged(
+                ApartmentModel::SingleThreaded
+            ))
+        ));
+        assert_eq!(state.ref_count, 1);
+    }
+
+    #[test]
+    fn failed_initialization_is_not_uninitialized() {
+        let mut state = ThreadState::new();
+        let result = state.acquire(ApartmentModel::MultiThreaded, |model| {
+            Err(CevioAIError::ApartmentChanged(model))
+        });
+        assert!(result.is_err());
+
+        let mut uninitialized = false;
+        state.release(|| uninitialized = true);
+        assert!(!uninitialized);
+        assert!(state.model.is_none());
     }
 }
@@ -0,0 +1,391 @@
+//! 音声の音量に基づく口パク
+//!
+//! 音素から求めた口の形だけでは、ささやき声でも大きな声でも同じだけ口が開きます。
+//! このモジュールは、合成した音声データから音量の包絡線（RMS・ピーク）を計算し、
+//! `phonemes()`から求めた口の形と組み合わせて口の開き具合を決める型を提供します。
+//!
+//! ```rust,no_run
+//! use cevio_ai::*;
+//!
+//! fn main() -> Result<()> {
+//!     let cevio = CevioAI::new()?;
+//!     let audio = cevio.synthesize("こんにちは")?;
+//!     let phonemes = cevio.phonemes("こんにちは")?;
+//!
+//!     let curves = EnvelopeLipSync::default().curves(&audio, &phonemes)?;
+//!     std::fs::write("hello.csv", curves.to_csv())?;
+//!     Ok(())
+//! }
+//! ```
+
+use std::fmt::Write;
+
+use crate::{
+    audio::AudioBuffer,
+    cevio::PhonemeData,
+    error::{CevioAIError, Result},
+    lipsync::{number, Live2DMotion},
+    viseme::{viseme_track, Coarticulation, JapaneseVisemes, Viseme},
+};
+
+/// 音量の包絡線
+///
+/// `i`番目の値は、時間`i / fps`を中心とする`1 / fps`秒の区間の値です。
+/// 値はフルスケールを1.0とした大きさで、すべてのチャンネルをまとめて計算します。
+#[derive(Debug, Clone, PartialEq)]
+pub struct Envelope {
+    /// フレームレート
+    pub fps: f64,
+
+    /// 二乗平均平方根（RMS）
+    pub rms: Vec<f64>,
+
+    /// ピーク（絶対値の最大値）
+    pub peak: Vec<f64>,
+}
+
+impl Envelope {
+    /// 音声データから包絡線を計算します。
+    ///
+    /// # Errors
+    ///
+    /// `fps`が正の数でない場合は`InvalidParameter`エラーを返します。
+    pub fn from_audio(audio: &AudioBuffer, fps: f64) -> Result<Self> {
+        if !(fps > 0.0 && fps.is_finite()) {
+            return Err(CevioAIError::InvalidParameter(format!(
+                "Frame rate must be positive, got {fps}"
+            )));
+        }
+
+        let channels = usize::from(audio.channels());
+        let sample_rate = f64::from(audio.sample_rate());
+        let frames = audio.frames();
+        let count = (audio.duration() * fps).round() as usize + 1;
+
+        let mut envelope = Self {
+            fps,
+            rms: Vec::with_capacity(count),
+            peak: Vec::with_capacity(count),
+        };
+        for index in 0..count {
+            let center = index as f64 / fps;
+            let start =
+                (((center - 0.5 / fps) * sample_rate).round().max(0.0) as usize).min(frames);
+            let end = (((center + 0.5 / fps) * sample_rate).round().max(0.0) as usize).min(frames);
+            let window = &audio.samples()[start * channels..end * channels];
+
+            let (sum, peak) = window.iter().fold((0.0, 0.0), |(sum, peak), &sample| {
+                let value = f64::from(sample) / 32768.0;
+                (sum + value * value, f64::max(peak, value.abs()))
+            });
+            envelope.rms.push(if window.is_empty() {
+                0.0
+            } else {
+                (sum / window.len() as f64).sqrt()
+            });
+            envelope.peak.push(peak);
+        }
+
+        Ok(envelope)
+    }
+
+    /// フレーム数を取得します。
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.rms.len()
+    }
+
+    /// フレームがないかどうかを取得します。
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.rms.is_empty()
+    }
+}
+
+/// 音量と口の形から口の動きを作成する設定
+#[derive(Debug, Clone, PartialEq)]
+pub struct EnvelopeLipSync {
+    /// フレームレート（既定値は30）
+    pub fps: f64,
+
+    /// 口を閉じたままにする音量（dBFS、既定値は-50）
+    pub noise_floor: f64,
+
+    /// 口を最大まで開く音量（dBFS）
+    ///
+    /// `None`の場合は音声データ中の最大の音量を使用します。
+    pub reference: Option<f64>,
+
+    /// 音量が下がったときに口を閉じる速さ（時定数、秒、既定値は0.08）
+    pub release: f64,
+}
+
+impl Default for EnvelopeLipSync {
+    fn default() -> Self {
+        Self {
+            fps: 30.0,
+            noise_floor: -50.0,
+            reference: None,
+            release: 0.08,
+        }
+    }
+}
+
+/// 口の動きの1フレーム
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MouthFrame {
+    /// 時間（秒）
+    pub time: f64,
+
+    /// 口の形
+    pub viseme: Viseme,
+
+    /// 音量（0.0～1.0）
+    pub level: f64,
+
+    /// 口の開き（0.0～1.0、口の形の開きに音量を掛けたもの）
+    pub open: f64,
+
+    /// 口の形（-1.0～1.0、`Live2DMotion::parameters`と同じ値）
+    pub form: f64,
+}
+
+/// 口の動きの曲線
+#[derive(Debug, Clone, PartialEq)]
+pub struct MouthCurves {
+    /// フレームレート
+    pub fps: f64,
+
+    /// フレーム
+    pub frames: Vec<MouthFrame>,
+}
+
+impl EnvelopeLipSync {
+    /// 音声データと音素データから口の動きを作成します。
+    ///
+    /// # Errors
+    ///
+    /// `fps`が正の数でない場合は`InvalidParameter`エラーを返します。
+    pub fn curves(&self, audio: &AudioBuffer, phonemes: &[PhonemeData]) -> Result<MouthCurves> {
+        let envelope = Envelope::from_audio(audio, self.fps)?;
+        let track = viseme_track(phonemes, &JapaneseVisemes, &Coarticulation::default());
+
+        let reference = self
+            .reference
+            .unwrap_or_else(|| decibels(envelope.rms.iter().copied().fold(0.0, f64::max)));
+        let range = reference - self.noise_floor;
+        let decay = if self.release > 0.0 {
+            (-1.0 / (self.fps * self.release)).exp()
+        } else {
+            0.0
+        };
+
+        let mut level = 0.0;
+        let frames = envelope
+            .rms
+            .iter()
+            .enumerate()
+            .map(|(index, &rms)| {
+                let time = index as f64 / self.fps;
+                let target = if range > 0.0 {
+                    ((decibels(rms) - self.noise_floor) / range).clamp(0.0, 1.0)
+                } else {
+                    0.0
+                };
+                // 口はすぐに開き、ゆっくり閉じる
+                level = f64::max(target, level * decay);
+
+                let viseme = track
+                    .iter()
+                    .find(|key| time < key.end_time)
+                    .or(track.last())
+                    .map_or(Viseme::Rest, |key| key.viseme);
+                let (open, form) = Live2DMotion::parameters(viseme);
+
+                MouthFrame {
+                    time,
+                    viseme,
+                    level,
+                    open: open * level,
+                    form,
+                }
+            })
+            .collect();
+
+        Ok(MouthCurves {
+            fps: self.fps,
+            frames,
+        })
+    }
+}
+
+impl MouthCurves {
+    /// JSON形式に変換します。
+    ///
+    /// ```json
+    /// {
+    ///   "fps": 30,
+    ///   "visemes": ["rest", "a"],
+    ///   "curves": {
+    ///     "level": [0, 1],
+    ///     "open": [0, 1],
+    ///     "form": [0, 0]
+    ///   }
+    /// }
+    /// ```
+    #[must_use]
+    pub fn to_json(&self) -> String {
+        let list = |values: Vec<String>| values.join(", ");
+        let curve = |value: fn(&MouthFrame) -> f64| {
+            list(
+                self.frames
+                    .iter()
+                    .map(|frame| number(value(frame)))
+                    .collect(),
+            )
+        };
+
+        let mut json = String::from("{\n");
+        let _ = writeln!(json, "  \"fps\": {},", number(self.fps));
+        let _ = writeln!(
+            json,
+            "  \"visemes\": [{}],",
+            list(
+                self.frames
+                    .iter()
+                    .map(|frame| format!("\"{}\"", frame.viseme.name()))
+                    .collect()
+            )
+        );
+        json.push_str("  \"curves\": {\n");
+        let _ = writeln!(json, "    \"level\": [{}],", curve(|frame| frame.level));
+        let _ = writeln!(json, "    \"open\": [{}],", curve(|frame| frame.open));
+        let _ = writeln!(json, "    \"form\": [{}]", curve(|frame| frame.form));
+        json.push_str("  }\n}\n");
+        json
+    }
+
+    /// CSV形式（`time,viseme,level,open,form`）に変換します。
+    #[must_use]
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("time,viseme,level,open,form\n");
+        for frame in &self.frames {
+            let _ = writeln!(
+                csv,
+                "{},{},{},{},{}",
+                number(frame.time),
+                frame.viseme.name(),
+                number(frame.level),
+                number(frame.open),
+                number(frame.form)
+            );
+        }
+        csv
+    }
+}
+
+/// 大きさをdBFSに変換します。
+fn decibels(value: f64) -> f64 {
+    20.0 * value.max(1e-10).log10()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::TAU;
+
+    use super::*;
+
+    const SAMPLE_RATE: u32 = 48_000;
+
+    /// 440Hzの正弦波を区間ごとの振幅でつなげた音声データ
+    fn sine(segments: &[(f64, f64)]) -> AudioBuffer {
+        let mut samples = Vec::new();
+        for &(seconds, amplitude) in segments {
+            let count = (seconds * f64::from(SAMPLE_RATE)).round() as usize;
+            samples.extend((0..count).map(|index| {
+                let time = index as f64 / f64::from(SAMPLE_RATE);
+                ((TAU * 440.0 * time).sin() * amplitude * 32767.0).round() as i16
+            }));
+        }
+        AudioBuffer::new(SAMPLE_RATE, 1, samples).unwrap()
+    }
+
+    #[test]
+    fn computes_envelope() {
+        let audio = sine(&[(0.5, 0.5), (0.5, 0.0)]);
+
+        let envelope = Envelope::from_audio(&audio, 10.0).unwrap();
+
+        assert_eq!(envelope.len(), 11);
+        assert!((envelope.rms[2] - 0.5 / 2f64.sqrt()).abs() < 1e-3);
+        assert!((envelope.peak[2] - 0.5).abs() < 1e-3);
+        assert_eq!(envelope.rms[8], 0.0);
+        assert_eq!(envelope.peak[10], 0.0);
+        assert!(Envelope::from_audio(&audio, 0.0).is_err());
+    }
+
+    #[test]
+    fn scales_mouth_by_loudness() {
+        // 同じ「あ」を大きな声と小さな声（-20dB）で
+        let audio = sine(&[(0.1, 0.0), (0.4, 0.8), (0.2, 0.0), (0.4, 0.08), (0.1, 0.0)]);
+        let phonemes = PhonemeData::sequence(&[
+            ("sil", 0.1),
+            ("a", 0.4),
+            ("pau", 0.2),
+            ("a", 0.4),
+            ("sil", 0.1),
+        ]);
+        let lipsync = EnvelopeLipSync {
+            fps: 10.0,
+            ..EnvelopeLipSync::default()
+        };
+
+        let curves = lipsync.curves(&audio, &phonemes).unwrap();
+
+        let loud = curves.frames[3];
+        let quiet = curves.frames[9];
+        assert_eq!(loud.viseme, Viseme::A);
+        assert_eq!(quiet.viseme, Viseme::A);
+        assert!((loud.open - 1.0).abs() < 1e-6);
+        // (-24.9dB + 50) / (-4.9dB + 50)
+        assert!((quiet.open - 0.556).abs() < 0.01);
+        // 音量が下がると時定数に従って口が閉じる
+        let closing = curves.frames[6];
+        assert_eq!(closing.viseme, Viseme::Rest);
+        assert!(closing.level > 0.0 && closing.level < loud.level);
+        assert_eq!(closing.open, 0.0);
+    }
+
+    #[test]
+    fn exports_curves() {
+        let curves = MouthCurves {
+            fps: 30.0,
+            frames: vec![
+                MouthFrame {
+                    time: 0.0,
+                    viseme: Viseme::Rest,
+                    level: 0.0,
+                    open: 0.0,
+                    form: 0.0,
+                },
+                MouthFrame {
+                    time: 1.0 / 30.0,
+                    viseme: Viseme::I,
+                    level: 0.5,
+                    open: 0.2,
+                    form: 1.0,
+                },
+            ],
+        };
+
+        assert_eq!(
+            curves.to_csv(),
+            "time,viseme,level,open,form\n0,rest,0,0,0\n0.033,i,0.5,0.2,1\n"
+        );
+        assert_eq!(
+            curves.to_json(),
+            "{\n  \"fps\": 30,\n  \"visemes\": [\"rest\", \"i\"],\n  \"curves\": {\n    \
+             \"level\": [0, 0.5],\n    \"open\": [0, 0.2],\n    \"form\": [0, 1]\n  }\n}\n"
+        );
+    }
+}
@@ -4,13 +4,24 @@
 //! すべてのエラーは`CevioAIError`列挙型にまとめられており、
 //! `thiserror`クレートを使用して詳細なエラーメッセージを提供します。
 
-use crate::{CastBuilderError, CevioAIConfigBuilderError};
+use std::fmt;
+
+use crate::{ApartmentModel, CastBuilderError, CevioAIConfigBuilderError};
 use thiserror::Error;
+use windows::core::HRESULT;
+use windows::Win32::Foundation::{
+    CO_E_CLASSSTRING, CO_E_OBJNOTCONNECTED, CO_E_SERVER_EXEC_FAILURE, E_ACCESSDENIED, E_INVALIDARG,
+    E_POINTER, REGDB_E_CLASSNOTREG, RPC_E_ACCESS_DENIED, RPC_E_CALL_CANCELED, RPC_E_CALL_REJECTED,
+    RPC_E_DISCONNECTED, RPC_E_SERVERCALL_REJECTED, RPC_E_SERVERCALL_RETRYLATER, RPC_E_SERVER_DIED,
+    RPC_E_SERVER_DIED_DNE, RPC_E_TIMEOUT,
+};
 
 #[derive(Debug, Error)]
 pub enum CevioAIError {
     #[error("Windows API error: {0}")]
     Windows(#[from] windows::core::Error),
+    #[error(transparent)]
+    Com(#[from] ComError),
     #[error("BuilderError error: {0}")]
     BuilderError(#[from] CastBuilderError),
     #[error("ConfigBuilderError error: {0}")]
@@ -25,6 +36,294 @@ pub enum CevioAIError {
     AppTerminated,
     #[error("Invalid parameter: {0}")]
     InvalidParameter(String),
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Invalid wave data: {0}")]
+    InvalidWave(String),
+    #[error("Failed to output wave")]
+    WaveOutputFailed,
+    #[error("Invalid VMD data: {0}")]
+    InvalidVmd(String),
+    #[error("Invalid TextGrid data: {0}")]
+    InvalidTextGrid(String),
+    #[error("Invalid timeline data: {0}")]
+    InvalidTimeline(String),
+    #[error("COM worker thread has stopped")]
+    WorkerStopped,
+    #[error("COM is already initialized on this thread with a different apartment model (requested: {0:?})")]
+    ApartmentChanged(ApartmentModel),
+    #[error("Not supported by the connected CeVIO host: {0}")]
+    Unsupported(String),
+    #[error("Unexpected VARIANT value: {0}")]
+    InvalidVariant(String),
+    #[error("{operation} timed out after {elapsed:?}")]
+    Timeout {
+        operation: &'static str,
+        elapsed: std::time::Duration,
+    },
+    #[error("Instance is unusable after a timed-out operation; rebuild it")]
+    Poisoned,
+    #[error("Unknown cast: {requested}{}", did_you_mean(.suggestions))]
+    UnknownCast {
+        requested: String,
+        suggestions: Vec<String>,
+    },
+}
+
+impl CevioAIError {
+    /// 同じ操作を再試行すると成功する可能性があるかどうかを返します。
+    ///
+    /// ホストが応答中で呼び出しを拒否した場合やCOM呼び出しがタイムアウトした場合に`true`を返します。
+    /// `CevioAIError::Timeout`の後はインスタンスが使用不能になり同じインスタンスでは再試行できないため、
+    /// `false`を返します。`CevioAI::rebuild`で作り直してから呼び出してください。
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            CevioAIError::Com(error) => error.kind().is_retryable(),
+            CevioAIError::Windows(error) => ComErrorKind::from_hresult(error.code()).is_retryable(),
+            _ => false,
+        }
+    }
+
+    /// COM呼び出しが返したHRESULTを返します。
+    pub fn hresult(&self) -> Option<HRESULT> {
+        match self {
+            CevioAIError::Com(error) => Some(error.hresult()),
+            CevioAIError::Windows(error) => Some(error.code()),
+            _ => None,
+        }
+    }
+
+    /// COM呼び出しのエラーに失敗した操作と引数を添えます。
+    ///
+    /// `CevioAIError::Windows`以外のエラーはそのまま返します。
+    pub(crate) fn with_context(self, operation: &'static str, arguments: Vec<String>) -> Self {
+        match self {
+            CevioAIError::Windows(source) => {
+                CevioAIError::Com(ComError::new(operation, arguments, source))
+            }
+            other => other,
+        }
+    }
+}
+
+/// キャスト名の候補を表示用に整形します。
+fn did_you_mean(suggestions: &[String]) -> String {
+    if suggestions.is_empty() {
+        String::new()
+    } else {
+        format!(" (did you mean: {}?)", suggestions.join(", "))
+    }
+}
+
+/// .NETの`InvalidOperationException`（ホスト未起動時の操作で返されます）
+const COR_E_INVALIDOPERATION: HRESULT = HRESULT(0x8013_1509_u32 as i32);
+/// .NETの`ArgumentOutOfRangeException`
+const COR_E_ARGUMENTOUTOFRANGE: HRESULT = HRESULT(0x8013_1502_u32 as i32);
+/// `RPC_S_SERVER_UNAVAILABLE`（1722）のHRESULT
+const RPC_SERVER_UNAVAILABLE: HRESULT = HRESULT::from_win32(1722);
+/// `ERROR_TIMEOUT`（1460）のHRESULT
+const TIMEOUT: HRESULT = HRESULT::from_win32(1460);
+/// `WAIT_TIMEOUT`（258）のHRESULT
+const WAIT_TIMEOUT: HRESULT = HRESULT::from_win32(258);
+
+/// COM呼び出しの失敗の分類
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ComErrorKind {
+    /// CeVIOのホストが起動していない
+    HostNotRunning,
+    /// CeVIOがインストール（登録）されていない
+    NotInstalled,
+    /// ホストとの接続が切れた
+    Disconnected,
+    /// 指定したキャストが見つからない
+    CastNotFound,
+    /// 引数が不正
+    InvalidArgument,
+    /// アクセスが拒否された
+    AccessDenied,
+    /// ホストが応答中で呼び出しを受け付けなかった
+    Busy,
+    /// 呼び出しがタイムアウトした
+    Timeout,
+    /// 上記以外
+    Other,
+}
+
+impl ComErrorKind {
+    /// HRESULTから分類を判定します。
+    pub fn from_hresult(hresult: HRESULT) -> Self {
+        match hresult {
+            COR_E_INVALIDOPERATION => ComErrorKind::HostNotRunning,
+            REGDB_E_CLASSNOTREG | CO_E_CLASSSTRING => ComErrorKind::NotInstalled,
+            RPC_E_DISCONNECTED
+            | RPC_E_SERVER_DIED
+            | RPC_E_SERVER_DIED_DNE
+            | CO_E_OBJNOTCONNECTED
+            | CO_E_SERVER_EXEC_FAILURE
+            | RPC_SERVER_UNAVAILABLE => ComErrorKind::Disconnected,
+            E_INVALIDARG | E_POINTER | COR_E_ARGUMENTOUTOFRANGE => ComErrorKind::InvalidArgument,
+            E_ACCESSDENIED | RPC_E_ACCESS_DENIED => ComErrorKind::AccessDenied,
+            RPC_E_CALL_REJECTED | RPC_E_SERVERCALL_RETRYLATER | RPC_E_SERVERCALL_REJECTED => {
+                ComErrorKind::Busy
+            }
+            RPC_E_TIMEOUT | RPC_E_CALL_CANCELED | TIMEOUT | WAIT_TIMEOUT => ComErrorKind::Timeout,
+            _ => ComErrorKind::Other,
+        }
+    }
+
+    /// 同じ操作を再試行すると成功する可能性があるかどうかを返します。
+    pub fn is_retryable(self) -> bool {
+        matches!(self, ComErrorKind::Busy | ComErrorKind::Timeout)
+    }
+}
+
+impl fmt::Display for ComErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ComErrorKind::HostNotRunning => "host is not running",
+            ComErrorKind::NotInstalled => "CeVIO is not installed",
+            ComErrorKind::Disconnected => "disconnected from host",
+            ComErrorKind::CastNotFound => "cast not found",
+            ComErrorKind::InvalidArgument => "invalid argument",
+            ComErrorKind::AccessDenied => "access denied",
+            ComErrorKind::Busy => "host is busy",
+            ComErrorKind::Timeout => "timed out",
+            ComErrorKind::Other => "COM error",
+        })
+    }
+}
+
+/// 失敗したCOM呼び出し
+///
+/// 分類・操作名・引数と、元のHRESULTを保持します。
+#[derive(Debug, Error)]
+#[error("{operation}({}) failed: {kind} ({:#010x}): {}", .arguments.join(", "), .source.code().0, .source.message())]
+pub struct ComError {
+    kind: ComErrorKind,
+    operation: &'static str,
+    arguments: Vec<String>,
+    #[source]
+    source: windows::core::Error,
+}
+
+impl ComError {
+    /// COM呼び出しのエラーを分類します。
+    ///
+    /// キャストの設定で引数が不正とされた場合は[`ComErrorKind::CastNotFound`]になります。
+    pub fn new(
+        operation: &'static str,
+        arguments: Vec<String>,
+        source: windows::core::Error,
+    ) -> Self {
+        let kind = match ComErrorKind::from_hresult(source.code()) {
+            ComErrorKind::InvalidArgument if operation == "SetCast" => ComErrorKind::CastNotFound,
+            kind => kind,
+        };
+        Self {
+            kind,
+            operation,
+            arguments,
+            source,
+        }
+    }
+
+    /// 失敗の分類
+    pub fn kind(&self) -> ComErrorKind {
+        self.kind
+    }
+
+    /// 失敗した操作の名前
+    pub fn operation(&self) -> &'static str {
+        self.operation
+    }
+
+    /// 失敗した操作の引数
+    pub fn arguments(&self) -> &[String] {
+        &self.arguments
+    }
+
+    /// COM呼び出しが返したHRESULT
+    pub fn hresult(&self) -> HRESULT {
+        self.source.code()
+    }
 }
 
 pub type Result<T> = std::result::Result<T, CevioAIError>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn error(hresult: u32) -> windows::core::Error {
+        windows::core::Error::from(HRESULT(hresult as i32))
+    }
+
+    #[test]
+    fn classifies_known_hresults() {
+        let cases = [
+            (0x8013_1509_u32, ComErrorKind::HostNotRunning),
+            (0x8004_0154, ComErrorKind::NotInstalled),
+            (0x8001_0108, ComErrorKind::Disconnected),
+            (0x8007_06BA, ComErrorKind::Disconnected),
+            (0x8007_0057, ComErrorKind::InvalidArgument),
+            (0x8007_0005, ComErrorKind::AccessDenied),
+            (0x8001_0001, ComErrorKind::Busy),
+            (0x8001_010A, ComErrorKind::Busy),
+            (0x8001_011F, ComErrorKind::Timeout),
+            (0x8007_05B4, ComErrorKind::Timeout),
+            (0x8000_4005, ComErrorKind::Other),
+        ];
+        for (hresult, kind) in cases {
+            assert_eq!(
+                ComErrorKind::from_hresult(HRESULT(hresult as i32)),
+                kind,
+                "{hresult:#x}"
+            );
+        }
+    }
+
+    #[test]
+    fn invalid_cast_is_cast_not_found() {
+        let set_cast = ComError::new("SetCast", vec!["unknown".into()], error(0x8007_0057));
+        assert_eq!(set_cast.kind(), ComErrorKind::CastNotFound);
+
+        let speak = ComError::new("Speak", vec!["text".into()], error(0x8007_0057));
+        assert_eq!(speak.kind(), ComErrorKind::InvalidArgument);
+    }
+
+    #[test]
+    fn context_keeps_hresult_and_arguments() {
+        let error =
+            CevioAIError::from(error(0x8001_010A)).with_context("Speak", vec!["こんにちは".into()]);
+        assert!(error.is_retryable());
+        assert_eq!(error.hresult(), Some(HRESULT(0x8001_010A_u32 as i32)));
+
+        let CevioAIError::Com(com) = &error else {
+            panic!("expected Com, got {error:?}");
+        };
+        assert_eq!(com.operation(), "Speak");
+        assert_eq!(com.arguments(), ["こんにちは"]);
+        assert!(error
+            .to_string()
+            .starts_with("Speak(こんにちは) failed: host is busy (0x8001010a)"));
+    }
+
+    #[test]
+    fn poisoning_errors_are_not_retryable() {
+        let timeout = CevioAIError::Timeout {
+            operation: "Speak",
+            elapsed: std::time::Duration::from_secs(5),
+        };
+        assert!(!timeout.is_retryable());
+        assert!(!CevioAIError::Poisoned.is_retryable());
+        assert!(!CevioAIError::WorkerStopped.is_retryable());
+        assert!(!CevioAIError::from(error(0x8001_0108)).is_retryable());
+        assert_eq!(CevioAIError::WorkerStopped.hresult(), None);
+        assert_eq!(
+            CevioAIError::InvalidParameter("x".into())
+                .with_context("Speak", Vec::new())
+                .hresult(),
+            None
+        );
+    }
+}
@@ -0,0 +1,350 @@
+//! テスト用のバックエンド
+//!
+//! このモジュールは、CeVIO AIを使用せずに`Backend`トレイトを実装する`FakeBackend`を提供します。
+//! `fake`フィーチャを有効にした場合のみ利用できます。
+//!
+//! 音素データと音声データはセリフと音声パラメータから決定的に生成されるため、
+//! CeVIO AIがインストールされていない環境でもアプリケーションの動作を検証できます。
+//!
+//! - セリフの前後に0.1秒の`sil`を置きます
+//! - 句読点（`、`・`。`・`！`・`？`など）は0.2秒の`pau`になります
+//! - それ以外の文字は1文字につき1つの母音（`a`・`i`・`u`・`e`・`o`）になり、
+//!   長さは話す速さが50のとき0.1秒、100のとき0.05秒、0のとき0.2秒です
+//! - 音声データは48kHz・16bitのモノラルで、母音の区間に音の高さに応じた正弦波を、
+//!   音の大きさに応じた振幅で出力します
+
+use std::{f64::consts::TAU, thread, time::Duration};
+
+use parking_lot::{Mutex, MutexGuard};
+
+use crate::{
+    audio::AudioBuffer,
+    backend::Backend,
+    cast_name::CastName,
+    cevio::{Cast, CloseMode, ComponentValue, PhonemeData},
+    error::{CevioAIError, Result},
+    parameter::{Alpha, Speed, Tone, ToneScale, Volume},
+};
+
+const SAMPLE_RATE: u32 = 48_000;
+const SILENCE: f64 = 0.1;
+const PAUSE: f64 = 0.2;
+const VOWELS: [&str; 5] = ["a", "i", "u", "e", "o"];
+const PUNCTUATION: &[char] = &['、', '。', '！', '？', '，', '．', ',', '.', '!', '?'];
+
+/// テスト用のバックエンド
+///
+/// # Example
+///
+/// ```rust
+/// use cevio_ai::*;
+///
+/// let backend = FakeBackend::new();
+/// backend.apply_cast(&CastBuilder::default().cast("さとうささら").build().unwrap()).unwrap();
+///
+/// let phonemes = backend.phonemes("こんにちは").unwrap();
+/// assert_eq!(phonemes.first().unwrap().phoneme(), "sil");
+/// assert!(backend.speak_and_wait("こんにちは").unwrap());
+/// assert_eq!(backend.spoken(), vec!["こんにちは".to_string()]);
+/// ```
+#[derive(Debug)]
+pub struct FakeBackend {
+    state: Mutex<FakeState>,
+}
+
+#[derive(Debug, Clone)]
+struct FakeState {
+    poisoned: bool,
+    started: bool,
+    host_version: String,
+    casts: Vec<(String, Vec<ComponentValue>)>,
+    current: Cast,
+    spoken: Vec<String>,
+    stops: usize,
+    latency: Duration,
+}
+
+impl Default for FakeBackend {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FakeBackend {
+    /// 標準的なキャストを持つ`FakeBackend`を作成します。
+    ///
+    /// - 『さとうささら』→ "普通", "元気", "怒り", "哀しみ"
+    /// - 『すずきつづみ』→ "クール", "照れ"
+    /// - 『タカハシ』→ "普通", "元気", "へこみ"
+    #[must_use]
+    pub fn new() -> Self {
+        Self::with_casts(&[
+            ("さとうささら", &["普通", "元気", "怒り", "哀しみ"]),
+            ("すずきつづみ", &["クール", "照れ"]),
+            ("タカハシ", &["普通", "元気", "へこみ"]),
+        ])
+    }
+
+    /// キャストと感情パラメータを指定して`FakeBackend`を作成します。
+    ///
+    /// 最初のキャストが選択された状態になります。
+    /// 各感情の値は、最初の感情が100、それ以外が0に初期化されます。
+    ///
+    /// # Arguments
+    ///
+    /// * `casts` - キャスト名と感情の名前の組
+    #[must_use]
+    pub fn with_casts(casts: &[(&str, &[&str])]) -> Self {
+        let casts: Vec<(String, Vec<ComponentValue>)> = casts
+            .iter()
+            .enumerate()
+            .map(|(cast_index, (cast, components))| {
+                let components = components
+                    .iter()
+                    .enumerate()
+                    .map(|(index, name)| ComponentValue {
+                        id: format!("fake-{cast_index}-{index}"),
+                        name: (*name).to_string(),
+                        value: if index == 0 { 100 } else { 0 },
+                    })
+                    .collect();
+                ((*cast).to_string(), components)
+            })
+            .collect();
+
+        let current = Cast {
+            cast: casts.first().map(|(name, _)| name.clone()),
+            volume: Some(Volume::new(50).unwrap()),
+            speed: Some(Speed::new(50).unwrap()),
+            tone: Some(Tone::new(50).unwrap()),
+            tone_scale: Some(ToneScale::new(50).unwrap()),
+            alpha: Some(Alpha::new(50).unwrap()),
+        };
+
+        Self {
+            state: Mutex::new(FakeState {
+                poisoned: false,
+                started: false,
+                host_version: "9.0.0.0".to_string(),
+                casts,
+                current,
+                spoken: Vec::new(),
+                stops: 0,
+                latency: Duration::ZERO,
+            }),
+        }
+    }
+
+    /// これまでに`speak_and_wait`で再生されたセリフを取得します。
+    #[must_use]
+    pub fn spoken(&self) -> Vec<String> {
+        self.state.lock().spoken.clone()
+    }
+
+    /// これまでに`stop`が呼び出された回数を取得します。
+    #[must_use]
+    pub fn stop_count(&self) -> usize {
+        self.state.lock().stops
+    }
+
+    /// `synthesize`が音声データを返すまでにかかる時間を設定します。
+    ///
+    /// 既定は0です。合成中の中断やタイムアウトの検証に使用します。
+    pub fn set_latency(&self, latency: Duration) {
+        self.state.lock().latency = latency;
+    }
+
+    /// 操作のタイムアウトを模して使用不能にします。
+    ///
+    /// 以降の操作は`CevioAIError::Poisoned`を返し、`rebuild`で作り直せます。
+    pub fn poison(&self) {
+        self.state.lock().poisoned = true;
+    }
+
+    /// 使用不能でなければ状態をロックします。
+    fn state(&self) -> Result<MutexGuard<'_, FakeState>> {
+        let state = self.state.lock();
+        if state.poisoned {
+            return Err(CevioAIError::Poisoned);
+        }
+        Ok(state)
+    }
+
+    fn phonemes_for(text: &str, speed: Speed) -> Vec<PhonemeData> {
+        let unit = 0.1 * 2f64.powf((50.0 - f64::from(speed.get())) / 50.0);
+
+        let mut phonemes = vec![PhonemeData::new("sil".to_string(), 0.0, SILENCE)];
+        let mut time = SILENCE;
+        for c in text.chars().filter(|c| !c.is_whitespace()) {
+            let (phoneme, length) = if PUNCTUATION.contains(&c) {
+                ("pau", PAUSE)
+            } else {
+                (VOWELS[c as usize % VOWELS.len()], unit)
+            };
+            phonemes.push(PhonemeData::new(phoneme.to_string(), time, time + length));
+            time += length;
+        }
+        phonemes.push(PhonemeData::new("sil".to_string(), time, time + SILENCE));
+
+        phonemes
+    }
+}
+
+impl FakeState {
+    fn components_mut(&mut self) -> Result<&mut Vec<ComponentValue>> {
+        let cast = self.current.cast.clone().unwrap_or_default();
+        self.casts
+            .iter_mut()
+            .find(|(name, _)| *name == cast)
+            .map(|(_, components)| components)
+            .ok_or_else(|| CevioAIError::InvalidParameter(format!("Unknown cast: {cast}")))
+    }
+}
+
+impl Backend for FakeBackend {
+    fn start(&self, _no_wait: bool) -> Result<()> {
+        self.state()?.started = true;
+        Ok(())
+    }
+
+    fn close(&self, _mode: CloseMode) -> Result<()> {
+        self.state()?.started = false;
+        Ok(())
+    }
+
+    fn is_host_started(&self) -> Result<bool> {
+        Ok(self.state()?.started)
+    }
+
+    fn host_version(&self) -> Result<String> {
+        Ok(self.state()?.host_version.clone())
+    }
+
+    fn interface_version(&self) -> Result<String> {
+        Ok("1.0.0.0".to_string())
+    }
+
+    fn available_casts(&self) -> Result<Vec<String>> {
+        Ok(self
+            .state()?
+            .casts
+            .iter()
+            .map(|(name, _)| name.clone())
+            .collect())
+    }
+
+    fn current_cast(&self) -> Result<Cast> {
+        Ok(self.state()?.current.clone())
+    }
+
+    fn apply_cast(&self, cast: &Cast) -> Result<()> {
+        let mut state = self.state()?;
+
+        if let Some(ref name) = cast.cast {
+            let casts: Vec<String> = state.casts.iter().map(|(cast, _)| cast.clone()).collect();
+            state.current.cast = Some(CastName::validate(name, &casts)?.into());
+        }
+
+        let current = &mut state.current;
+        current.volume = cast.volume.or(current.volume);
+        current.speed = cast.speed.or(current.speed);
+        current.tone = cast.tone.or(current.tone);
+        current.tone_scale = cast.tone_scale.or(current.tone_scale);
+        current.alpha = cast.alpha.or(current.alpha);
+
+        Ok(())
+    }
+
+    fn component_values(&self) -> Result<Vec<ComponentValue>> {
+        Ok(self.state()?.components_mut()?.clone())
+    }
+
+    fn apply_components(&self, values: &[(String, u8)]) -> Result<()> {
+        let mut state = self.state()?;
+        let components = state.components_mut()?;
+
+        for (name, value) in values {
+            if *value > 100 {
+                return Err(CevioAIError::InvalidParameter(format!(
+                    "Component value must be 0-100, got {value}"
+                )));
+            }
+            let component = components
+                .iter_mut()
+                .find(|component| component.name == *name)
+                .ok_or_else(|| {
+                    CevioAIError::InvalidParameter(format!("Unknown component: {name}"))
+                })?;
+            component.value = *value;
+        }
+
+        Ok(())
+    }
+
+    fn speak_and_wait(&self, text: &str) -> Result<bool> {
+        self.state()?.spoken.push(text.to_string());
+        Ok(true)
+    }
+
+    fn stop(&self) -> Result<bool> {
+        self.state()?.stops += 1;
+        Ok(true)
+    }
+
+    fn text_duration(&self, text: &str) -> Result<f64> {
+        Ok(self
+            .phonemes(text)?
+            .last()
+            .map_or(0.0, PhonemeData::end_time))
+    }
+
+    fn phonemes(&self, text: &str) -> Result<Vec<PhonemeData>> {
+        let speed = self.state()?.current.speed.unwrap_or_default();
+        Ok(Self::phonemes_for(text, speed))
+    }
+
+    fn synthesize(&self, text: &str) -> Result<AudioBuffer> {
+        let (current, latency) = {
+            let state = self.state()?;
+            (state.current.clone(), state.latency)
+        };
+        thread::sleep(latency);
+        let phonemes = Self::phonemes_for(text, current.speed.unwrap_or_default());
+
+        let amplitude = f64::from(current.volume.unwrap_or_default().get()) / 100.0 * 8000.0;
+        let frequency =
+            220.0 * 2f64.powf((f64::from(current.tone.unwrap_or_default().get()) - 50.0) / 50.0);
+
+        let total = phonemes.last().map_or(0.0, PhonemeData::end_time);
+        let frames = (total * f64::from(SAMPLE_RATE)).round() as usize;
+        let mut samples = vec![0i16; frames];
+
+        for phoneme in phonemes
+            .iter()
+            .filter(|phoneme| VOWELS.contains(&phoneme.phoneme()))
+        {
+            let start = (phoneme.start_time() * f64::from(SAMPLE_RATE)).round() as usize;
+            let end = ((phoneme.end_time() * f64::from(SAMPLE_RATE)).round() as usize).min(frames);
+            for (index, sample) in samples[start..end].iter_mut().enumerate() {
+                let t = (start + index) as f64 / f64::from(SAMPLE_RATE);
+                *sample = (amplitude * (TAU * frequency * t).sin()) as i16;
+            }
+        }
+
+        AudioBuffer::new(SAMPLE_RATE, 1, samples)
+    }
+
+    fn is_poisoned(&self) -> bool {
+        self.state.lock().poisoned
+    }
+
+    fn rebuild(&self) -> Result<Self> {
+        let state = self.state.lock().clone();
+        Ok(Self {
+            state: Mutex::new(FakeState {
+                poisoned: false,
+                ..state
+            }),
+        })
+    }
+}
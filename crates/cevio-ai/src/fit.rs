@@ -0,0 +1,412 @@
+//! セリフの長さの調整
+//!
+//! このモジュールは、セリフが指定した長さに収まる話す速さ（`Speed`）を探す関数と、
+//! 合成後の音声の間（`pau`）を短くして長さを詰める関数を提供します。
+//! 字幕のタイミングに合わせた吹き替えなど、決まった時間枠にセリフを収めたい場合に利用できます。
+//!
+//! ```rust,no_run
+//! use cevio_ai::*;
+//!
+//! fn main() -> Result<()> {
+//!     let cevio = CevioAI::new()?;
+//!
+//!     let fit = fit_to_duration(&cevio, "本日はお日柄もよく、絶好の行楽日和となりました。", 3.0)?;
+//!     cevio.apply_cast(&fit.cast)?;
+//!     println!("{:.2}s (収まる: {})", fit.duration, fit.fits);
+//!     Ok(())
+//! }
+//! ```
+
+use crate::{
+    audio::AudioBuffer,
+    backend::Backend,
+    cevio::{Cast, PhonemeData},
+    error::{CevioAIError, Result},
+    parameter::Speed,
+    speed_search::{self, Bracket},
+};
+
+/// `fit_to_duration_with`の設定
+#[derive(Debug, Clone, PartialEq)]
+pub struct FitOptions {
+    /// 話す速さを最大にしても収まらない場合に、間を短くして収めるかどうか（既定値は`false`）
+    pub trim_pauses: bool,
+
+    /// 間を短くする場合に残す長さ（秒、既定値は0.05）
+    pub min_pause: f64,
+}
+
+impl Default for FitOptions {
+    fn default() -> Self {
+        Self {
+            trim_pauses: false,
+            min_pause: 0.05,
+        }
+    }
+}
+
+/// `fit_to_duration`の結果
+#[derive(Debug, Clone, PartialEq)]
+pub struct DurationFit {
+    /// 選択した話す速さを設定したキャスト
+    pub cast: Cast,
+
+    /// 間を短くした後のセリフの長さ（秒）
+    pub duration: f64,
+
+    /// 指定した長さに収まったかどうか
+    pub fits: bool,
+
+    /// 合成後に間から取り除く長さ（秒）
+    ///
+    /// 0より大きい場合は、合成した音声に`trim_pauses`を適用してください。
+    pub pause_trim: f64,
+}
+
+/// セリフが`max_seconds`秒に収まる話す速さを探します。
+///
+/// 現在の話す速さで収まる場合はそのまま、収まらない場合は収まる最も遅い話す速さを選択します。
+/// 話す速さを最大にしても収まらない場合は、最大の話す速さで`fits`が`false`の結果を返します。
+/// 探索後、話す速さは元の値に戻されます。
+///
+/// # Errors
+///
+/// `max_seconds`が正の数でない場合は`InvalidParameter`エラーを返します。
+pub fn fit_to_duration<B: Backend + ?Sized>(
+    backend: &B,
+    text: &str,
+    max_seconds: f64,
+) -> Result<DurationFit> {
+    fit_to_duration_with(backend, text, max_seconds, &FitOptions::default())
+}
+
+/// 設定を指定して、セリフが`max_seconds`秒に収まる話す速さを探します。
+///
+/// `FitOptions::trim_pauses`が`true`の場合、話す速さを最大にしても収まらなければ、
+/// 間を短くして収まるかどうかを調べます。
+///
+/// # Errors
+///
+/// `max_seconds`が正の数でない場合は`InvalidParameter`エラーを返します。
+pub fn fit_to_duration_with<B: Backend + ?Sized>(
+    backend: &B,
+    text: &str,
+    max_seconds: f64,
+    options: &FitOptions,
+) -> Result<DurationFit> {
+    speed_search::preserving_speed(backend, |original| {
+        let current = original.speed.unwrap_or_default();
+        let with_speed = |speed: Speed| Cast {
+            speed: Some(speed),
+            ..original.clone()
+        };
+
+        let (speed, duration) = search_fit(current, max_seconds, |speed| {
+            backend.apply_cast(&with_speed(speed))?;
+            backend.text_duration(text)
+        })?;
+
+        let mut fit = DurationFit {
+            cast: with_speed(speed),
+            duration,
+            fits: duration <= max_seconds,
+            pause_trim: 0.0,
+        };
+        if !fit.fits && options.trim_pauses {
+            // 探索の最後に最大の話す速さを設定しているため、そのまま音素データを取得できる
+            let phonemes = backend.phonemes(text)?;
+            let trimmable = trimmable_pause(&phonemes, options.min_pause);
+            let excess = duration - max_seconds;
+            fit.pause_trim = excess.min(trimmable);
+            fit.duration = duration - fit.pause_trim;
+            fit.fits = excess <= trimmable;
+        }
+        Ok(fit)
+    })
+}
+
+/// 複数のセリフについて、それぞれの長さに収まる話す速さを探します。
+///
+/// 各セリフの探索は元の話す速さから始めます。
+///
+/// # Arguments
+///
+/// * `backend` - 使用するバックエンド
+/// * `lines` - セリフと収める長さ（秒）の組
+/// * `options` - 設定
+///
+/// # Errors
+///
+/// いずれかのセリフの探索に失敗した場合はエラーを返します。
+pub fn fit_lines_to_durations<B: Backend + ?Sized, S: AsRef<str>>(
+    backend: &B,
+    lines: &[(S, f64)],
+    options: &FitOptions,
+) -> Result<Vec<DurationFit>> {
+    lines
+        .iter()
+        .map(|(text, max_seconds)| {
+            fit_to_duration_with(backend, text.as_ref(), *max_seconds, options)
+        })
+        .collect()
+}
+
+/// 長さが話す速さに対して単調減少することを利用して、収まる最も遅い話す速さを探します。
+///
+/// 戻り値は選択した話す速さとそのときの長さです。
+/// 最後に`duration_at`を呼び出した話す速さが選択した話す速さになります。
+fn search_fit(
+    current: Speed,
+    max_seconds: f64,
+    mut duration_at: impl FnMut(Speed) -> Result<f64>,
+) -> Result<(Speed, f64)> {
+    if !(max_seconds > 0.0 && max_seconds.is_finite()) {
+        return Err(CevioAIError::InvalidParameter(format!(
+            "Maximum duration must be positive, got {max_seconds}"
+        )));
+    }
+
+    let mut last = current;
+    let bracket = speed_search::search(
+        current,
+        |speed| {
+            last = speed;
+            duration_at(speed)
+        },
+        |&duration| duration <= max_seconds,
+    )?;
+    Ok(match bracket {
+        Bracket::Start(measured) | Bracket::Exhausted(measured) => measured,
+        Bracket::Between { above, .. } => {
+            if last != above.0 {
+                // 最後に測定した話す速さを選択した話す速さに合わせる
+                duration_at(above.0)?;
+            }
+            above
+        }
+    })
+}
+
+/// セリフの途中の間（連続する`pau`・`sil`）の区間を取得します。
+fn pauses(phonemes: &[PhonemeData]) -> Vec<(f64, f64)> {
+    let is_silent = |phoneme: &PhonemeData| phoneme.to_phoneme().is_silent();
+    let Some(start) = phonemes.iter().position(|phoneme| !is_silent(phoneme)) else {
+        return Vec::new();
+    };
+    let end = phonemes
+        .iter()
+        .rposition(|phoneme| !is_silent(phoneme))
+        .unwrap_or(start);
+
+    let mut pauses: Vec<(f64, f64)> = Vec::new();
+    let mut previous_silent = false;
+    for phoneme in &phonemes[start..=end] {
+        let silent = is_silent(phoneme);
+        match pauses.last_mut() {
+            Some(pause) if silent && previous_silent => pause.1 = phoneme.end_time(),
+            _ if silent => pauses.push((phoneme.start_time(), phoneme.end_time())),
+            _ => {}
+        }
+        previous_silent = silent;
+    }
+    pauses
+}
+
+/// 間を`min_pause`秒まで短くした場合に取り除ける長さ（秒）を取得します。
+fn trimmable_pause(phonemes: &[PhonemeData], min_pause: f64) -> f64 {
+    pauses(phonemes)
+        .iter()
+        .map(|(start, end)| (end - start - min_pause).max(0.0))
+        .sum()
+}
+
+/// 合成した音声の間を短くします。
+///
+/// セリフの途中の間から、取り除ける長さに比例して合計`seconds`秒を取り除きます。
+/// 各間は`min_pause`秒より短くなりません。音素データの時間も合わせて調整されます。
+///
+/// # Arguments
+///
+/// * `audio` - 合成した音声データ
+/// * `phonemes` - 音声データに対応する音素データ
+/// * `seconds` - 取り除く長さ（秒）
+/// * `min_pause` - 各間に残す長さ（秒）
+///
+/// # Errors
+///
+/// `seconds`が取り除ける長さを超える場合は`InvalidParameter`エラーを返します。
+pub fn trim_pauses(
+    audio: &AudioBuffer,
+    phonemes: &[PhonemeData],
+    seconds: f64,
+    min_pause: f64,
+) -> Result<(AudioBuffer, Vec<PhonemeData>)> {
+    let trimmable = trimmable_pause(phonemes, min_pause);
+    if seconds < 0.0 || seconds > trimmable + 1e-9 {
+        return Err(CevioAIError::InvalidParameter(format!(
+            "Cannot trim {seconds}s of pauses, only {trimmable}s available"
+        )));
+    }
+    let ratio = if trimmable > 0.0 {
+        seconds / trimmable
+    } else {
+        0.0
+    };
+
+    // 各間の中央から取り除く区間
+    let cuts: Vec<(f64, f64)> = pauses(phonemes)
+        .into_iter()
+        .filter_map(|(start, end)| {
+            let cut = (end - start - min_pause).max(0.0) * ratio;
+            let middle = (start + end) / 2.0;
+            (cut > 0.0).then(|| (middle - cut / 2.0, middle + cut / 2.0))
+        })
+        .collect();
+
+    // 時間 t より前に取り除かれる長さ
+    let removed_before = |time: f64| -> f64 {
+        cuts.iter()
+            .map(|&(start, end)| (time.min(end) - start).max(0.0))
+            .sum()
+    };
+    let phonemes = phonemes
+        .iter()
+        .map(|phoneme| {
+            PhonemeData::new(
+                phoneme.phoneme().to_string(),
+                phoneme.start_time() - removed_before(phoneme.start_time()),
+                phoneme.end_time() - removed_before(phoneme.end_time()),
+            )
+        })
+        .collect();
+
+    let channels = usize::from(audio.channels());
+    let sample_rate = f64::from(audio.sample_rate());
+    let frame = |time: f64| ((time * sample_rate).round().max(0.0) as usize).min(audio.frames());
+    let mut samples = Vec::with_capacity(audio.samples().len());
+    let mut position = 0;
+    for &(start, end) in &cuts {
+        let (start, end) = (frame(start), frame(end));
+        samples.extend_from_slice(&audio.samples()[position * channels..start * channels]);
+        position = end;
+    }
+    samples.extend_from_slice(&audio.samples()[position * channels..]);
+
+    Ok((
+        AudioBuffer::new(audio.sample_rate(), audio.channels(), samples)?,
+        phonemes,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn speed(value: u8) -> Speed {
+        Speed::new(value).unwrap()
+    }
+
+    /// 話す速さ50で4秒、100で2秒
+    fn duration(speed: Speed) -> Result<f64> {
+        Ok(4.0 * 2f64.powf((50.0 - f64::from(speed.get())) / 50.0))
+    }
+
+    #[test]
+    fn keeps_speed_when_it_fits() {
+        let mut calls = Vec::new();
+        let (selected, seconds) = search_fit(speed(50), 5.0, |speed| {
+            calls.push(speed.get());
+            duration(speed)
+        })
+        .unwrap();
+
+        assert_eq!(selected.get(), 50);
+        assert!((seconds - 4.0).abs() < 1e-9);
+        assert_eq!(calls, [50]);
+    }
+
+    #[test]
+    fn finds_slowest_fitting_speed() {
+        let mut last = 0;
+        let (selected, seconds) = search_fit(speed(50), 3.0, |speed| {
+            last = speed.get();
+            duration(speed)
+        })
+        .unwrap();
+
+        // 4 * 2^((50 - s) / 50) <= 3 となる最小の s は 71
+        assert_eq!(selected.get(), 71);
+        assert!(seconds <= 3.0);
+        assert!(duration(speed(70)).unwrap() > 3.0);
+        assert_eq!(last, 71);
+    }
+
+    #[test]
+    fn reports_when_nothing_fits() {
+        let (selected, seconds) = search_fit(speed(50), 1.0, duration).unwrap();
+
+        assert_eq!(selected.get(), 100);
+        assert!((seconds - 2.0).abs() < 1e-9);
+        assert!(search_fit(speed(50), 0.0, duration).is_err());
+    }
+
+    #[test]
+    fn trims_pauses() {
+        let phonemes: Vec<_> = [
+            ("sil", 0.0, 0.1),
+            ("a", 0.1, 0.3),
+            ("pau", 0.3, 0.7),
+            ("i", 0.7, 0.9),
+            ("pau", 0.9, 1.1),
+            ("u", 1.1, 1.3),
+            ("sil", 1.3, 1.4),
+        ]
+        .iter()
+        .map(|&(phoneme, start, end)| PhonemeData::new(phoneme.to_string(), start, end))
+        .collect();
+        let audio = AudioBuffer::new(1000, 1, vec![1; 1400]).unwrap();
+
+        // 取り除ける長さは 0.35 + 0.15 = 0.5 秒
+        assert!((trimmable_pause(&phonemes, 0.05) - 0.5).abs() < 1e-9);
+        let (trimmed, timed) = trim_pauses(&audio, &phonemes, 0.25, 0.05).unwrap();
+
+        assert_eq!(trimmed.frames(), 1150);
+        assert!((timed[2].end_time() - timed[2].start_time() - 0.225).abs() < 1e-9);
+        assert!((timed[4].end_time() - timed[4].start_time() - 0.125).abs() < 1e-9);
+        assert!((timed[6].end_time() - 1.15).abs() < 1e-9);
+        assert!(trim_pauses(&audio, &phonemes, 0.6, 0.05).is_err());
+    }
+
+    #[cfg(feature = "fake")]
+    #[test]
+    fn fits_fake_backend() {
+        use crate::{cevio::CastBuilder, fake::FakeBackend};
+
+        let backend = FakeBackend::new();
+        backend
+            .apply_cast(&CastBuilder::default().with_defaults().build().unwrap())
+            .unwrap();
+
+        // 速さ50で 0.1 + 9 * 0.1 + 0.2 + 0.1 = 1.3 秒
+        let text = "こんにちは、いい天気";
+        let fits = fit_lines_to_durations(
+            &backend,
+            &[(text, 2.0), (text, 1.0), (text, 0.6)],
+            &FitOptions {
+                trim_pauses: true,
+                ..FitOptions::default()
+            },
+        )
+        .unwrap();
+
+        assert_eq!(fits[0].cast.speed.unwrap().get(), 50);
+        assert!(fits[1].fits);
+        assert!(fits[1].cast.speed.unwrap().get() > 50);
+        assert!(fits[1].duration <= 1.0);
+        assert_eq!(fits[1].pause_trim, 0.0);
+        // 速さ100で 0.1 + 9 * 0.05 + 0.2 + 0.1 = 0.85 秒、間は 0.15 秒まで詰められる
+        assert!(!fits[2].fits);
+        assert!((fits[2].pause_trim - 0.15).abs() < 1e-9);
+        assert_eq!(backend.current_cast().unwrap().speed.unwrap().get(), 50);
+    }
+}
@@ -0,0 +1,964 @@
+//! `IDispatch`による遅延バインディング
+//!
+//! winmdから生成したバインディングはインターフェースのvtableの並びに依存するため、
+//! ホストの更新で並びが変わると誤ったメソッドを呼び出してしまいます。
+//! このモジュールはメンバー名から`IDispatch::GetIDsOfNames`でDISPIDを取得し、
+//! `IDispatch::Invoke`で呼び出すワーカーのハンドラを提供します。
+//! 引数と戻り値は[`Value`]と`VARIANT`の間で変換します。
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::mem::ManuallyDrop;
+use std::path::Path;
+use std::ptr;
+
+use windows::{
+    core::{BSTR, GUID, HRESULT, PCWSTR},
+    Win32::{
+        Foundation::{DISP_E_EXCEPTION, VARIANT_BOOL},
+        System::{
+            Com::{
+                CLSIDFromProgID, CoCreateInstance, IDispatch, CLSCTX_INPROC_SERVER, DISPATCH_FLAGS,
+                DISPATCH_METHOD, DISPATCH_PROPERTYGET, DISPATCH_PROPERTYPUT, DISPPARAMS, EXCEPINFO,
+            },
+            Ole::DISPID_PROPERTYPUT,
+            Variant::{
+                VariantClear, VARIANT, VT_BOOL, VT_BSTR, VT_DISPATCH, VT_EMPTY, VT_I2, VT_I4,
+                VT_INT, VT_NULL, VT_R4, VT_R8, VT_UI1, VT_UI2, VT_UI4, VT_UINT,
+            },
+        },
+    },
+};
+
+use cevio_ai_sys::ComArray;
+
+use crate::{
+    cevio::{Capabilities, CloseMode, PhonemeData, Product},
+    com_manager::{ApartmentModel, ComGuard},
+    error::{CevioAIError, Result},
+    worker::{respond, ComponentEntry, Handle, HandleTable, Handler, Request, TalkerParameter},
+};
+
+/// `GetIDsOfNames`・`Invoke`に渡すロケール（`LOCALE_USER_DEFAULT`）
+const LOCALE_USER_DEFAULT: u32 = 0x0400;
+
+/// `VARIANT`として受け渡す値
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Value {
+    Empty,
+    Bool(bool),
+    I32(i32),
+    U32(u32),
+    F64(f64),
+    String(String),
+    Object(IDispatch),
+}
+
+impl Value {
+    /// エラーメッセージ用の型名
+    const fn type_name(&self) -> &'static str {
+        match self {
+            Self::Empty => "Empty",
+            Self::Bool(_) => "Bool",
+            Self::I32(_) => "I32",
+            Self::U32(_) => "U32",
+            Self::F64(_) => "F64",
+            Self::String(_) => "String",
+            Self::Object(_) => "Object",
+        }
+    }
+
+    fn mismatch(&self, expected: &str) -> CevioAIError {
+        CevioAIError::InvalidVariant(format!("expected {expected}, got {}", self.type_name()))
+    }
+
+    pub(crate) fn into_bool(self) -> Result<bool> {
+        match self {
+            Self::Bool(value) => Ok(value),
+            other => Err(other.mismatch("Bool")),
+        }
+    }
+
+    /// 整数として取り出します。範囲外の値は失敗します。
+    pub(crate) fn into_i32(self) -> Result<i32> {
+        match self {
+            Self::I32(value) => Ok(value),
+            Self::U32(value) => i32::try_from(value)
+                .map_err(|_| CevioAIError::InvalidVariant(format!("{value} does not fit in I32"))),
+            other => Err(other.mismatch("I32")),
+        }
+    }
+
+    /// 符号なし整数として取り出します。負の値は失敗します。
+    pub(crate) fn into_u32(self) -> Result<u32> {
+        match self {
+            Self::U32(value) => Ok(value),
+            Self::I32(value) => u32::try_from(value)
+                .map_err(|_| CevioAIError::InvalidVariant(format!("{value} does not fit in U32"))),
+            other => Err(other.mismatch("U32")),
+        }
+    }
+
+    pub(crate) fn into_f64(self) -> Result<f64> {
+        match self {
+            Self::F64(value) => Ok(value),
+            Self::I32(value) => Ok(f64::from(value)),
+            Self::U32(value) => Ok(f64::from(value)),
+            other => Err(other.mismatch("F64")),
+        }
+    }
+
+    /// 文字列として取り出します。`Empty`は空文字列として扱います。
+    pub(crate) fn into_string(self) -> Result<String> {
+        match self {
+            Self::String(value) => Ok(value),
+            Self::Empty => Ok(String::new()),
+            other => Err(other.mismatch("String")),
+        }
+    }
+
+    pub(crate) fn into_object(self) -> Result<IDispatch> {
+        match self {
+            Self::Object(value) => Ok(value),
+            other => Err(other.mismatch("Object")),
+        }
+    }
+}
+
+impl From<bool> for Value {
+    fn from(value: bool) -> Self {
+        Self::Bool(value)
+    }
+}
+
+impl From<i32> for Value {
+    fn from(value: i32) -> Self {
+        Self::I32(value)
+    }
+}
+
+impl From<u32> for Value {
+    fn from(value: u32) -> Self {
+        Self::U32(value)
+    }
+}
+
+impl From<f64> for Value {
+    fn from(value: f64) -> Self {
+        Self::F64(value)
+    }
+}
+
+impl From<&str> for Value {
+    fn from(value: &str) -> Self {
+        Self::String(value.to_string())
+    }
+}
+
+/// 所有権を持つ`VARIANT`
+///
+/// 破棄時に`VariantClear`で中身（`BSTR`やインターフェース）を解放します。
+/// `DISPPARAMS`に配列として渡すため、`VARIANT`と同じレイアウトにしています。
+#[repr(transparent)]
+struct Variant(VARIANT);
+
+impl Variant {
+    const fn empty() -> Self {
+        // VT_EMPTYはすべて0
+        Self(unsafe { std::mem::zeroed() })
+    }
+}
+
+impl From<&Value> for Variant {
+    fn from(value: &Value) -> Self {
+        let mut variant = Self::empty();
+        unsafe {
+            let inner = &mut *variant.0.Anonymous.Anonymous;
+            match value {
+                Value::Empty => {}
+                Value::Bool(value) => {
+                    inner.vt = VT_BOOL;
+                    inner.Anonymous.boolVal = VARIANT_BOOL::from(*value);
+                }
+                Value::I32(value) => {
+                    inner.vt = VT_I4;
+                    inner.Anonymous.lVal = *value;
+                }
+                Value::U32(value) => {
+                    inner.vt = VT_UI4;
+                    inner.Anonymous.ulVal = *value;
+                }
+                Value::F64(value) => {
+                    inner.vt = VT_R8;
+                    inner.Anonymous.dblVal = *value;
+                }
+                Value::String(value) => {
+                    inner.vt = VT_BSTR;
+                    inner.Anonymous.bstrVal = ManuallyDrop::new(BSTR::from(value.as_str()));
+                }
+                Value::Object(value) => {
+                    inner.vt = VT_DISPATCH;
+                    inner.Anonymous.pdispVal = ManuallyDrop::new(Some(value.clone()));
+                }
+            }
+        }
+        variant
+    }
+}
+
+impl TryFrom<&Variant> for Value {
+    type Error = CevioAIError;
+
+    fn try_from(variant: &Variant) -> Result<Self> {
+        unsafe {
+            let inner = &*variant.0.Anonymous.Anonymous;
+            let value = &inner.Anonymous;
+            Ok(match inner.vt {
+                VT_EMPTY | VT_NULL => Self::Empty,
+                VT_BOOL => Self::Bool(value.boolVal.as_bool()),
+                VT_I2 => Self::I32(i32::from(value.iVal)),
+                VT_I4 | VT_INT => Self::I32(value.lVal),
+                VT_UI1 => Self::U32(u32::from(value.bVal)),
+                VT_UI2 => Self::U32(u32::from(value.uiVal)),
+                VT_UI4 | VT_UINT => Self::U32(value.ulVal),
+                VT_R4 => Self::F64(f64::from(value.fltVal)),
+                VT_R8 => Self::F64(value.dblVal),
+                VT_BSTR => Self::String(value.bstrVal.to_string()),
+                VT_DISPATCH => match &*value.pdispVal {
+                    Some(dispatch) => Self::Object(dispatch.clone()),
+                    None => Self::Empty,
+                },
+                vt => {
+                    return Err(CevioAIError::InvalidVariant(format!(
+                        "unsupported VARTYPE {}",
+                        vt.0
+                    )))
+                }
+            })
+        }
+    }
+}
+
+impl Drop for Variant {
+    fn drop(&mut self) {
+        unsafe {
+            let _ = VariantClear(&mut self.0);
+        }
+    }
+}
+
+/// メンバーを持つオブジェクトの種類
+///
+/// 同じ種類のオブジェクトはDISPIDも同じため、キャッシュのキーに使用します。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Kind {
+    Service,
+    Talker,
+    Component,
+    ComponentArray,
+    SpeakingState,
+    StringArray,
+    PhonemeData,
+    PhonemeDataArray,
+}
+
+/// DISPIDのキャッシュ
+#[derive(Debug, Default)]
+struct DispidCache {
+    ids: HashMap<(Kind, &'static str), i32>,
+}
+
+impl DispidCache {
+    /// キャッシュ済みのDISPIDを返し、なければ`lookup`で取得して保存します。
+    ///
+    /// 取得に失敗した場合は保存しません。
+    fn get_or_lookup(
+        &mut self,
+        kind: Kind,
+        name: &'static str,
+        lookup: impl FnOnce() -> Result<i32>,
+    ) -> Result<i32> {
+        if let Some(&id) = self.ids.get(&(kind, name)) {
+            return Ok(id);
+        }
+        let id = lookup()?;
+        self.ids.insert((kind, name), id);
+        Ok(id)
+    }
+}
+
+/// メンバー名による`IDispatch`の呼び出し
+#[derive(Debug, Default)]
+struct Dispatcher {
+    cache: DispidCache,
+}
+
+impl Dispatcher {
+    /// メンバー名に対応するDISPIDを取得します。
+    fn dispid(&mut self, object: &IDispatch, kind: Kind, name: &'static str) -> Result<i32> {
+        self.cache.get_or_lookup(kind, name, || {
+            let wide = to_wide(name);
+            let names = [PCWSTR(wide.as_ptr())];
+            let mut id = 0;
+            unsafe {
+                object.GetIDsOfNames(
+                    &GUID::zeroed(),
+                    names.as_ptr(),
+                    1,
+                    LOCALE_USER_DEFAULT,
+                    &mut id,
+                )
+            }?;
+            Ok(id)
+        })
+    }
+
+    /// メンバーを持つかどうかを返します。
+    fn has_member(&mut self, object: &IDispatch, kind: Kind, name: &'static str) -> bool {
+        self.dispid(object, kind, name).is_ok()
+    }
+
+    fn invoke(
+        &mut self,
+        object: &IDispatch,
+        kind: Kind,
+        name: &'static str,
+        flags: DISPATCH_FLAGS,
+        args: &[Value],
+    ) -> Result<Value> {
+        let id = self.dispid(object, kind, name)?;
+
+        // DISPPARAMSの引数は逆順に並べる
+        let mut args: Vec<Variant> = args.iter().rev().map(Variant::from).collect();
+        let mut named = DISPID_PROPERTYPUT;
+        let is_put = flags == DISPATCH_PROPERTYPUT;
+        let params = DISPPARAMS {
+            rgvarg: args.as_mut_ptr().cast(),
+            rgdispidNamedArgs: if is_put { &mut named } else { ptr::null_mut() },
+            cArgs: args.len() as u32,
+            cNamedArgs: u32::from(is_put),
+        };
+
+        let mut result = Variant::empty();
+        let mut exception = EXCEPINFO::default();
+        let invoked = unsafe {
+            object.Invoke(
+                id,
+                &GUID::zeroed(),
+                LOCALE_USER_DEFAULT,
+                flags,
+                &params,
+                Some(&mut result.0),
+                Some(&mut exception),
+                None,
+            )
+        };
+        let error = exception_error(&mut exception, invoked.err());
+
+        match error {
+            None => Value::try_from(&result),
+            Some(e) => Err(e.into()),
+        }
+    }
+
+    /// プロパティを取得します。
+    fn get(&mut self, object: &IDispatch, kind: Kind, name: &'static str) -> Result<Value> {
+        self.invoke(object, kind, name, DISPATCH_PROPERTYGET, &[])
+    }
+
+    /// プロパティを設定します。
+    fn put(
+        &mut self,
+        object: &IDispatch,
+        kind: Kind,
+        name: &'static str,
+        value: Value,
+    ) -> Result<()> {
+        self.invoke(object, kind, name, DISPATCH_PROPERTYPUT, &[value])?;
+        Ok(())
+    }
+
+    /// メソッドを呼び出します。
+    fn call(
+        &mut self,
+        object: &IDispatch,
+        kind: Kind,
+        name: &'static str,
+        args: &[Value],
+    ) -> Result<Value> {
+        self.invoke(object, kind, name, DISPATCH_METHOD, args)
+    }
+
+    /// `Length`と`At`を持つ配列オブジェクトの要素を取得します。
+    fn elements(&mut self, array: &IDispatch, kind: Kind) -> Result<Vec<Value>> {
+        DispatchArray {
+            dispatcher: RefCell::new(self),
+            array,
+            kind,
+        }
+        .to_vec()
+    }
+}
+
+/// `IDispatch`で`Length`と`At`を呼び出す配列
+struct DispatchArray<'a> {
+    dispatcher: RefCell<&'a mut Dispatcher>,
+    array: &'a IDispatch,
+    kind: Kind,
+}
+
+impl ComArray for DispatchArray<'_> {
+    type Item = Value;
+    type Error = CevioAIError;
+
+    fn len(&self) -> Result<usize> {
+        let len = self
+            .dispatcher
+            .borrow_mut()
+            .get(self.array, self.kind, "Length")?
+            .into_u32()?;
+        Ok(len as usize)
+    }
+
+    fn at(&self, index: usize) -> Result<Value> {
+        let index = i32::try_from(index)
+            .map_err(|_| CevioAIError::InvalidParameter(format!("index {index} out of range")))?;
+        self.dispatcher
+            .borrow_mut()
+            .call(self.array, self.kind, "At", &[Value::I32(index)])
+    }
+}
+
+/// NUL終端のUTF-16文字列に変換します。
+fn to_wide(s: &str) -> Vec<u16> {
+    s.encode_utf16().chain(std::iter::once(0)).collect()
+}
+
+/// `Invoke`のエラーを例外情報のHRESULTと説明に置き換え、`EXCEPINFO`が所有する文字列を解放します。
+///
+/// `DISP_E_EXCEPTION`ではホストのエラーが`scode`（または`wCode`）にあるため、
+/// `pfnDeferredFillIn`で例外情報を埋めてからそちらを使用します。
+fn exception_error(
+    exception: &mut EXCEPINFO,
+    error: Option<windows::core::Error>,
+) -> Option<windows::core::Error> {
+    if error.as_ref().is_some_and(|e| e.code() == DISP_E_EXCEPTION) {
+        if let Some(fill_in) = exception.pfnDeferredFillIn {
+            // 失敗しても埋められた範囲の情報を使用する
+            let _ = unsafe { fill_in(exception) };
+        }
+    }
+
+    let description = unsafe {
+        let description = exception.bstrDescription.to_string();
+        ManuallyDrop::drop(&mut exception.bstrSource);
+        ManuallyDrop::drop(&mut exception.bstrDescription);
+        ManuallyDrop::drop(&mut exception.bstrHelpFile);
+        description
+    };
+
+    let error = error?;
+    let code = if error.code() != DISP_E_EXCEPTION {
+        error.code()
+    } else if exception.scode != 0 {
+        HRESULT(exception.scode)
+    } else if exception.wCode != 0 {
+        // wCodeはFACILITY_DISPATCHのエラーコード
+        HRESULT((0x8002_0000 | u32::from(exception.wCode)) as i32)
+    } else {
+        error.code()
+    };
+
+    Some(if description.is_empty() {
+        windows::core::Error::from(code)
+    } else {
+        windows::core::Error::new(code, description)
+    })
+}
+
+/// ProgIDからオブジェクトを作成します。
+fn create_from_prog_id(prog_id: &str) -> windows::core::Result<IDispatch> {
+    let wide = to_wide(prog_id);
+    unsafe {
+        let clsid = CLSIDFromProgID(PCWSTR(wide.as_ptr()))?;
+        CoCreateInstance(&clsid, None, CLSCTX_INPROC_SERVER)
+    }
+}
+
+/// V40のProgIDからオブジェクトを作成し、できない場合は従来のProgIDを試します。
+///
+/// どちらも作成できない場合はV40のエラーに`CreateInstance`とProgIDを添えて返します。
+fn create((v40, legacy): (&str, &str)) -> Result<IDispatch> {
+    create_from_prog_id(v40)
+        .or_else(|e| create_from_prog_id(legacy).map_err(|_| e))
+        .map_err(|e| CevioAIError::from(e).with_context("CreateInstance", vec![v40.to_string()]))
+}
+
+impl TalkerParameter {
+    /// トークインターフェースのプロパティ名
+    const fn member(self) -> &'static str {
+        match self {
+            Self::Volume => "Volume",
+            Self::Speed => "Speed",
+            Self::Tone => "Tone",
+            Self::ToneScale => "ToneScale",
+            Self::Alpha => "Alpha",
+        }
+    }
+}
+
+/// V40と従来のProgIDの組
+type ProgIds = (&'static str, &'static str);
+
+/// 製品ごとの制御・トークオブジェクトのProgID
+const fn prog_ids(product: Product) -> (ProgIds, ProgIds) {
+    match product {
+        Product::AI => (
+            (
+                "CeVIO.Talk.RemoteService2.ServiceControl2V40",
+                "CeVIO.Talk.RemoteService2.ServiceControl2",
+            ),
+            (
+                "CeVIO.Talk.RemoteService2.Talker2V40",
+                "CeVIO.Talk.RemoteService2.Talker2",
+            ),
+        ),
+        Product::CreativeStudio => (
+            (
+                "CeVIO.Talk.RemoteService.ServiceControlV40",
+                "CeVIO.Talk.RemoteService.ServiceControl",
+            ),
+            (
+                "CeVIO.Talk.RemoteService.TalkerV40",
+                "CeVIO.Talk.RemoteService.Talker",
+            ),
+        ),
+    }
+}
+
+/// `IDispatch`経由でCOMオブジェクトを操作するハンドラ
+pub(crate) struct LateBoundHandler {
+    service: IDispatch,
+    talker: IDispatch,
+    capabilities: Capabilities,
+    dispatcher: Dispatcher,
+    components: HandleTable<IDispatch>,
+    states: HandleTable<IDispatch>,
+    // COMオブジェクトの解放後にCOMを終了させるため、最後に宣言する
+    _com_guard: ComGuard,
+}
+
+impl LateBoundHandler {
+    /// COMを初期化し、ProgIDから指定した製品のCOMオブジェクトを作成します。
+    ///
+    /// 対応機能はメンバー名を問い合わせて判定します。
+    pub(crate) fn new(apartment: ApartmentModel, product: Product) -> Result<Self> {
+        let com_guard = ComGuard::new(apartment)?;
+
+        let (service_ids, talker_ids) = prog_ids(product);
+        let service = create(service_ids)?;
+        let talker = create(talker_ids)?;
+
+        let mut dispatcher = Dispatcher::default();
+        let capabilities = Capabilities {
+            has_tone_scale: dispatcher.has_member(&talker, Kind::Talker, "ToneScale"),
+            has_host_version: dispatcher.has_member(&service, Kind::Service, "HostVersion"),
+        };
+
+        Ok(Self {
+            service,
+            talker,
+            capabilities,
+            dispatcher,
+            components: HandleTable::default(),
+            states: HandleTable::default(),
+            _com_guard: com_guard,
+        })
+    }
+
+    fn start_host(&mut self, no_wait: bool) -> Result<i32> {
+        self.dispatcher
+            .call(&self.service, Kind::Service, "StartHost", &[no_wait.into()])?
+            .into_i32()
+    }
+
+    fn close(&mut self, mode: CloseMode) -> Result<()> {
+        self.dispatcher.call(
+            &self.service,
+            Kind::Service,
+            "CloseHost",
+            &[(mode as i32).into()],
+        )?;
+        Ok(())
+    }
+
+    fn service_string(&mut self, name: &'static str) -> Result<String> {
+        if !self.capabilities.has_host_version {
+            return Err(CevioAIError::Unsupported(name.to_string()));
+        }
+        self.dispatcher
+            .get(&self.service, Kind::Service, name)?
+            .into_string()
+    }
+
+    fn is_host_started(&mut self) -> Result<bool> {
+        self.dispatcher
+            .get(&self.service, Kind::Service, "IsHostStarted")?
+            .into_bool()
+    }
+
+    fn check_parameter(&self, parameter: TalkerParameter) -> Result<()> {
+        if parameter == TalkerParameter::ToneScale && !self.capabilities.has_tone_scale {
+            return Err(CevioAIError::Unsupported("ToneScale".to_string()));
+        }
+        Ok(())
+    }
+
+    fn parameter(&mut self, parameter: TalkerParameter) -> Result<u32> {
+        self.check_parameter(parameter)?;
+        self.dispatcher
+            .get(&self.talker, Kind::Talker, parameter.member())?
+            .into_u32()
+    }
+
+    fn set_parameter(&mut self, parameter: TalkerParameter, value: u32) -> Result<()> {
+        self.check_parameter(parameter)?;
+        self.dispatcher
+            .put(&self.talker, Kind::Talker, parameter.member(), value.into())
+    }
+
+    fn cast(&mut self) -> Result<String> {
+        self.dispatcher
+            .get(&self.talker, Kind::Talker, "Cast")?
+            .into_string()
+    }
+
+    fn set_cast(&mut self, cast: &str) -> Result<()> {
+        self.dispatcher
+            .put(&self.talker, Kind::Talker, "Cast", cast.into())
+    }
+
+    fn available_casts(&mut self) -> Result<Vec<String>> {
+        let strings = self
+            .dispatcher
+            .get(&self.talker, Kind::Talker, "AvailableCasts")?
+            .into_object()?;
+        self.dispatcher
+            .elements(&strings, Kind::StringArray)?
+            .into_iter()
+            .map(Value::into_string)
+            .collect()
+    }
+
+    fn components(&mut self) -> Result<Vec<ComponentEntry>> {
+        let array = self
+            .dispatcher
+            .get(&self.talker, Kind::Talker, "Components")?
+            .into_object()?;
+
+        let mut entries = Vec::new();
+        for element in self.dispatcher.elements(&array, Kind::ComponentArray)? {
+            let component = element.into_object()?;
+            let id = self
+                .dispatcher
+                .get(&component, Kind::Component, "Id")?
+                .into_string()?;
+            let name = self
+                .dispatcher
+                .get(&component, Kind::Component, "Name")?
+                .into_string()?;
+            entries.push(ComponentEntry {
+                handle: self.components.insert(component),
+                id,
+                name,
+            });
+        }
+        Ok(entries)
+    }
+
+    fn component_value(&mut self, component: Handle) -> Result<u32> {
+        let component = self.components.get(component)?;
+        self.dispatcher
+            .get(component, Kind::Component, "Value")?
+            .into_u32()
+    }
+
+    fn set_component_value(&mut self, component: Handle, value: u32) -> Result<()> {
+        let component = self.components.get(component)?;
+        self.dispatcher
+            .put(component, Kind::Component, "Value", value.into())
+    }
+
+    fn speak(&mut self, text: &str) -> Result<Handle> {
+        let state = self
+            .dispatcher
+            .call(&self.talker, Kind::Talker, "Speak", &[text.into()])?
+            .into_object()?;
+        Ok(self.states.insert(state))
+    }
+
+    fn stop(&mut self) -> Result<bool> {
+        self.dispatcher
+            .call(&self.talker, Kind::Talker, "Stop", &[])?
+            .into_bool()
+    }
+
+    fn state_flag(&mut self, state: Handle, name: &'static str) -> Result<bool> {
+        let state = self.states.get(state)?;
+        self.dispatcher
+            .get(state, Kind::SpeakingState, name)?
+            .into_bool()
+    }
+
+    fn text_duration(&mut self, text: &str) -> Result<f64> {
+        self.dispatcher
+            .call(
+                &self.talker,
+                Kind::Talker,
+                "GetTextDuration",
+                &[text.into()],
+            )?
+            .into_f64()
+    }
+
+    fn phonemes(&mut self, text: &str) -> Result<Vec<PhonemeData>> {
+        let array = self
+            .dispatcher
+            .call(&self.talker, Kind::Talker, "GetPhonemes", &[text.into()])?
+            .into_object()?;
+
+        let mut phonemes = Vec::new();
+        for element in self.dispatcher.elements(&array, Kind::PhonemeDataArray)? {
+            let data = element.into_object()?;
+            let phoneme = self
+                .dispatcher
+                .get(&data, Kind::PhonemeData, "Phoneme")?
+                .into_string()?;
+            let start_time = self
+                .dispatcher
+                .get(&data, Kind::PhonemeData, "StartTime")?
+                .into_f64()?;
+            let end_time = self
+                .dispatcher
+                .get(&data, Kind::PhonemeData, "EndTime")?
+                .into_f64()?;
+            phonemes.push(PhonemeData::new(phoneme, start_time, end_time));
+        }
+        Ok(phonemes)
+    }
+
+    fn output_wave_to_file(&mut self, text: &str, path: &Path) -> Result<bool> {
+        let path = path.to_string_lossy();
+        self.dispatcher
+            .call(
+                &self.talker,
+                Kind::Talker,
+                "OutputWaveToFile",
+                &[text.into(), path.as_ref().into()],
+            )?
+            .into_bool()
+    }
+}
+
+impl Handler for LateBoundHandler {
+    fn handle(&mut self, request: Request) {
+        match request {
+            Request::Capabilities { reply } => respond(reply, Ok(self.capabilities)),
+            Request::StartHost { no_wait, reply } => respond(reply, self.start_host(no_wait)),
+            Request::CloseHost { mode, reply } => respond(reply, self.close(mode)),
+            Request::HostVersion { reply } => respond(reply, self.service_string("HostVersion")),
+            Request::InterfaceVersion { reply } => {
+                respond(reply, self.service_string("InterfaceVersion"));
+            }
+            Request::IsHostStarted { reply } => respond(reply, self.is_host_started()),
+            Request::Parameter { parameter, reply } => respond(reply, self.parameter(parameter)),
+            Request::SetParameter {
+                parameter,
+                value,
+                reply,
+            } => respond(reply, self.set_parameter(parameter, value)),
+            Request::Cast { reply } => respond(reply, self.cast()),
+            Request::SetCast { cast, reply } => respond(reply, self.set_cast(&cast)),
+            Request::AvailableCasts { reply } => respond(reply, self.available_casts()),
+            Request::Components { reply } => respond(reply, self.components()),
+            Request::ComponentValue { component, reply } => {
+                respond(reply, self.component_value(component));
+            }
+            Request::SetComponentValue {
+                component,
+                value,
+                reply,
+            } => respond(reply, self.set_component_value(component, value)),
+            Request::Speak { text, reply } => respond(reply, self.speak(&text)),
+            Request::Stop { reply } => respond(reply, self.stop()),
+            Request::IsCompleted { state, reply } => {
+                respond(reply, self.state_flag(state, "IsCompleted"));
+            }
+            Request::IsSucceeded { state, reply } => {
+                respond(reply, self.state_flag(state, "IsSucceeded"));
+            }
+            Request::TextDuration { text, reply } => respond(reply, self.text_duration(&text)),
+            Request::Phonemes { text, reply } => respond(reply, self.phonemes(&text)),
+            Request::OutputWaveToFile { text, path, reply } => {
+                respond(reply, self.output_wave_to_file(&text, &path));
+            }
+            Request::Release { handle } => {
+                self.components.remove(handle);
+                self.states.remove(handle);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ComErrorKind;
+
+    #[test]
+    fn integer_conversions_check_range() {
+        assert_eq!(Value::I32(50).into_u32().unwrap(), 50);
+        assert_eq!(Value::U32(50).into_i32().unwrap(), 50);
+        assert!(matches!(
+            Value::I32(-1).into_u32(),
+            Err(CevioAIError::InvalidVariant(_))
+        ));
+        assert!(matches!(
+            Value::U32(u32::MAX).into_i32(),
+            Err(CevioAIError::InvalidVariant(_))
+        ));
+        assert_eq!(Value::U32(3).into_f64().unwrap(), 3.0);
+    }
+
+    #[test]
+    fn mismatched_types_are_rejected() {
+        assert!(Value::String("1".to_string()).into_u32().is_err());
+        assert!(Value::I32(1).into_bool().is_err());
+        assert!(Value::Bool(true).into_object().is_err());
+        assert_eq!(Value::Empty.into_string().unwrap(), "");
+    }
+
+    #[test]
+    fn variant_round_trip() {
+        for value in [
+            Value::Empty,
+            Value::Bool(true),
+            Value::Bool(false),
+            Value::I32(-5),
+            Value::U32(100),
+            Value::F64(1.25),
+            Value::String("こんにちは".to_string()),
+        ] {
+            let variant = Variant::from(&value);
+            assert_eq!(Value::try_from(&variant).unwrap(), value);
+        }
+    }
+
+    #[test]
+    fn narrow_variants_are_widened() {
+        let mut variant = Variant::empty();
+        unsafe {
+            let inner = &mut *variant.0.Anonymous.Anonymous;
+            inner.vt = VT_UI1;
+            inner.Anonymous.bVal = 7;
+        }
+        assert_eq!(Value::try_from(&variant).unwrap(), Value::U32(7));
+    }
+
+    #[test]
+    fn dispids_are_cached_per_kind() {
+        let mut cache = DispidCache::default();
+        let mut lookups = 0;
+
+        for _ in 0..2 {
+            let id = cache
+                .get_or_lookup(Kind::Talker, "Volume", || {
+                    lookups += 1;
+                    Ok(0x6002_0000)
+                })
+                .unwrap();
+            assert_eq!(id, 0x6002_0000);
+        }
+        assert_eq!(lookups, 1);
+
+        cache
+            .get_or_lookup(Kind::Component, "Volume", || {
+                lookups += 1;
+                Ok(1)
+            })
+            .unwrap();
+        assert_eq!(lookups, 2);
+    }
+
+    #[test]
+    fn failed_lookups_are_not_cached() {
+        let mut cache = DispidCache::default();
+        let result = cache.get_or_lookup(Kind::Talker, "ToneScale", || {
+            Err(CevioAIError::Unsupported("ToneScale".to_string()))
+        });
+        assert!(result.is_err());
+
+        let id = cache
+            .get_or_lookup(Kind::Talker, "ToneScale", || Ok(8))
+            .unwrap();
+        assert_eq!(id, 8);
+    }
+
+    /// `SetCast`で引数が不正とされた例外
+    fn invalid_argument(description: &str) -> EXCEPINFO {
+        EXCEPINFO {
+            scode: 0x8007_0057_u32 as i32,
+            bstrDescription: ManuallyDrop::new(BSTR::from(description)),
+            ..EXCEPINFO::default()
+        }
+    }
+
+    #[test]
+    fn exceptions_keep_the_host_hresult() {
+        let mut exception = invalid_argument("Cast not found");
+        let error = exception_error(&mut exception, Some(DISP_E_EXCEPTION.into())).unwrap();
+        assert_eq!(error.code(), HRESULT(0x8007_0057_u32 as i32));
+
+        let error = CevioAIError::from(error).with_context("SetCast", vec!["unknown".into()]);
+        assert!(matches!(
+            error,
+            CevioAIError::Com(ref e) if e.kind() == ComErrorKind::CastNotFound
+        ));
+
+        let mut exception = EXCEPINFO {
+            wCode: 0x1234,
+            ..EXCEPINFO::default()
+        };
+        let error = exception_error(&mut exception, Some(DISP_E_EXCEPTION.into())).unwrap();
+        assert_eq!(error.code(), HRESULT(0x8002_1234_u32 as i32));
+
+        let mut exception = EXCEPINFO::default();
+        assert!(exception_error(&mut exception, None).is_none());
+    }
+
+    #[test]
+    fn missing_classes_are_not_installed() {
+        let result = create(("CeVIO.Talk.Missing.V40", "CeVIO.Talk.Missing"));
+        let Err(CevioAIError::Com(error)) = result else {
+            panic!("expected Com error");
+        };
+        assert_eq!(error.kind(), ComErrorKind::NotInstalled);
+        assert_eq!(error.operation(), "CreateInstance");
+        assert_eq!(error.arguments(), ["CeVIO.Talk.Missing.V40"]);
+    }
+
+    #[test]
+    fn exceptions_are_filled_in_on_demand() {
+        unsafe extern "system" fn fill_in(exception: *mut EXCEPINFO) -> HRESULT {
+            unsafe { exception.write(invalid_argument("deferred")) };
+            HRESULT(0)
+        }
+
+        let mut exception = EXCEPINFO {
+            pfnDeferredFillIn: Some(fill_in),
+            ..EXCEPINFO::default()
+        };
+        let error = exception_error(&mut exception, Some(DISP_E_EXCEPTION.into())).unwrap();
+        assert_eq!(error.code(), HRESULT(0x8007_0057_u32 as i32));
+    }
+}
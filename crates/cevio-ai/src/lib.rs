@@ -53,14 +53,52 @@
 //! }
 //! ```
 
+mod analysis;
+mod audio;
+mod backend;
+mod cache;
+mod cast_name;
 mod cevio;
 mod com_manager;
+mod envelope;
 mod error;
+#[cfg(feature = "fake")]
+mod fake;
+mod fit;
+mod late_bound;
+mod lipsync;
 mod parameter;
-
+mod phoneme;
+mod speed_search;
+mod subtitles;
+#[cfg(test)]
+mod test_util;
+mod textgrid;
+mod timeline;
+mod viseme;
+mod vmd;
+mod worker;
+
+pub use analysis::*;
+pub use audio::*;
+pub use backend::*;
+pub use cache::*;
+pub use cast_name::*;
 pub use cevio::*;
+pub use com_manager::*;
+pub use envelope::*;
 pub use error::*;
+#[cfg(feature = "fake")]
+pub use fake::*;
+pub use fit::*;
+pub use lipsync::*;
 pub use parameter::*;
+pub use phoneme::*;
+pub use subtitles::*;
+pub use textgrid::*;
+pub use timeline::*;
+pub use viseme::*;
+pub use vmd::*;
 
 #[cfg(test)]
 mod tests {
@@ -181,4 +219,13 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn handles_are_send_and_sync() {
+        fn assert_send_sync<T: Send + Sync>() {}
+
+        assert_send_sync::<CevioAI>();
+        assert_send_sync::<Component>();
+        assert_send_sync::<SpeakingState>();
+    }
 }
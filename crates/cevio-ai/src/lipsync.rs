@@ -0,0 +1,522 @@
+//! リップシンクツール向けのエクスポート
+//!
+//! このモジュールは、`phonemes()`で取得した音素データを、
+//! アニメーションツールが読み込める口パクデータに変換する関数を提供します。
+//!
+//! | 形式 | 関数 |
+//! |---|---|
+//! | Rhubarb Lip Sync（TSV・JSON） | `to_rhubarb_tsv` / `to_rhubarb_json` |
+//! | Papagayo（`.pgo`） | `Papagayo::to_pgo` |
+//! | Moho（スイッチレイヤーの`.dat`） | `to_moho_switch` |
+//! | Live2D Cubism（`motion3.json`） | `Live2DMotion::to_json` |
+//!
+//! 出力はすべて文字列です。ファイルへの保存には`std::fs::write`を使用してください。
+//!
+//! ```rust,no_run
+//! use cevio_ai::*;
+//!
+//! fn main() -> Result<()> {
+//!     let cevio = CevioAI::new()?;
+//!     let phonemes = cevio.phonemes("こんにちは")?;
+//!
+//!     std::fs::write("hello.tsv", to_rhubarb_tsv(&phonemes))?;
+//!     std::fs::write("hello.motion3.json", Live2DMotion::default().to_json(&phonemes)?)?;
+//!     Ok(())
+//! }
+//! ```
+
+use std::fmt::Write;
+
+use crate::{
+    cevio::PhonemeData,
+    error::{CevioAIError, Result},
+    phoneme::MoraSegmenter,
+    viseme::{
+        viseme_track, Coarticulation, JapaneseVisemes, PrestonBlair, Viseme, VisemeKey, VisemeSet,
+    },
+};
+
+/// Rhubarb Lip Syncの口の形
+///
+/// | 口の形 | 内容 | 対応する音素 |
+/// |---|---|---|
+/// | `A` | 唇を閉じる | `N` `cl` `m` `b` `p` など |
+/// | `B` | わずかに開く | `i` と多くの子音 |
+/// | `C` | 開く | `e` |
+/// | `D` | 大きく開く | `a` |
+/// | `E` | やや丸める | `o` |
+/// | `F` | すぼめる | `u` `w` |
+/// | `G` | 上の歯を下唇に当てる | `f` `v` |
+/// | `H` | 舌を上げる | `r` |
+/// | `X` | 休止 | `pau` `sil` |
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RhubarbShape {
+    A,
+    B,
+    C,
+    D,
+    E,
+    F,
+    G,
+    H,
+    X,
+}
+
+impl RhubarbShape {
+    /// Rhubarb Lip Syncで使われる名前を取得します。
+    #[must_use]
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::A => "A",
+            Self::B => "B",
+            Self::C => "C",
+            Self::D => "D",
+            Self::E => "E",
+            Self::F => "F",
+            Self::G => "G",
+            Self::H => "H",
+            Self::X => "X",
+        }
+    }
+}
+
+/// Rhubarb Lip Syncの口の形への対応
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Rhubarb;
+
+impl VisemeSet for Rhubarb {
+    type Viseme = RhubarbShape;
+
+    fn viseme(&self, phoneme: &str) -> RhubarbShape {
+        match phoneme {
+            "a" | "A" => RhubarbShape::D,
+            "i" | "I" => RhubarbShape::B,
+            "u" | "U" | "w" => RhubarbShape::F,
+            "e" | "E" => RhubarbShape::C,
+            "o" | "O" => RhubarbShape::E,
+            "N" | "cl" | "m" | "my" | "b" | "by" | "p" | "py" => RhubarbShape::A,
+            "f" | "v" => RhubarbShape::G,
+            "r" | "ry" => RhubarbShape::H,
+            "pau" | "sil" => RhubarbShape::X,
+            _ => RhubarbShape::B,
+        }
+    }
+}
+
+fn rhubarb_track(phonemes: &[PhonemeData]) -> Vec<VisemeKey<RhubarbShape>> {
+    viseme_track(phonemes, &Rhubarb, &Coarticulation::default())
+}
+
+/// Rhubarb Lip SyncのTSV形式（開始時間と口の形）に変換します。
+///
+/// 最後の行は音声の終了時間と休止（`X`）です。
+#[must_use]
+pub fn to_rhubarb_tsv(phonemes: &[PhonemeData]) -> String {
+    let track = rhubarb_track(phonemes);
+    let mut tsv = String::new();
+    for key in &track {
+        let _ = writeln!(tsv, "{:.2}\t{}", key.start_time, key.viseme.name());
+    }
+    if let Some(last) = track.last() {
+        if last.viseme != RhubarbShape::X {
+            let _ = writeln!(tsv, "{:.2}\tX", last.end_time);
+        }
+    }
+    tsv
+}
+
+/// Rhubarb Lip SyncのJSON形式に変換します。
+///
+/// # Arguments
+///
+/// * `phonemes` - 音素データ
+/// * `sound_file` - `metadata.soundFile`に記録する音声ファイルのパス
+#[must_use]
+pub fn to_rhubarb_json(phonemes: &[PhonemeData], sound_file: &str) -> String {
+    let track = rhubarb_track(phonemes);
+    let duration = phonemes.last().map_or(0.0, PhonemeData::end_time);
+
+    let mut json = String::new();
+    json.push_str("{\n  \"metadata\": {\n");
+    let _ = writeln!(json, "    \"soundFile\": {},", json_string(sound_file));
+    let _ = writeln!(json, "    \"duration\": {duration:.2}");
+    json.push_str("  },\n  \"mouthCues\": [\n");
+    for (index, key) in track.iter().enumerate() {
+        let separator = if index + 1 < track.len() { "," } else { "" };
+        let _ = writeln!(
+            json,
+            "    {{ \"start\": {:.2}, \"end\": {:.2}, \"value\": \"{}\" }}{separator}",
+            key.start_time,
+            key.end_time,
+            key.viseme.name()
+        );
+    }
+    json.push_str("  ]\n}\n");
+    json
+}
+
+/// Papagayoのエクスポート設定
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Papagayo {
+    /// 音声ファイルのパス
+    pub sound_file: String,
+
+    /// フレームレート
+    pub fps: u32,
+
+    /// 声（キャラクター）の名前
+    pub voice: String,
+}
+
+impl Default for Papagayo {
+    fn default() -> Self {
+        Self {
+            sound_file: String::new(),
+            fps: 24,
+            voice: "Voice 1".to_string(),
+        }
+    }
+}
+
+impl Papagayo {
+    /// Papagayoのプロジェクト（`.pgo`）形式に変換します。
+    ///
+    /// ポーズで区切られた区間をフレーズ、モーラを単語とし、
+    /// 各音素をPreston Blairの口の形で記録します。
+    /// テキストにはモーラの読み（カタカナ）を使用します。
+    #[must_use]
+    pub fn to_pgo(&self, phonemes: &[PhonemeData]) -> String {
+        let frame = |time: f64| to_frame(time, f64::from(self.fps));
+
+        let mut phrases: Vec<Vec<_>> = vec![Vec::new()];
+        for mora in MoraSegmenter::new().include_silence(true).segment(phonemes) {
+            if mora.is_silent() {
+                phrases.push(Vec::new());
+            } else if let Some(phrase) = phrases.last_mut() {
+                phrase.push(mora);
+            }
+        }
+        phrases.retain(|phrase| !phrase.is_empty());
+
+        let texts: Vec<String> = phrases
+            .iter()
+            .map(|phrase| phrase.iter().map(|mora| mora.kana()).collect())
+            .collect();
+        let end = phonemes.last().map_or(0.0, PhonemeData::end_time);
+
+        let mut pgo = String::new();
+        pgo.push_str("lipsync version 1\n");
+        let _ = writeln!(pgo, "{}", self.sound_file);
+        let _ = writeln!(pgo, "{}", self.fps);
+        let _ = writeln!(pgo, "{}", frame(end));
+        pgo.push_str("1\n");
+        let _ = writeln!(pgo, "\t{}", self.voice);
+        let _ = writeln!(pgo, "\t{}", texts.join("|"));
+        let _ = writeln!(pgo, "\t{}", phrases.len());
+        for (phrase, text) in phrases.iter().zip(&texts) {
+            let start = phrase.first().map_or(0.0, |mora| mora.start_time);
+            let end = phrase.last().map_or(0.0, |mora| mora.end_time);
+            let _ = writeln!(pgo, "\t\t{text}");
+            let _ = writeln!(pgo, "\t\t{}", frame(start));
+            let _ = writeln!(pgo, "\t\t{}", frame(end));
+            let _ = writeln!(pgo, "\t\t{}", phrase.len());
+            for mora in phrase {
+                let _ = writeln!(
+                    pgo,
+                    "\t\t\t{} {} {} {}",
+                    mora.kana(),
+                    frame(mora.start_time),
+                    frame(mora.end_time),
+                    mora.phonemes.len()
+                );
+                for phoneme in &mora.phonemes {
+                    let _ = writeln!(
+                        pgo,
+                        "\t\t\t\t{} {}",
+                        frame(phoneme.start_time()),
+                        PrestonBlair.viseme(phoneme.phoneme()).name()
+                    );
+                }
+            }
+        }
+        pgo
+    }
+}
+
+/// Mohoのスイッチレイヤー用データ（`.dat`）形式に変換します。
+///
+/// 口の形はPreston Blairの名前で、フレーム番号は1から始まります。
+///
+/// # Arguments
+///
+/// * `phonemes` - 音素データ
+/// * `fps` - フレームレート
+#[must_use]
+pub fn to_moho_switch(phonemes: &[PhonemeData], fps: f64) -> String {
+    let track = viseme_track(phonemes, &PrestonBlair, &Coarticulation::default());
+
+    // 同じフレームに複数のキーがある場合は後のキーを使用する
+    let mut keys: Vec<(u32, &str)> = Vec::with_capacity(track.len());
+    for key in &track {
+        let frame = to_frame(key.start_time, fps) + 1;
+        match keys.last_mut() {
+            Some(last) if last.0 == frame => last.1 = key.viseme.name(),
+            _ => keys.push((frame, key.viseme.name())),
+        }
+    }
+
+    let mut dat = String::from("MohoSwitch1\n");
+    for (frame, name) in keys {
+        let _ = writeln!(dat, "{frame} {name}");
+    }
+    dat
+}
+
+/// Live2D Cubismのモーションのエクスポート設定
+///
+/// 口の開き（`ParamMouthOpenY`）と口の形（`ParamMouthForm`）の2つのカーブを、
+/// 指定したフレームレートで標本化した直線セグメントとして出力します。
+#[derive(Debug, Clone, PartialEq)]
+pub struct Live2DMotion {
+    /// フレームレート
+    pub fps: f64,
+
+    /// 口の開きのパラメータID
+    pub open_parameter: String,
+
+    /// 口の形のパラメータID
+    pub form_parameter: String,
+}
+
+impl Default for Live2DMotion {
+    fn default() -> Self {
+        Self {
+            fps: 30.0,
+            open_parameter: "ParamMouthOpenY".to_string(),
+            form_parameter: "ParamMouthForm".to_string(),
+        }
+    }
+}
+
+impl Live2DMotion {
+    /// 口の形に対応するパラメータ値（口の開き 0.0～1.0、口の形 -1.0～1.0）を取得します。
+    #[must_use]
+    pub const fn parameters(viseme: Viseme) -> (f64, f64) {
+        match viseme {
+            Viseme::A => (1.0, 0.0),
+            Viseme::I => (0.4, 1.0),
+            Viseme::U => (0.3, -1.0),
+            Viseme::E => (0.6, 0.6),
+            Viseme::O => (0.8, -0.5),
+            Viseme::Closed | Viseme::Rest => (0.0, 0.0),
+        }
+    }
+
+    /// `motion3.json`形式に変換します。
+    ///
+    /// # Errors
+    ///
+    /// `fps`が正の数でない場合は`InvalidParameter`エラーを返します。
+    pub fn to_json(&self, phonemes: &[PhonemeData]) -> Result<String> {
+        if !(self.fps > 0.0 && self.fps.is_finite()) {
+            return Err(CevioAIError::InvalidParameter(format!(
+                "Frame rate must be positive, got {}",
+                self.fps
+            )));
+        }
+
+        let track = viseme_track(phonemes, &JapaneseVisemes, &Coarticulation::default());
+        let duration = phonemes.last().map_or(0.0, PhonemeData::end_time);
+        let frames = to_frame(duration, self.fps);
+
+        let samples: Vec<(f64, (f64, f64))> = (0..=frames)
+            .map(|frame| {
+                let time = (f64::from(frame) / self.fps).min(duration);
+                let viseme = track
+                    .iter()
+                    .find(|key| time < key.end_time)
+                    .or(track.last())
+                    .map_or(Viseme::Rest, |key| key.viseme);
+                (time, Self::parameters(viseme))
+            })
+            .collect();
+
+        let open = simplify(samples.iter().map(|&(time, (open, _))| (time, open)));
+        let form = simplify(samples.iter().map(|&(time, (_, form))| (time, form)));
+        let segments = open.len().saturating_sub(1) + form.len().saturating_sub(1);
+        let points = open.len() + form.len();
+
+        let mut json = String::new();
+        json.push_str("{\n  \"Version\": 3,\n  \"Meta\": {\n");
+        let _ = writeln!(json, "    \"Duration\": {},", number(duration));
+        let _ = writeln!(json, "    \"Fps\": {},", number(self.fps));
+        json.push_str("    \"Loop\": false,\n");
+        json.push_str("    \"AreBeziersRestricted\": true,\n");
+        json.push_str("    \"CurveCount\": 2,\n");
+        let _ = writeln!(json, "    \"TotalSegmentCount\": {segments},");
+        let _ = writeln!(json, "    \"TotalPointCount\": {points},");
+        json.push_str("    \"UserDataCount\": 0,\n");
+        json.push_str("    \"TotalUserDataSize\": 0\n");
+        json.push_str("  },\n  \"Curves\": [\n");
+        write_curve(&mut json, &self.open_parameter, &open);
+        json.push_str(",\n");
+        write_curve(&mut json, &self.form_parameter, &form);
+        json.push_str("\n  ]\n}\n");
+        Ok(json)
+    }
+}
+
+/// 前後と同じ値の点を取り除きます。
+fn simplify(points: impl Iterator<Item = (f64, f64)>) -> Vec<(f64, f64)> {
+    let points: Vec<_> = points.collect();
+    points
+        .iter()
+        .enumerate()
+        .filter(|&(index, &(_, value))| {
+            let previous = index.checked_sub(1).map(|i| points[i].1);
+            let next = points.get(index + 1).map(|point| point.1);
+            previous != Some(value) || next != Some(value)
+        })
+        .map(|(_, &point)| point)
+        .collect()
+}
+
+fn write_curve(json: &mut String, id: &str, points: &[(f64, f64)]) {
+    json.push_str("    {\n      \"Target\": \"Parameter\",\n");
+    let _ = writeln!(json, "      \"Id\": {},", json_string(id));
+    json.push_str("      \"Segments\": [");
+    for (index, &(time, value)) in points.iter().enumerate() {
+        if index > 0 {
+            // 0: 直線セグメント
+            json.push_str(", 0, ");
+        }
+        let _ = write!(json, "{}, {}", number(time), number(value));
+    }
+    json.push_str("]\n    }");
+}
+
+/// 時間（秒）をフレーム番号に変換します。
+fn to_frame(time: f64, fps: f64) -> u32 {
+    (time * fps).round().max(0.0) as u32
+}
+
+/// 小数点以下3桁までの数値を、末尾の0を除いて書式化します。
+pub(crate) fn number(value: f64) -> String {
+    let formatted = format!("{value:.3}");
+    let trimmed = formatted.trim_end_matches('0').trim_end_matches('.');
+    match trimmed {
+        "-0" => "0".to_string(),
+        _ => trimmed.to_string(),
+    }
+}
+
+/// 文字列をJSONの文字列リテラルに変換します。
+fn json_string(value: &str) -> String {
+    let mut json = String::with_capacity(value.len() + 2);
+    json.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            '\t' => json.push_str("\\t"),
+            c if c.is_control() => {
+                let _ = write!(json, "\\u{:04x}", c as u32);
+            }
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+    json
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 「こんにちは、ふゆ」
+    fn phonemes() -> Vec<PhonemeData> {
+        PhonemeData::sequence(&[
+            ("sil", 0.1),
+            ("k", 0.05),
+            ("o", 0.1),
+            ("N", 0.08),
+            ("n", 0.04),
+            ("i", 0.08),
+            ("ch", 0.06),
+            ("i", 0.07),
+            ("w", 0.05),
+            ("a", 0.12),
+            ("pau", 0.2),
+            ("f", 0.06),
+            ("u", 0.1),
+            ("y", 0.04),
+            ("u", 0.15),
+            ("sil", 0.1),
+        ])
+    }
+
+    #[test]
+    fn rhubarb_tsv() {
+        assert_eq!(
+            to_rhubarb_tsv(&phonemes()),
+            include_str!("../tests/golden/rhubarb.tsv")
+        );
+    }
+
+    #[test]
+    fn rhubarb_json() {
+        assert_eq!(
+            to_rhubarb_json(&phonemes(), "hello.wav"),
+            include_str!("../tests/golden/rhubarb.json")
+        );
+    }
+
+    #[test]
+    fn papagayo() {
+        let papagayo = Papagayo {
+            sound_file: "hello.wav".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(
+            papagayo.to_pgo(&phonemes()),
+            include_str!("../tests/golden/papagayo.pgo")
+        );
+    }
+
+    #[test]
+    fn moho_switch() {
+        assert_eq!(
+            to_moho_switch(&phonemes(), 24.0),
+            include_str!("../tests/golden/moho.dat")
+        );
+    }
+
+    #[test]
+    fn live2d_motion() {
+        assert_eq!(
+            Live2DMotion::default().to_json(&phonemes()).unwrap(),
+            include_str!("../tests/golden/live2d.motion3.json")
+        );
+
+        for fps in [0.0, -30.0, f64::NAN, f64::INFINITY] {
+            let motion = Live2DMotion {
+                fps,
+                ..Live2DMotion::default()
+            };
+            assert!(matches!(
+                motion.to_json(&phonemes()),
+                Err(CevioAIError::InvalidParameter(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn formats_numbers_and_strings() {
+        assert_eq!(number(1.0), "1");
+        assert_eq!(number(0.1234), "0.123");
+        assert_eq!(number(-0.0001), "0");
+        assert_eq!(json_string("a\"b\\c\n"), r#""a\"b\\c\n""#);
+    }
+}
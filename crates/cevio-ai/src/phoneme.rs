@@ -0,0 +1,625 @@
+//! 音素とモーラ関連の型定義
+//!
+//! このモジュールは、`PhonemeData`の音素（文字列）を型付きの`Phoneme`として扱うための型と、
+//! 音素をモーラ（拍）にまとめる`MoraSegmenter`を提供します。
+//!
+//! ```rust
+//! use cevio_ai::{MoraSegmenter, PhonemeData};
+//! # fn example(phonemes: &[PhonemeData]) {
+//! // 「こんにちは」→ コ・ン・ニ・チ・ワ
+//! for mora in MoraSegmenter::new().segment(phonemes) {
+//!     println!("{:.3}-{:.3}: {}", mora.start_time, mora.end_time, mora.kana());
+//! }
+//! # }
+//! ```
+
+use std::{convert::Infallible, fmt, str::FromStr};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::cevio::PhonemeData;
+
+/// 母音
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Vowel {
+    A,
+    I,
+    U,
+    E,
+    O,
+}
+
+impl Vowel {
+    const fn index(self) -> usize {
+        match self {
+            Self::A => 0,
+            Self::I => 1,
+            Self::U => 2,
+            Self::E => 3,
+            Self::O => 4,
+        }
+    }
+}
+
+/// 子音
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Consonant {
+    K,
+    Ky,
+    Kw,
+    G,
+    Gy,
+    Gw,
+    S,
+    Sh,
+    Z,
+    J,
+    T,
+    Ty,
+    Ch,
+    Ts,
+    D,
+    Dy,
+    N,
+    Ny,
+    H,
+    Hy,
+    F,
+    B,
+    By,
+    P,
+    Py,
+    M,
+    My,
+    Y,
+    R,
+    Ry,
+    W,
+    V,
+}
+
+impl Consonant {
+    /// すべての子音
+    pub const ALL: [Self; 32] = [
+        Self::K,
+        Self::Ky,
+        Self::Kw,
+        Self::G,
+        Self::Gy,
+        Self::Gw,
+        Self::S,
+        Self::Sh,
+        Self::Z,
+        Self::J,
+        Self::T,
+        Self::Ty,
+        Self::Ch,
+        Self::Ts,
+        Self::D,
+        Self::Dy,
+        Self::N,
+        Self::Ny,
+        Self::H,
+        Self::Hy,
+        Self::F,
+        Self::B,
+        Self::By,
+        Self::P,
+        Self::Py,
+        Self::M,
+        Self::My,
+        Self::Y,
+        Self::R,
+        Self::Ry,
+        Self::W,
+        Self::V,
+    ];
+
+    /// CeVIO AIの表記を取得します。
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::K => "k",
+            Self::Ky => "ky",
+            Self::Kw => "kw",
+            Self::G => "g",
+            Self::Gy => "gy",
+            Self::Gw => "gw",
+            Self::S => "s",
+            Self::Sh => "sh",
+            Self::Z => "z",
+            Self::J => "j",
+            Self::T => "t",
+            Self::Ty => "ty",
+            Self::Ch => "ch",
+            Self::Ts => "ts",
+            Self::D => "d",
+            Self::Dy => "dy",
+            Self::N => "n",
+            Self::Ny => "ny",
+            Self::H => "h",
+            Self::Hy => "hy",
+            Self::F => "f",
+            Self::B => "b",
+            Self::By => "by",
+            Self::P => "p",
+            Self::Py => "py",
+            Self::M => "m",
+            Self::My => "my",
+            Self::Y => "y",
+            Self::R => "r",
+            Self::Ry => "ry",
+            Self::W => "w",
+            Self::V => "v",
+        }
+    }
+
+    /// 母音と組み合わせたカタカナの行を取得します（ア段・イ段・ウ段・エ段・オ段の順）。
+    const fn kana_row(self) -> [&'static str; 5] {
+        match self {
+            Self::K => ["カ", "キ", "ク", "ケ", "コ"],
+            Self::Ky => ["キャ", "キ", "キュ", "キェ", "キョ"],
+            Self::Kw => ["クヮ", "クィ", "ク", "クェ", "クォ"],
+            Self::G => ["ガ", "ギ", "グ", "ゲ", "ゴ"],
+            Self::Gy => ["ギャ", "ギ", "ギュ", "ギェ", "ギョ"],
+            Self::Gw => ["グヮ", "グィ", "グ", "グェ", "グォ"],
+            Self::S => ["サ", "スィ", "ス", "セ", "ソ"],
+            Self::Sh => ["シャ", "シ", "シュ", "シェ", "ショ"],
+            Self::Z => ["ザ", "ズィ", "ズ", "ゼ", "ゾ"],
+            Self::J => ["ジャ", "ジ", "ジュ", "ジェ", "ジョ"],
+            Self::T => ["タ", "ティ", "トゥ", "テ", "ト"],
+            Self::Ty => ["テャ", "ティ", "テュ", "テェ", "テョ"],
+            Self::Ch => ["チャ", "チ", "チュ", "チェ", "チョ"],
+            Self::Ts => ["ツァ", "ツィ", "ツ", "ツェ", "ツォ"],
+            Self::D => ["ダ", "ディ", "ドゥ", "デ", "ド"],
+            Self::Dy => ["デャ", "ディ", "デュ", "デェ", "デョ"],
+            Self::N => ["ナ", "ニ", "ヌ", "ネ", "ノ"],
+            Self::Ny => ["ニャ", "ニ", "ニュ", "ニェ", "ニョ"],
+            Self::H => ["ハ", "ヒ", "フ", "ヘ", "ホ"],
+            Self::Hy => ["ヒャ", "ヒ", "ヒュ", "ヒェ", "ヒョ"],
+            Self::F => ["ファ", "フィ", "フ", "フェ", "フォ"],
+            Self::B => ["バ", "ビ", "ブ", "ベ", "ボ"],
+            Self::By => ["ビャ", "ビ", "ビュ", "ビェ", "ビョ"],
+            Self::P => ["パ", "ピ", "プ", "ペ", "ポ"],
+            Self::Py => ["ピャ", "ピ", "ピュ", "ピェ", "ピョ"],
+            Self::M => ["マ", "ミ", "ム", "メ", "モ"],
+            Self::My => ["ミャ", "ミ", "ミュ", "ミェ", "ミョ"],
+            Self::Y => ["ヤ", "イ", "ユ", "イェ", "ヨ"],
+            Self::R => ["ラ", "リ", "ル", "レ", "ロ"],
+            Self::Ry => ["リャ", "リ", "リュ", "リェ", "リョ"],
+            Self::W => ["ワ", "ウィ", "ウ", "ウェ", "ウォ"],
+            Self::V => ["ヴァ", "ヴィ", "ヴ", "ヴェ", "ヴォ"],
+        }
+    }
+}
+
+/// 音素
+///
+/// CeVIO AIの音素表記と相互に変換できます。
+///
+/// | 表記 | 値 |
+/// |---|---|
+/// | `a` `i` `u` `e` `o` | `Vowel(..)` |
+/// | `A` `I` `U` `E` `O` | `DevoicedVowel(..)` |
+/// | `k` `sh` `ch` など | `Consonant(..)` |
+/// | `N` | `Nasal` |
+/// | `cl` | `Geminate` |
+/// | `pau` | `Pause` |
+/// | `sil` | `Silence` |
+/// | その他 | `Unknown(..)` |
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Phoneme {
+    /// 母音
+    Vowel(Vowel),
+    /// 無声化した母音
+    DevoicedVowel(Vowel),
+    /// 子音
+    Consonant(Consonant),
+    /// 撥音（ん）
+    Nasal,
+    /// 促音（っ）
+    Geminate,
+    /// ポーズ（句読点などによる息継ぎ）
+    Pause,
+    /// 無音（文頭・文末）
+    Silence,
+    /// 未知の音素
+    Unknown(String),
+}
+
+/// 音素の分類
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PhonemeClass {
+    Vowel,
+    DevoicedVowel,
+    Consonant,
+    Nasal,
+    Geminate,
+    Pause,
+    Silence,
+    Unknown,
+}
+
+impl Phoneme {
+    /// CeVIO AIの音素表記を解析します。
+    ///
+    /// 未知の表記は`Phoneme::Unknown`になります。
+    #[must_use]
+    pub fn parse(phoneme: &str) -> Self {
+        match phoneme {
+            "a" => Self::Vowel(Vowel::A),
+            "i" => Self::Vowel(Vowel::I),
+            "u" => Self::Vowel(Vowel::U),
+            "e" => Self::Vowel(Vowel::E),
+            "o" => Self::Vowel(Vowel::O),
+            "A" => Self::DevoicedVowel(Vowel::A),
+            "I" => Self::DevoicedVowel(Vowel::I),
+            "U" => Self::DevoicedVowel(Vowel::U),
+            "E" => Self::DevoicedVowel(Vowel::E),
+            "O" => Self::DevoicedVowel(Vowel::O),
+            "N" => Self::Nasal,
+            "cl" => Self::Geminate,
+            "pau" => Self::Pause,
+            "sil" => Self::Silence,
+            other => Consonant::ALL
+                .into_iter()
+                .find(|consonant| consonant.as_str() == other)
+                .map_or_else(|| Self::Unknown(other.to_string()), Self::Consonant),
+        }
+    }
+
+    /// CeVIO AIの音素表記を取得します。
+    #[must_use]
+    pub fn as_str(&self) -> &str {
+        match self {
+            Self::Vowel(vowel) => ["a", "i", "u", "e", "o"][vowel.index()],
+            Self::DevoicedVowel(vowel) => ["A", "I", "U", "E", "O"][vowel.index()],
+            Self::Consonant(consonant) => consonant.as_str(),
+            Self::Nasal => "N",
+            Self::Geminate => "cl",
+            Self::Pause => "pau",
+            Self::Silence => "sil",
+            Self::Unknown(phoneme) => phoneme,
+        }
+    }
+
+    /// 分類を取得します。
+    #[must_use]
+    pub const fn class(&self) -> PhonemeClass {
+        match self {
+            Self::Vowel(_) => PhonemeClass::Vowel,
+            Self::DevoicedVowel(_) => PhonemeClass::DevoicedVowel,
+            Self::Consonant(_) => PhonemeClass::Consonant,
+            Self::Nasal => PhonemeClass::Nasal,
+            Self::Geminate => PhonemeClass::Geminate,
+            Self::Pause => PhonemeClass::Pause,
+            Self::Silence => PhonemeClass::Silence,
+            Self::Unknown(_) => PhonemeClass::Unknown,
+        }
+    }
+
+    /// 母音（無声化した母音を含む）を取得します。
+    #[must_use]
+    pub const fn vowel(&self) -> Option<Vowel> {
+        match self {
+            Self::Vowel(vowel) | Self::DevoicedVowel(vowel) => Some(*vowel),
+            _ => None,
+        }
+    }
+
+    /// 母音（無声化した母音を含む）かどうかを取得します。
+    #[must_use]
+    pub const fn is_vowel(&self) -> bool {
+        self.vowel().is_some()
+    }
+
+    /// 無声化した母音かどうかを取得します。
+    #[must_use]
+    pub const fn is_devoiced(&self) -> bool {
+        matches!(self, Self::DevoicedVowel(_))
+    }
+
+    /// 子音かどうかを取得します。
+    #[must_use]
+    pub const fn is_consonant(&self) -> bool {
+        matches!(self, Self::Consonant(_))
+    }
+
+    /// ポーズまたは無音かどうかを取得します。
+    #[must_use]
+    pub const fn is_silent(&self) -> bool {
+        matches!(self, Self::Pause | Self::Silence)
+    }
+
+    /// 単独でモーラになる音素（母音・撥音・促音）かどうかを取得します。
+    #[must_use]
+    pub const fn is_mora_nucleus(&self) -> bool {
+        matches!(
+            self,
+            Self::Vowel(_) | Self::DevoicedVowel(_) | Self::Nasal | Self::Geminate
+        )
+    }
+}
+
+impl FromStr for Phoneme {
+    type Err = Infallible;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(Self::parse(s))
+    }
+}
+
+impl fmt::Display for Phoneme {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl Serialize for Phoneme {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> Deserialize<'de> for Phoneme {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Ok(Self::parse(&String::deserialize(deserializer)?))
+    }
+}
+
+impl PhonemeData {
+    /// 音素を型付きの`Phoneme`として取得します。
+    #[must_use]
+    pub fn to_phoneme(&self) -> Phoneme {
+        Phoneme::parse(self.phoneme())
+    }
+}
+
+/// モーラ（拍）
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Mora {
+    /// モーラを構成する音素
+    pub phonemes: Vec<PhonemeData>,
+
+    /// 開始時間（秒）
+    pub start_time: f64,
+
+    /// 終了時間（秒）
+    pub end_time: f64,
+}
+
+impl Mora {
+    /// 長さ（秒）を取得します。
+    #[must_use]
+    pub fn duration(&self) -> f64 {
+        self.end_time - self.start_time
+    }
+
+    /// 子音を取得します。
+    #[must_use]
+    pub fn consonant(&self) -> Option<Consonant> {
+        self.phonemes
+            .iter()
+            .find_map(|phoneme| match phoneme.to_phoneme() {
+                Phoneme::Consonant(consonant) => Some(consonant),
+                _ => None,
+            })
+    }
+
+    /// 母音・撥音・促音・ポーズなど、モーラの中心となる音素を取得します。
+    #[must_use]
+    pub fn nucleus(&self) -> Option<Phoneme> {
+        self.phonemes
+            .iter()
+            .map(PhonemeData::to_phoneme)
+            .find(|phoneme| !phoneme.is_consonant())
+    }
+
+    /// ポーズまたは無音のモーラかどうかを取得します。
+    #[must_use]
+    pub fn is_silent(&self) -> bool {
+        self.nucleus().as_ref().is_some_and(Phoneme::is_silent)
+    }
+
+    /// モーラの読みをカタカナで取得します。
+    ///
+    /// ポーズ・無音・未知の音素は空文字列になります。
+    #[must_use]
+    pub fn kana(&self) -> String {
+        mora_kana(self.consonant(), self.nucleus().as_ref()).to_string()
+    }
+
+    /// モーラの読みをひらがなで取得します。
+    #[must_use]
+    pub fn hiragana(&self) -> String {
+        to_hiragana(&self.kana())
+    }
+}
+
+/// 子音と中心となる音素からカタカナを組み立てます。
+fn mora_kana(consonant: Option<Consonant>, nucleus: Option<&Phoneme>) -> &'static str {
+    match (consonant, nucleus) {
+        (_, Some(Phoneme::Nasal)) => "ン",
+        (_, Some(Phoneme::Geminate)) => "ッ",
+        (None, Some(Phoneme::Vowel(vowel) | Phoneme::DevoicedVowel(vowel))) => {
+            ["ア", "イ", "ウ", "エ", "オ"][vowel.index()]
+        }
+        (Some(consonant), Some(Phoneme::Vowel(vowel) | Phoneme::DevoicedVowel(vowel))) => {
+            consonant.kana_row()[vowel.index()]
+        }
+        // 母音を伴わない子音はウ段として読む
+        (Some(consonant), None) => consonant.kana_row()[Vowel::U.index()],
+        _ => "",
+    }
+}
+
+/// カタカナをひらがなに変換します。
+///
+/// ひらがなに対応する文字がない「ヷ」「ヺ」などはそのまま残ります。
+fn to_hiragana(katakana: &str) -> String {
+    katakana
+        .chars()
+        .map(|c| match c {
+            'ァ'..='ヶ' => char::from_u32(c as u32 - 0x60).unwrap_or(c),
+            _ => c,
+        })
+        .collect()
+}
+
+/// 音素をモーラにまとめる
+///
+/// 子音は直後の母音・撥音・促音と同じモーラに含まれます。
+/// ポーズと無音は既定では除外され、`include_silence(true)`で独立したモーラとして出力されます。
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MoraSegmenter {
+    include_silence: bool,
+}
+
+impl MoraSegmenter {
+    /// 新しい`MoraSegmenter`を作成します。
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            include_silence: false,
+        }
+    }
+
+    /// ポーズと無音をモーラとして出力するかどうかを設定します。
+    #[must_use]
+    pub const fn include_silence(mut self, include: bool) -> Self {
+        self.include_silence = include;
+        self
+    }
+
+    /// 音素データをモーラにまとめます。
+    pub fn segment(&self, phonemes: &[PhonemeData]) -> Vec<Mora> {
+        let mut moras = Vec::new();
+        let mut pending: Option<Mora> = None;
+
+        for data in phonemes {
+            let phoneme = data.to_phoneme();
+
+            // 母音を伴わない子音は単独のモーラとして確定させる
+            if !phoneme.is_mora_nucleus() {
+                moras.extend(pending.take());
+            }
+
+            if !phoneme.is_consonant() && !phoneme.is_mora_nucleus() {
+                if self.include_silence || !phoneme.is_silent() {
+                    moras.push(Mora {
+                        phonemes: vec![data.clone()],
+                        start_time: data.start_time(),
+                        end_time: data.end_time(),
+                    });
+                }
+                continue;
+            }
+
+            let nucleus = phoneme.is_mora_nucleus();
+            let mora = pending.get_or_insert_with(|| Mora {
+                phonemes: Vec::new(),
+                start_time: data.start_time(),
+                end_time: data.end_time(),
+            });
+            mora.phonemes.push(data.clone());
+            mora.end_time = data.end_time();
+            if nucleus {
+                moras.extend(pending.take());
+            }
+        }
+
+        moras.extend(pending);
+        moras
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_and_classifies() {
+        assert_eq!(Phoneme::parse("a"), Phoneme::Vowel(Vowel::A));
+        assert_eq!(Phoneme::parse("U"), Phoneme::DevoicedVowel(Vowel::U));
+        assert_eq!(Phoneme::parse("sh"), Phoneme::Consonant(Consonant::Sh));
+        assert_eq!(Phoneme::parse("N"), Phoneme::Nasal);
+        assert_eq!(Phoneme::parse("cl"), Phoneme::Geminate);
+        assert_eq!(Phoneme::parse("pau").class(), PhonemeClass::Pause);
+        assert_eq!(Phoneme::parse("sil").class(), PhonemeClass::Silence);
+        assert_eq!(Phoneme::parse("xx"), Phoneme::Unknown("xx".to_string()));
+
+        assert!(Phoneme::parse("I").is_devoiced());
+        assert!(Phoneme::parse("I").is_vowel());
+        assert!(!Phoneme::parse("n").is_vowel());
+        assert!(Phoneme::parse("n").is_consonant());
+        assert!(!Phoneme::parse("N").is_consonant());
+
+        for phoneme in ["a", "O", "ky", "ts", "N", "cl", "pau", "sil", "xx"] {
+            assert_eq!(phoneme.parse::<Phoneme>().unwrap().to_string(), phoneme);
+        }
+    }
+
+    #[test]
+    fn segments_moras_with_kana() {
+        // 「こんにちは、きって」
+        let data = PhonemeData::sequence(&[
+            ("sil", 0.1),
+            ("k", 0.05),
+            ("o", 0.1),
+            ("N", 0.1),
+            ("n", 0.05),
+            ("i", 0.1),
+            ("ch", 0.05),
+            ("i", 0.1),
+            ("w", 0.05),
+            ("a", 0.1),
+            ("pau", 0.2),
+            ("k", 0.05),
+            ("I", 0.05),
+            ("cl", 0.1),
+            ("t", 0.05),
+            ("e", 0.1),
+            ("sil", 0.1),
+        ]);
+
+        let moras = MoraSegmenter::new().segment(&data);
+
+        let kana: Vec<_> = moras.iter().map(Mora::kana).collect();
+        assert_eq!(kana, ["コ", "ン", "ニ", "チ", "ワ", "キ", "ッ", "テ"]);
+        assert!((moras[0].start_time - 0.1).abs() < 1e-9);
+        assert!((moras[0].end_time - 0.25).abs() < 1e-9);
+        assert_eq!(moras[5].nucleus(), Some(Phoneme::DevoicedVowel(Vowel::I)));
+        assert_eq!(moras[3].consonant(), Some(Consonant::Ch));
+        assert_eq!(moras[4].hiragana(), "わ");
+
+        let moras = MoraSegmenter::new().include_silence(true).segment(&data);
+        assert_eq!(moras.len(), 11);
+        assert!(moras[0].is_silent());
+        assert!(moras[6].is_silent());
+        assert_eq!(moras[6].kana(), "");
+    }
+
+    #[test]
+    fn handles_irregular_sequences() {
+        // 母音を伴わない子音と未知の音素
+        let data = PhonemeData::sequence(&[
+            ("sh", 0.1),
+            ("pau", 0.1),
+            ("xx", 0.1),
+            ("ky", 0.05),
+            ("o", 0.1),
+        ]);
+
+        let moras = MoraSegmenter::new().segment(&data);
+
+        assert_eq!(moras.len(), 3);
+        assert_eq!(moras[0].kana(), "シュ");
+        assert_eq!(moras[1].nucleus(), Some(Phoneme::Unknown("xx".to_string())));
+        assert_eq!(moras[2].kana(), "キョ");
+        assert_eq!(moras[2].hiragana(), "きょ");
+    }
+}
@@ -0,0 +1,157 @@
+//! 話す速さの探索
+//!
+//! 発話速度やセリフの長さのように、話す速さ（`Speed`）に対して単調に変化する値を測定しながら、
+//! 条件を満たす境界を二分探索で求めます。`calibrate_speed`と`fit_to_duration`が使用します。
+
+use crate::{backend::Backend, cevio::Cast, error::Result, parameter::Speed};
+
+/// 探索の結果
+///
+/// いずれも話す速さとその測定値の組を持ちます。
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum Bracket<T> {
+    /// 探索を始めた話す速さで条件を満たした
+    Start((Speed, T)),
+
+    /// 最大の話す速さでも条件を満たさなかった
+    Exhausted((Speed, T)),
+
+    /// 条件を満たさない最も速い話す速さ（`below`）と、満たす最も遅い話す速さ（`above`）
+    ///
+    /// 2つの話す速さは隣り合っています。
+    Between {
+        below: (Speed, T),
+        above: (Speed, T),
+    },
+}
+
+/// `from`から最大の話す速さまでの範囲で、条件を満たす最も遅い話す速さを探します。
+///
+/// `reached`は話す速さに対して単調である（ある速さで満たせば、それより速い場合も満たす）必要があります。
+/// `from`で条件を満たす場合は`from`だけを、最大の話す速さでも満たさない場合は最大の話す速さを返します。
+///
+/// # Arguments
+///
+/// * `from` - 探索を始める話す速さ
+/// * `measure` - 話す速さを設定して値を測定する関数
+/// * `reached` - 測定値が条件を満たすかどうかを判定する関数
+pub(crate) fn search<T>(
+    from: Speed,
+    mut measure: impl FnMut(Speed) -> Result<T>,
+    reached: impl Fn(&T) -> bool,
+) -> Result<Bracket<T>> {
+    let start = measure(from)?;
+    if reached(&start) {
+        return Ok(Bracket::Start((from, start)));
+    }
+    let fastest = Speed::new(Speed::MAX_VALUE).unwrap_or_default();
+    if from == fastest {
+        return Ok(Bracket::Exhausted((from, start)));
+    }
+    let end = measure(fastest)?;
+    if !reached(&end) {
+        return Ok(Bracket::Exhausted((fastest, end)));
+    }
+
+    // low は条件を満たさず、high は満たす
+    let (mut low, mut high) = ((from, start), (fastest, end));
+    while high.0.get() - low.0.get() > 1 {
+        let speed = Speed::new(low.0.get() + (high.0.get() - low.0.get()) / 2).unwrap_or_default();
+        let value = measure(speed)?;
+        if reached(&value) {
+            high = (speed, value);
+        } else {
+            low = (speed, value);
+        }
+    }
+
+    Ok(Bracket::Between {
+        below: low,
+        above: high,
+    })
+}
+
+/// 現在のキャストを`f`に渡して実行し、終了後に話す速さを元の値に戻します。
+///
+/// `f`が失敗した場合も話す速さを戻し、`f`のエラーを返します（戻せなかった場合のエラーは無視します）。
+pub(crate) fn preserving_speed<B: Backend + ?Sized, T>(
+    backend: &B,
+    f: impl FnOnce(&Cast) -> Result<T>,
+) -> Result<T> {
+    let original = backend.current_cast()?;
+    let result = f(&original);
+
+    if original.speed.is_some() {
+        let restored = backend.apply_cast(&Cast {
+            speed: original.speed,
+            ..Cast::default()
+        });
+        // タイムアウトの後は戻せずPoisonedになるため、元のエラーを優先する
+        if result.is_ok() {
+            restored?;
+        }
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn speed(value: u8) -> Speed {
+        Speed::new(value).unwrap()
+    }
+
+    #[test]
+    fn brackets_the_boundary() {
+        let mut calls = 0;
+        let bracket = search(
+            speed(10),
+            |speed| {
+                calls += 1;
+                Ok(speed.get())
+            },
+            |&value| value >= 42,
+        )
+        .unwrap();
+
+        assert_eq!(
+            bracket,
+            Bracket::Between {
+                below: (speed(41), 41),
+                above: (speed(42), 42)
+            }
+        );
+        // 両端の2回と、90の範囲を狭める7回
+        assert_eq!(calls, 9);
+    }
+
+    #[cfg(feature = "fake")]
+    #[test]
+    fn keeps_the_original_error() {
+        use crate::{error::CevioAIError, fake::FakeBackend};
+
+        let backend = FakeBackend::new();
+        let result: Result<()> = preserving_speed(&backend, |_| {
+            backend.poison();
+            Err(CevioAIError::Timeout {
+                operation: "GetPhonemes",
+                elapsed: std::time::Duration::from_secs(1),
+            })
+        });
+        assert!(matches!(result, Err(CevioAIError::Timeout { .. })));
+    }
+
+    #[test]
+    fn stops_at_the_ends() {
+        let bracket = search(speed(50), |speed| Ok(speed.get()), |_| true).unwrap();
+        assert_eq!(bracket, Bracket::Start((speed(50), 50)));
+
+        let bracket = search(speed(50), |speed| Ok(speed.get()), |_| false).unwrap();
+        assert_eq!(bracket, Bracket::Exhausted((speed(100), 100)));
+
+        let bracket = search(speed(100), |speed| Ok(speed.get()), |_| false).unwrap();
+        assert_eq!(bracket, Bracket::Exhausted((speed(100), 100)));
+    }
+}
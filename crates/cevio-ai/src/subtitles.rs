@@ -0,0 +1,712 @@
+//! 字幕の作成
+//!
+//! このモジュールは、元のセリフ（表示用に行分割したもの）と`phonemes()`の音素データから、
+//! SRT・WebVTT・ASS形式の字幕を作成するための型を提供します。
+//!
+//! 音素から組み立てたモーラの読み（カタカナ）を元のセリフの文字に対応付けることで、
+//! 行ごとの表示時間と、モーラごとのカラオケのタイミングを求めます。
+//! 漢字などの読みは`ReadingMap`で指定でき、指定がない部分は前後の仮名から推定されます。
+//!
+//! 複数の行をまとめて合成した（または時間をずらして連結した）音素データを渡すことで、
+//! シーン全体を1つの字幕ファイルにできます。
+//!
+//! ```rust,no_run
+//! use cevio_ai::*;
+//!
+//! fn main() -> Result<()> {
+//!     let cevio = CevioAI::new()?;
+//!     let lines = ["今日はいい天気。", "散歩に行こう！"];
+//!     let phonemes = cevio.phonemes(&lines.concat())?;
+//!
+//!     let readings: ReadingMap = [("今日", "きょう"), ("天気", "てんき")].into_iter().collect();
+//!     let subtitles = Subtitles::align(&lines, &phonemes, &readings);
+//!     std::fs::write("scene.srt", subtitles.to_srt())?;
+//!     std::fs::write("scene.ass", subtitles.to_ass())?;
+//!     Ok(())
+//! }
+//! ```
+
+use std::{collections::HashMap, fmt::Write};
+
+use crate::{cevio::PhonemeData, phoneme::MoraSegmenter};
+
+/// 表記と読みの対応
+///
+/// 読みはひらがなとカタカナのどちらでも指定できます。
+/// 表記は最長一致で検索されます。
+#[derive(Debug, Clone, Default)]
+pub struct ReadingMap {
+    readings: HashMap<String, String>,
+    max_chars: usize,
+}
+
+impl ReadingMap {
+    /// 空の`ReadingMap`を作成します。
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 表記と読みの対応を追加します。
+    pub fn insert(&mut self, surface: impl Into<String>, reading: impl Into<String>) {
+        let surface = surface.into();
+        self.max_chars = self.max_chars.max(surface.chars().count());
+        self.readings.insert(surface, reading.into());
+    }
+
+    /// 表記の読みを取得します。
+    #[must_use]
+    pub fn get(&self, surface: &str) -> Option<&str> {
+        self.readings.get(surface).map(String::as_str)
+    }
+
+    /// 文字列の先頭に最長一致する表記と、その読みを取得します。
+    fn longest_match<'a>(&self, chars: &'a [char]) -> Option<(&'a [char], &str)> {
+        (1..=self.max_chars.min(chars.len()))
+            .rev()
+            .find_map(|length| {
+                let surface: String = chars[..length].iter().collect();
+                self.get(&surface)
+                    .map(|reading| (&chars[..length], reading))
+            })
+    }
+}
+
+impl<K: Into<String>, V: Into<String>> FromIterator<(K, V)> for ReadingMap {
+    fn from_iter<T: IntoIterator<Item = (K, V)>>(iter: T) -> Self {
+        let mut map = Self::new();
+        for (surface, reading) in iter {
+            map.insert(surface, reading);
+        }
+        map
+    }
+}
+
+/// カラオケの区間
+#[derive(Debug, Clone, PartialEq)]
+pub struct KaraokeSegment {
+    /// 表示する文字列
+    pub text: String,
+
+    /// 開始時間（秒）
+    pub start_time: f64,
+
+    /// 終了時間（秒）
+    pub end_time: f64,
+}
+
+/// 字幕の1行
+#[derive(Debug, Clone, PartialEq)]
+pub struct SubtitleCue {
+    /// 表示する文字列
+    pub text: String,
+
+    /// 開始時間（秒）
+    pub start_time: f64,
+
+    /// 終了時間（秒）
+    pub end_time: f64,
+
+    /// モーラ（読みを指定した表記や読みを推定した部分はまとめて1つ）ごとの区間
+    pub segments: Vec<KaraokeSegment>,
+}
+
+/// 字幕
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Subtitles {
+    /// 字幕の行
+    pub cues: Vec<SubtitleCue>,
+}
+
+/// 表示上の単位
+#[derive(Debug)]
+struct Unit {
+    /// 行番号
+    line: usize,
+    /// 表示する文字列
+    text: String,
+    /// 期待される読み
+    reading: Reading,
+}
+
+#[derive(Debug)]
+enum Reading {
+    /// モーラの読み（カタカナ）
+    Moras(Vec<String>),
+    /// 読みが不明（漢字など）
+    Unknown,
+    /// 読まない（句読点・空白など）
+    Silent,
+}
+
+/// 期待されるモーラ
+#[derive(Debug)]
+enum Expected {
+    Kana(String),
+    Any,
+}
+
+impl Subtitles {
+    /// セリフの各行と音素データを対応付けて字幕を作成します。
+    ///
+    /// # Arguments
+    ///
+    /// * `lines` - 表示する行（連結したものが合成したセリフ）
+    /// * `phonemes` - 音素データ
+    /// * `readings` - 漢字などの読み
+    #[must_use]
+    pub fn align<S: AsRef<str>>(
+        lines: &[S],
+        phonemes: &[PhonemeData],
+        readings: &ReadingMap,
+    ) -> Self {
+        let units = tokenize(lines, readings);
+        let moras = MoraSegmenter::new().segment(phonemes);
+        let actual: Vec<String> = moras.iter().map(|mora| mora.kana()).collect();
+
+        // 期待されるモーラの列（と対応する単位）
+        let mut expected = Vec::new();
+        for (index, unit) in units.iter().enumerate() {
+            match &unit.reading {
+                Reading::Moras(moras) => expected.extend(
+                    moras
+                        .iter()
+                        .map(|mora| (index, Expected::Kana(mora.clone()))),
+                ),
+                Reading::Unknown => expected.push((index, Expected::Any)),
+                Reading::Silent => {}
+            }
+        }
+
+        let owners = align(&expected, &actual);
+
+        // 単位ごとの時間
+        let mut spans: Vec<Option<(f64, f64)>> = vec![None; units.len()];
+        for (mora, owner) in moras.iter().zip(owners) {
+            let Some(owner) = owner.map(|item| expected[item].0) else {
+                continue;
+            };
+            let span = spans[owner].get_or_insert((mora.start_time, mora.end_time));
+            span.0 = span.0.min(mora.start_time);
+            span.1 = span.1.max(mora.end_time);
+        }
+
+        let mut cues: Vec<SubtitleCue> = Vec::new();
+        let mut previous_end = 0.0;
+        for (line, text) in lines.iter().enumerate() {
+            let mut segments = Vec::new();
+            let mut voiced = false;
+            for (unit, span) in units
+                .iter()
+                .zip(&spans)
+                .filter(|(unit, _)| unit.line == line)
+            {
+                let (start_time, end_time) = match span {
+                    Some(span) => {
+                        voiced = true;
+                        *span
+                    }
+                    None => (previous_end, previous_end),
+                };
+                previous_end = end_time;
+                segments.push(KaraokeSegment {
+                    text: unit.text.clone(),
+                    start_time,
+                    end_time,
+                });
+            }
+            if !voiced {
+                continue;
+            }
+
+            let start_time = segments
+                .iter()
+                .filter(|segment| segment.end_time > segment.start_time)
+                .map(|segment| segment.start_time)
+                .fold(f64::INFINITY, f64::min);
+            let end_time = segments
+                .iter()
+                .map(|segment| segment.end_time)
+                .fold(start_time, f64::max);
+            for segment in &mut segments {
+                segment.start_time = segment.start_time.max(start_time);
+                segment.end_time = segment.end_time.max(segment.start_time);
+            }
+
+            cues.push(SubtitleCue {
+                text: text.as_ref().to_string(),
+                start_time,
+                end_time,
+                segments,
+            });
+        }
+
+        Self { cues }
+    }
+
+    /// SRT形式に変換します。
+    #[must_use]
+    pub fn to_srt(&self) -> String {
+        let mut srt = String::new();
+        for (index, cue) in self.cues.iter().enumerate() {
+            let _ = writeln!(srt, "{}", index + 1);
+            let _ = writeln!(
+                srt,
+                "{} --> {}",
+                timestamp(cue.start_time, ','),
+                timestamp(cue.end_time, ',')
+            );
+            let _ = writeln!(srt, "{}\n", cue.text);
+        }
+        srt
+    }
+
+    /// WebVTT形式に変換します。
+    ///
+    /// カラオケのタイミングはタイムスタンプタグ（`<00:00:01.000>`）で出力されます。
+    #[must_use]
+    pub fn to_webvtt(&self) -> String {
+        let mut vtt = String::from("WEBVTT\n\n");
+        for cue in &self.cues {
+            let _ = writeln!(
+                vtt,
+                "{} --> {}",
+                timestamp(cue.start_time, '.'),
+                timestamp(cue.end_time, '.')
+            );
+            for (index, segment) in cue.segments.iter().enumerate() {
+                if index > 0 && segment.end_time > segment.start_time {
+                    let _ = write!(vtt, "<{}>", timestamp(segment.start_time, '.'));
+                }
+                vtt.push_str(&escape_webvtt(&segment.text));
+            }
+            vtt.push_str("\n\n");
+        }
+        vtt
+    }
+
+    /// ASS（Advanced SubStation Alpha）形式に変換します。
+    ///
+    /// 各区間の前に`\k`タグ（センチ秒）を付け、区間の間の無音は空の`\k`タグで表します。
+    #[must_use]
+    pub fn to_ass(&self) -> String {
+        let mut ass = String::from(
+            "[Script Info]\n\
+             ScriptType: v4.00+\n\
+             PlayResX: 1920\n\
+             PlayResY: 1080\n\
+             \n\
+             [V4+ Styles]\n\
+             Format: Name, Fontname, Fontsize, PrimaryColour, SecondaryColour, OutlineColour, BackColour, Bold, Italic, Underline, StrikeOut, ScaleX, ScaleY, Spacing, Angle, BorderStyle, Outline, Shadow, Alignment, MarginL, MarginR, MarginV, Encoding\n\
+             Style: Default,Noto Sans JP,64,&H00FFFFFF,&H0000FFFF,&H00000000,&H80000000,0,0,0,0,100,100,0,0,1,3,0,2,40,40,40,1\n\
+             \n\
+             [Events]\n\
+             Format: Layer, Start, End, Style, Name, MarginL, MarginR, MarginV, Effect, Text\n",
+        );
+        for cue in &self.cues {
+            let start = centiseconds(cue.start_time);
+            let mut text = String::new();
+            let mut position = start;
+            for segment in &cue.segments {
+                let segment_start = centiseconds(segment.start_time);
+                let segment_end = centiseconds(segment.end_time);
+                if segment_start > position {
+                    let _ = write!(text, "{{\\k{}}}", segment_start - position);
+                }
+                let _ = write!(
+                    text,
+                    "{{\\k{}}}{}",
+                    segment_end.saturating_sub(segment_start.max(position)),
+                    escape_ass(&segment.text)
+                );
+                position = position.max(segment_end);
+            }
+            let _ = writeln!(
+                ass,
+                "Dialogue: 0,{},{},Default,,0,0,0,,{text}",
+                ass_timestamp(start),
+                ass_timestamp(centiseconds(cue.end_time))
+            );
+        }
+        ass
+    }
+}
+
+/// 行を表示上の単位に分割します。
+fn tokenize<S: AsRef<str>>(lines: &[S], readings: &ReadingMap) -> Vec<Unit> {
+    let mut units = Vec::new();
+    for (line, text) in lines.iter().enumerate() {
+        let chars: Vec<char> = text.as_ref().chars().collect();
+        let mut position = 0;
+        while position < chars.len() {
+            let rest = &chars[position..];
+
+            if let Some((surface, reading)) = readings.longest_match(rest) {
+                units.push(Unit {
+                    line,
+                    text: surface.iter().collect(),
+                    reading: Reading::Moras(split_moras(reading)),
+                });
+                position += surface.len();
+                continue;
+            }
+
+            let c = rest[0];
+            if is_kana(c) {
+                // 1モーラ（拗音などの小書き文字を含む）
+                let length = 1 + rest[1..].iter().take_while(|&&c| is_small_kana(c)).count();
+                let surface: String = rest[..length].iter().collect();
+                units.push(Unit {
+                    line,
+                    reading: Reading::Moras(vec![to_katakana(&surface)]),
+                    text: surface,
+                });
+                position += length;
+            } else if is_silent(c) {
+                units.push(Unit {
+                    line,
+                    text: c.to_string(),
+                    reading: Reading::Silent,
+                });
+                position += 1;
+            } else {
+                let length = rest
+                    .iter()
+                    .take_while(|&&c| !is_kana(c) && !is_silent(c))
+                    .enumerate()
+                    .take_while(|&(index, _)| {
+                        index == 0 || readings.longest_match(&rest[index..]).is_none()
+                    })
+                    .count();
+                units.push(Unit {
+                    line,
+                    text: rest[..length].iter().collect(),
+                    reading: Reading::Unknown,
+                });
+                position += length;
+            }
+        }
+    }
+    units
+}
+
+/// 期待されるモーラの列と実際のモーラの列を対応付けます。
+///
+/// 戻り値は実際のモーラごとの、対応する期待されるモーラの番号です。
+/// 読みが不明な部分（`Expected::Any`）は1つ以上の任意のモーラに対応します。
+fn align(expected: &[(usize, Expected)], actual: &[String]) -> Vec<Option<usize>> {
+    /// 一致しないモーラの対応・挿入・削除のコスト
+    const MISMATCH: f32 = 1.0;
+    /// 読みが不明な部分に含めるモーラ1つあたりのコスト
+    const ABSORB: f32 = 0.1;
+
+    #[derive(Clone, Copy)]
+    enum Step {
+        Start,
+        /// 期待されるモーラと実際のモーラを対応付ける
+        Match,
+        /// 期待されるモーラを読み飛ばす
+        Skip,
+        /// 実際のモーラを直前の期待されるモーラに含める
+        Insert,
+    }
+
+    let rows = expected.len() + 1;
+    let columns = actual.len() + 1;
+    let mut cost = vec![f32::INFINITY; rows * columns];
+    let mut steps = vec![Step::Start; rows * columns];
+    cost[0] = 0.0;
+
+    for i in 0..rows {
+        for j in 0..columns {
+            let index = i * columns + j;
+            let mut best = (cost[index], steps[index]);
+            let mut consider = |candidate: f32, step: Step| {
+                if candidate < best.0 {
+                    best = (candidate, step);
+                }
+            };
+
+            if i > 0 && j > 0 {
+                let penalty = match &expected[i - 1].1 {
+                    Expected::Kana(kana) if same_mora(kana, &actual[j - 1]) => 0.0,
+                    Expected::Kana(_) => MISMATCH,
+                    Expected::Any => ABSORB,
+                };
+                consider(cost[index - columns - 1] + penalty, Step::Match);
+            }
+            if i > 0 {
+                consider(cost[index - columns] + MISMATCH, Step::Skip);
+            }
+            if j > 0 {
+                let penalty = match expected.get(i.wrapping_sub(1)) {
+                    Some((_, Expected::Any)) => ABSORB,
+                    _ => MISMATCH,
+                };
+                consider(cost[index - 1] + penalty, Step::Insert);
+            }
+
+            cost[index] = best.0;
+            steps[index] = best.1;
+        }
+    }
+
+    let mut owners = vec![None; actual.len()];
+    let (mut i, mut j) = (expected.len(), actual.len());
+    while i > 0 || j > 0 {
+        match steps[i * columns + j] {
+            Step::Match => {
+                owners[j - 1] = Some(i - 1);
+                i -= 1;
+                j -= 1;
+            }
+            Step::Skip => i -= 1,
+            Step::Insert => {
+                // 先頭より前の余分なモーラは最初の期待されるモーラに含める
+                owners[j - 1] = Some(i.saturating_sub(1)).filter(|_| !expected.is_empty());
+                j -= 1;
+            }
+            Step::Start => break,
+        }
+    }
+    owners
+}
+
+/// 2つのモーラの読みが同じとみなせるかどうかを判定します。
+fn same_mora(expected: &str, actual: &str) -> bool {
+    let normalize = |kana: &str| -> String {
+        kana.chars()
+            .map(|c| match c {
+                'ヲ' => 'オ',
+                'ヅ' => 'ズ',
+                'ヂ' => 'ジ',
+                'ヮ' => 'ワ',
+                c => c,
+            })
+            .collect()
+    };
+    let (expected, actual) = (normalize(expected), normalize(actual));
+
+    expected == actual
+        // 助詞の「は」「へ」
+        || (expected == "ハ" && actual == "ワ")
+        || (expected == "ヘ" && actual == "エ")
+        // 長音は直前の母音の延長として読まれる
+        || (expected == "ー" && ["ア", "イ", "ウ", "エ", "オ"].contains(&actual.as_str()))
+}
+
+/// 仮名の読みをモーラに分割します。
+fn split_moras(reading: &str) -> Vec<String> {
+    let mut moras: Vec<String> = Vec::new();
+    for c in to_katakana(reading).chars() {
+        match moras.last_mut() {
+            Some(last) if is_small_kana(c) => last.push(c),
+            _ if is_silent(c) => {}
+            _ => moras.push(c.to_string()),
+        }
+    }
+    moras
+}
+
+fn is_kana(c: char) -> bool {
+    matches!(c, 'ぁ'..='ゖ' | 'ァ'..='ヺ' | 'ー')
+}
+
+fn is_small_kana(c: char) -> bool {
+    matches!(
+        c,
+        'ぁ' | 'ぃ'
+            | 'ぅ'
+            | 'ぇ'
+            | 'ぉ'
+            | 'ゃ'
+            | 'ゅ'
+            | 'ょ'
+            | 'ゎ'
+            | 'ァ'
+            | 'ィ'
+            | 'ゥ'
+            | 'ェ'
+            | 'ォ'
+            | 'ャ'
+            | 'ュ'
+            | 'ョ'
+            | 'ヮ'
+    )
+}
+
+/// 読まない文字（空白・句読点・記号）かどうかを判定します。
+fn is_silent(c: char) -> bool {
+    c.is_whitespace()
+        || c.is_ascii_punctuation()
+        || matches!(c, '\u{3000}'..='\u{303f}' | '\u{ff01}'..='\u{ff0f}' | '\u{ff1a}'..='\u{ff20}' | '・' | '…' | '‥')
+}
+
+fn to_katakana(text: &str) -> String {
+    text.chars()
+        .map(|c| match c {
+            'ぁ'..='ゖ' => char::from_u32(c as u32 + 0x60).unwrap_or(c),
+            c => c,
+        })
+        .collect()
+}
+
+/// `HH:MM:SS,mmm`（SRT）または`HH:MM:SS.mmm`（WebVTT）形式の時刻
+fn timestamp(seconds: f64, separator: char) -> String {
+    let milliseconds = (seconds.max(0.0) * 1000.0).round() as u64;
+    format!(
+        "{:02}:{:02}:{:02}{separator}{:03}",
+        milliseconds / 3_600_000,
+        milliseconds / 60_000 % 60,
+        milliseconds / 1000 % 60,
+        milliseconds % 1000
+    )
+}
+
+fn centiseconds(seconds: f64) -> u64 {
+    (seconds.max(0.0) * 100.0).round() as u64
+}
+
+/// `H:MM:SS.cc`（ASS）形式の時刻
+fn ass_timestamp(centiseconds: u64) -> String {
+    format!(
+        "{}:{:02}:{:02}.{:02}",
+        centiseconds / 360_000,
+        centiseconds / 6000 % 60,
+        centiseconds / 100 % 60,
+        centiseconds % 100
+    )
+}
+
+fn escape_webvtt(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+fn escape_ass(text: &str) -> String {
+    text.replace('\\', "＼")
+        .replace('{', "｛")
+        .replace('}', "｝")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 「今日はいい天気。散歩に行こう！」
+    fn scene() -> Subtitles {
+        let data = PhonemeData::sequence(&[
+            ("sil", 0.1),
+            ("ky", 0.05),
+            ("o", 0.1),
+            ("u", 0.1),
+            ("w", 0.05),
+            ("a", 0.1),
+            ("i", 0.1),
+            ("i", 0.1),
+            ("t", 0.05),
+            ("e", 0.1),
+            ("N", 0.1),
+            ("k", 0.05),
+            ("i", 0.1),
+            ("pau", 0.3),
+            ("s", 0.05),
+            ("a", 0.1),
+            ("N", 0.1),
+            ("p", 0.05),
+            ("o", 0.1),
+            ("n", 0.05),
+            ("i", 0.1),
+            ("i", 0.1),
+            ("k", 0.05),
+            ("o", 0.1),
+            ("u", 0.15),
+            ("sil", 0.1),
+        ]);
+        let readings: ReadingMap = [("今日", "きょう"), ("天気", "テンキ")]
+            .into_iter()
+            .collect();
+
+        Subtitles::align(&["今日はいい天気。", "散歩に行こう！"], &data, &readings)
+    }
+
+    #[test]
+    fn aligns_lines_and_moras() {
+        let subtitles = scene();
+
+        assert_eq!(subtitles.cues.len(), 2);
+        let first = &subtitles.cues[0];
+        assert_eq!(first.text, "今日はいい天気。");
+        assert!((first.start_time - 0.1).abs() < 1e-9);
+        assert!((first.end_time - 1.1).abs() < 1e-9);
+        let texts: Vec<_> = first.segments.iter().map(|s| s.text.as_str()).collect();
+        assert_eq!(texts, ["今日", "は", "い", "い", "天気", "。"]);
+        assert!((first.segments[1].start_time - 0.35).abs() < 1e-9);
+
+        let second = &subtitles.cues[1];
+        assert!((second.start_time - 1.4).abs() < 1e-9);
+        let texts: Vec<_> = second.segments.iter().map(|s| s.text.as_str()).collect();
+        assert_eq!(texts, ["散歩", "に", "行", "こ", "う", "！"]);
+        // 散歩 = サンポ
+        assert!((second.segments[0].end_time - 1.8).abs() < 1e-9);
+        // 行 = イ
+        assert!((second.segments[2].start_time - 1.95).abs() < 1e-9);
+        assert!((second.segments[2].end_time - 2.05).abs() < 1e-9);
+    }
+
+    #[test]
+    fn tolerates_mismatched_readings() {
+        // 「ー」や読みの誤りがあっても後続の文字に影響しない
+        let data = PhonemeData::sequence(&[
+            ("sil", 0.1),
+            ("r", 0.05),
+            ("a", 0.1),
+            ("a", 0.1),
+            ("m", 0.05),
+            ("e", 0.1),
+            ("N", 0.1),
+            ("sil", 0.1),
+        ]);
+        let readings: ReadingMap = [("麺", "めん")].into_iter().collect();
+
+        let subtitles = Subtitles::align(&["ラーメソ"], &data, &readings);
+
+        let segments = &subtitles.cues[0].segments;
+        let texts: Vec<_> = segments.iter().map(|s| s.text.as_str()).collect();
+        assert_eq!(texts, ["ラ", "ー", "メ", "ソ"]);
+        assert!((segments[1].start_time - 0.25).abs() < 1e-9);
+        assert!((segments[3].start_time - 0.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn srt() {
+        assert_eq!(
+            scene().to_srt(),
+            include_str!("../tests/golden/subtitles.srt")
+        );
+    }
+
+    #[test]
+    fn webvtt() {
+        assert_eq!(
+            scene().to_webvtt(),
+            include_str!("../tests/golden/subtitles.vtt")
+        );
+    }
+
+    #[test]
+    fn ass() {
+        assert_eq!(
+            scene().to_ass(),
+            include_str!("../tests/golden/subtitles.ass")
+        );
+    }
+
+    #[test]
+    fn formats_timestamps() {
+        assert_eq!(timestamp(3723.456, ','), "01:02:03,456");
+        assert_eq!(timestamp(0.0, '.'), "00:00:00.000");
+        assert_eq!(ass_timestamp(centiseconds(3723.456)), "1:02:03.46");
+    }
+}
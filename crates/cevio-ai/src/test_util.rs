@@ -0,0 +1,18 @@
+//! テスト用のヘルパー
+
+use crate::cevio::PhonemeData;
+
+impl PhonemeData {
+    /// 音素と長さ（秒）の組から、0秒から隙間なく並んだ音素データを作成します。
+    pub(crate) fn sequence(items: &[(&str, f64)]) -> Vec<Self> {
+        let mut time = 0.0;
+        items
+            .iter()
+            .map(|&(phoneme, length)| {
+                let data = Self::new(phoneme.to_string(), time, time + length);
+                time += length;
+                data
+            })
+            .collect()
+    }
+}
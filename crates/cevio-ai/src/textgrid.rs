@@ -0,0 +1,614 @@
+//! Praat TextGrid・Audacityラベルのエクスポートとインポート
+//!
+//! このモジュールは、`phonemes()`で取得した音素データを音声分析ツール向けの
+//! テキスト形式に変換する型と関数を提供します。
+//!
+//! | 形式 | 関数 |
+//! |---|---|
+//! | Praat TextGrid | `TextGrid::from_phonemes` / `TextGrid::to_string` |
+//! | Audacity ラベル | `to_audacity_labels` |
+//!
+//! Praatで編集したTextGridは`TextGrid::parse`で読み込み、
+//! `TextGrid::phonemes`で音素データに戻して比較できます。
+//!
+//! ```rust,no_run
+//! use cevio_ai::*;
+//!
+//! fn main() -> Result<()> {
+//!     let cevio = CevioAI::new()?;
+//!     let phonemes = cevio.phonemes("こんにちは")?;
+//!
+//!     let textgrid = TextGrid::from_phonemes(&phonemes, TextGridTiers::default());
+//!     std::fs::write("hello.TextGrid", textgrid.to_string())?;
+//!
+//!     let edited = TextGrid::parse(&std::fs::read_to_string("hello.TextGrid")?)?;
+//!     let phonemes = edited.phonemes(TextGrid::PHONE_TIER)?;
+//!     Ok(())
+//! }
+//! ```
+
+use std::{fmt, str::FromStr};
+
+use crate::{
+    cevio::PhonemeData,
+    error::{CevioAIError, Result},
+    phoneme::{Mora, MoraSegmenter},
+};
+
+/// TextGridの区間
+#[derive(Debug, Clone, PartialEq)]
+pub struct Interval {
+    /// 開始時間（秒）
+    pub xmin: f64,
+
+    /// 終了時間（秒）
+    pub xmax: f64,
+
+    /// ラベル
+    pub text: String,
+}
+
+/// TextGridの区間層（`IntervalTier`）
+#[derive(Debug, Clone, PartialEq)]
+pub struct IntervalTier {
+    /// 層の名前
+    pub name: String,
+
+    /// 開始時間（秒）
+    pub xmin: f64,
+
+    /// 終了時間（秒）
+    pub xmax: f64,
+
+    /// 区間
+    pub intervals: Vec<Interval>,
+}
+
+impl IntervalTier {
+    /// 区間の間の隙間を空の区間で埋めて層を作成します。
+    fn filled(name: &str, xmin: f64, xmax: f64, labels: impl Iterator<Item = Interval>) -> Self {
+        let mut intervals: Vec<Interval> = Vec::new();
+        let mut time = xmin;
+        for interval in labels {
+            if interval.xmin > time {
+                intervals.push(Interval {
+                    xmin: time,
+                    xmax: interval.xmin,
+                    text: String::new(),
+                });
+            }
+            time = interval.xmax;
+            intervals.push(interval);
+        }
+        if xmax > time {
+            intervals.push(Interval {
+                xmin: time,
+                xmax,
+                text: String::new(),
+            });
+        }
+
+        Self {
+            name: name.to_string(),
+            xmin,
+            xmax,
+            intervals,
+        }
+    }
+}
+
+/// `TextGrid::from_phonemes`で追加する層
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct TextGridTiers {
+    /// モーラ層（カタカナ）を追加するかどうか
+    pub moras: bool,
+
+    /// 単語層（休止で区切ったモーラのまとまり）を追加するかどうか
+    pub words: bool,
+}
+
+/// Praat TextGrid
+///
+/// `Display`で長い形式（Praatの既定の保存形式）のテキストに変換できます。
+/// 読み込みは長い形式と短い形式の両方に対応しています。
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextGrid {
+    /// 開始時間（秒）
+    pub xmin: f64,
+
+    /// 終了時間（秒）
+    pub xmax: f64,
+
+    /// 区間層
+    pub tiers: Vec<IntervalTier>,
+}
+
+impl TextGrid {
+    /// 音素層の名前
+    pub const PHONE_TIER: &'static str = "phones";
+
+    /// モーラ層の名前
+    pub const MORA_TIER: &'static str = "moras";
+
+    /// 単語層の名前
+    pub const WORD_TIER: &'static str = "words";
+
+    /// 音素データからTextGridを作成します。
+    ///
+    /// 音素層のラベルは音素記号（`sil`・`pau`を含む）です。
+    /// モーラ層と単語層では、無音の区間は空のラベルになります。
+    ///
+    /// # Arguments
+    ///
+    /// * `phonemes` - 音素データ
+    /// * `tiers` - 音素層の他に追加する層
+    #[must_use]
+    pub fn from_phonemes(phonemes: &[PhonemeData], tiers: TextGridTiers) -> Self {
+        let xmin = 0.0;
+        let xmax = phonemes
+            .iter()
+            .map(PhonemeData::end_time)
+            .fold(xmin, f64::max);
+
+        let mut grid = Self {
+            xmin,
+            xmax,
+            tiers: vec![IntervalTier::filled(
+                Self::PHONE_TIER,
+                xmin,
+                xmax,
+                phonemes.iter().map(|phoneme| Interval {
+                    xmin: phoneme.start_time(),
+                    xmax: phoneme.end_time(),
+                    text: phoneme.phoneme().to_string(),
+                }),
+            )],
+        };
+
+        let moras: Vec<Mora> = MoraSegmenter::new().include_silence(true).segment(phonemes);
+        if tiers.moras {
+            grid.tiers.push(IntervalTier::filled(
+                Self::MORA_TIER,
+                xmin,
+                xmax,
+                moras.iter().map(|mora| Interval {
+                    xmin: mora.start_time,
+                    xmax: mora.end_time,
+                    text: mora.kana(),
+                }),
+            ));
+        }
+        if tiers.words {
+            grid.tiers.push(IntervalTier::filled(
+                Self::WORD_TIER,
+                xmin,
+                xmax,
+                words(&moras).into_iter(),
+            ));
+        }
+
+        grid
+    }
+
+    /// 名前で区間層を取得します。
+    #[must_use]
+    pub fn tier(&self, name: &str) -> Option<&IntervalTier> {
+        self.tiers.iter().find(|tier| tier.name == name)
+    }
+
+    /// 区間層を音素データに変換します。
+    ///
+    /// ラベルが空の区間は含まれません。
+    ///
+    /// # Errors
+    ///
+    /// 指定した名前の区間層がない場合は`InvalidTextGrid`エラーを返します。
+    pub fn phonemes(&self, tier: &str) -> Result<Vec<PhonemeData>> {
+        let tier = self
+            .tier(tier)
+            .ok_or_else(|| CevioAIError::InvalidTextGrid(format!("tier not found: {tier}")))?;
+        Ok(tier
+            .intervals
+            .iter()
+            .filter(|interval| !interval.text.trim().is_empty())
+            .map(|interval| {
+                PhonemeData::new(
+                    interval.text.trim().to_string(),
+                    interval.xmin,
+                    interval.xmax,
+                )
+            })
+            .collect())
+    }
+
+    /// TextGridのテキスト（長い形式・短い形式）を読み込みます。
+    ///
+    /// 点層（`TextTier`）は読み飛ばされます。
+    ///
+    /// # Errors
+    ///
+    /// TextGridとして解釈できない場合は`InvalidTextGrid`エラーを返します。
+    pub fn parse(text: &str) -> Result<Self> {
+        let mut tokens = Tokens::new(text)?;
+        if tokens.text()? != "ooTextFile" || tokens.text()? != "TextGrid" {
+            return Err(CevioAIError::InvalidTextGrid(
+                "not a TextGrid text file".to_string(),
+            ));
+        }
+
+        let xmin = tokens.number()?;
+        let xmax = tokens.number()?;
+        // `tiers? <absent>`の場合はサイズがない
+        let size = if tokens.is_empty() {
+            0
+        } else {
+            tokens.count()?
+        };
+
+        let mut tiers = Vec::new();
+        for _ in 0..size {
+            let class = tokens.text()?;
+            let name = tokens.text()?;
+            let tier_xmin = tokens.number()?;
+            let tier_xmax = tokens.number()?;
+            let count = tokens.count()?;
+            match class.as_str() {
+                "IntervalTier" => {
+                    let intervals = (0..count)
+                        .map(|_| {
+                            Ok(Interval {
+                                xmin: tokens.number()?,
+                                xmax: tokens.number()?,
+                                text: tokens.text()?,
+                            })
+                        })
+                        .collect::<Result<_>>()?;
+                    tiers.push(IntervalTier {
+                        name,
+                        xmin: tier_xmin,
+                        xmax: tier_xmax,
+                        intervals,
+                    });
+                }
+                "TextTier" => {
+                    for _ in 0..count {
+                        tokens.number()?;
+                        tokens.text()?;
+                    }
+                }
+                class => {
+                    return Err(CevioAIError::InvalidTextGrid(format!(
+                        "unknown tier class: {class}"
+                    )))
+                }
+            }
+        }
+
+        Ok(Self { xmin, xmax, tiers })
+    }
+}
+
+impl FromStr for TextGrid {
+    type Err = CevioAIError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Self::parse(s)
+    }
+}
+
+impl fmt::Display for TextGrid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "File type = \"ooTextFile\"")?;
+        writeln!(f, "Object class = \"TextGrid\"")?;
+        writeln!(f)?;
+        writeln!(f, "xmin = {} ", seconds(self.xmin))?;
+        writeln!(f, "xmax = {} ", seconds(self.xmax))?;
+        if self.tiers.is_empty() {
+            return writeln!(f, "tiers? <absent> ");
+        }
+        writeln!(f, "tiers? <exists> ")?;
+        writeln!(f, "size = {} ", self.tiers.len())?;
+        writeln!(f, "item []: ")?;
+        for (index, tier) in self.tiers.iter().enumerate() {
+            writeln!(f, "    item [{}]:", index + 1)?;
+            writeln!(f, "        class = \"IntervalTier\" ")?;
+            writeln!(f, "        name = {} ", quote(&tier.name))?;
+            writeln!(f, "        xmin = {} ", seconds(tier.xmin))?;
+            writeln!(f, "        xmax = {} ", seconds(tier.xmax))?;
+            writeln!(f, "        intervals: size = {} ", tier.intervals.len())?;
+            for (index, interval) in tier.intervals.iter().enumerate() {
+                writeln!(f, "        intervals [{}]:", index + 1)?;
+                writeln!(f, "            xmin = {} ", seconds(interval.xmin))?;
+                writeln!(f, "            xmax = {} ", seconds(interval.xmax))?;
+                writeln!(f, "            text = {} ", quote(&interval.text))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// 音素データをAudacityのラベル（タブ区切りの開始時間・終了時間・ラベル）に変換します。
+#[must_use]
+pub fn to_audacity_labels(phonemes: &[PhonemeData]) -> String {
+    phonemes
+        .iter()
+        .map(|phoneme| {
+            format!(
+                "{:.6}\t{:.6}\t{}\n",
+                phoneme.start_time(),
+                phoneme.end_time(),
+                phoneme.phoneme()
+            )
+        })
+        .collect()
+}
+
+/// 休止で区切ったモーラのまとまりを単語の区間にします。
+fn words(moras: &[Mora]) -> Vec<Interval> {
+    let mut words: Vec<Interval> = Vec::new();
+    let mut continues = false;
+    for mora in moras {
+        if mora.is_silent() {
+            continues = false;
+            continue;
+        }
+        match words.last_mut() {
+            Some(word) if continues => {
+                word.xmax = mora.end_time;
+                word.text.push_str(&mora.kana());
+            }
+            _ => words.push(Interval {
+                xmin: mora.start_time,
+                xmax: mora.end_time,
+                text: mora.kana(),
+            }),
+        }
+        continues = true;
+    }
+    words
+}
+
+/// 時間を小数点以下6桁までに丸めて書式化します。
+fn seconds(value: f64) -> String {
+    let rounded = (value * 1e6).round() / 1e6;
+    if rounded == 0.0 {
+        "0".to_string()
+    } else {
+        rounded.to_string()
+    }
+}
+
+/// 文字列をTextGridの文字列リテラルに変換します。
+fn quote(value: &str) -> String {
+    format!("\"{}\"", value.replace('"', "\"\""))
+}
+
+/// TextGridのトークン
+#[derive(Debug)]
+enum Token {
+    Text(String),
+    Number(f64),
+}
+
+/// TextGridのテキストから文字列と数値だけを取り出したもの
+///
+/// 長い形式の`xmin =`のようなラベルや`[1]`のような添字、`<exists>`は読み飛ばすため、
+/// 長い形式と短い形式で同じトークンの列になります。
+struct Tokens(std::vec::IntoIter<Token>);
+
+impl Tokens {
+    fn new(text: &str) -> Result<Self> {
+        let mut tokens = Vec::new();
+        let mut chars = text.trim_start_matches('\u{feff}').chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '"' => {
+                    let mut value = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('"') if chars.peek() == Some(&'"') => {
+                                chars.next();
+                                value.push('"');
+                            }
+                            Some('"') => break,
+                            Some(c) => value.push(c),
+                            None => {
+                                return Err(CevioAIError::InvalidTextGrid(
+                                    "unterminated string".to_string(),
+                                ))
+                            }
+                        }
+                    }
+                    tokens.push(Token::Text(value));
+                }
+                '[' => while chars.next().is_some_and(|c| c != ']') {},
+                '<' => while chars.next().is_some_and(|c| c != '>') {},
+                '!' => while chars.next().is_some_and(|c| c != '\n') {},
+                c if c.is_ascii_digit() || matches!(c, '-' | '+' | '.') => {
+                    let mut number = c.to_string();
+                    while let Some(&c) = chars.peek() {
+                        if !(c.is_ascii_digit() || matches!(c, '-' | '+' | '.' | 'e' | 'E')) {
+                            break;
+                        }
+                        number.push(c);
+                        chars.next();
+                    }
+                    let value = number.parse().map_err(|_| {
+                        CevioAIError::InvalidTextGrid(format!("invalid number: {number}"))
+                    })?;
+                    tokens.push(Token::Number(value));
+                }
+                c if c.is_alphabetic() => while chars.next_if(|c| c.is_alphanumeric()).is_some() {},
+                _ => {}
+            }
+        }
+        Ok(Self(tokens.into_iter()))
+    }
+
+    fn is_empty(&self) -> bool {
+        self.0.len() == 0
+    }
+
+    fn text(&mut self) -> Result<String> {
+        match self.0.next() {
+            Some(Token::Text(value)) => Ok(value),
+            token => Err(unexpected("string", token)),
+        }
+    }
+
+    fn number(&mut self) -> Result<f64> {
+        match self.0.next() {
+            Some(Token::Number(value)) => Ok(value),
+            token => Err(unexpected("number", token)),
+        }
+    }
+
+    fn count(&mut self) -> Result<usize> {
+        let value = self.number()?;
+        if value < 0.0 || value.fract() != 0.0 {
+            return Err(CevioAIError::InvalidTextGrid(format!(
+                "invalid size: {value}"
+            )));
+        }
+        Ok(value as usize)
+    }
+}
+
+fn unexpected(expected: &str, token: Option<Token>) -> CevioAIError {
+    CevioAIError::InvalidTextGrid(match token {
+        Some(token) => format!("expected {expected}, found {token:?}"),
+        None => format!("expected {expected}, found end of file"),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 「こんにちは、ふゆ」
+    fn phonemes() -> Vec<PhonemeData> {
+        PhonemeData::sequence(&[
+            ("sil", 0.1),
+            ("k", 0.05),
+            ("o", 0.1),
+            ("N", 0.08),
+            ("n", 0.04),
+            ("i", 0.08),
+            ("ch", 0.06),
+            ("i", 0.07),
+            ("w", 0.05),
+            ("a", 0.12),
+            ("pau", 0.2),
+            ("f", 0.06),
+            ("u", 0.1),
+            ("y", 0.04),
+            ("u", 0.15),
+            ("sil", 0.1),
+        ])
+    }
+
+    fn all_tiers() -> TextGridTiers {
+        TextGridTiers {
+            moras: true,
+            words: true,
+        }
+    }
+
+    #[test]
+    fn textgrid() {
+        let textgrid = TextGrid::from_phonemes(&phonemes(), all_tiers());
+
+        assert_eq!(
+            textgrid.to_string(),
+            include_str!("../tests/golden/phonemes.TextGrid")
+        );
+    }
+
+    #[test]
+    fn audacity_labels() {
+        assert_eq!(
+            to_audacity_labels(&phonemes()),
+            include_str!("../tests/golden/audacity.txt")
+        );
+    }
+
+    #[test]
+    fn round_trips_textgrid() {
+        let textgrid = TextGrid::from_phonemes(&phonemes(), all_tiers());
+        let parsed: TextGrid = textgrid.to_string().parse().unwrap();
+
+        assert_eq!(parsed.to_string(), textgrid.to_string());
+        let restored = parsed.phonemes(TextGrid::PHONE_TIER).unwrap();
+        assert_eq!(restored.len(), phonemes().len());
+        for (restored, original) in restored.iter().zip(phonemes()) {
+            assert_eq!(restored.phoneme(), original.phoneme());
+            assert!((restored.start_time() - original.start_time()).abs() < 1e-6);
+            assert!((restored.end_time() - original.end_time()).abs() < 1e-6);
+        }
+        let words: Vec<_> = parsed
+            .tier(TextGrid::WORD_TIER)
+            .unwrap()
+            .intervals
+            .iter()
+            .map(|i| i.text.as_str())
+            .collect();
+        assert_eq!(words, ["", "コンニチワ", "", "フユ", ""]);
+    }
+
+    #[test]
+    fn parses_short_format() {
+        let text = "File type = \"ooTextFile\"\n\
+                    Object class = \"TextGrid\"\n\
+                    \n\
+                    0\n\
+                    0.5\n\
+                    <exists>\n\
+                    2\n\
+                    \"TextTier\"\n\
+                    \"events\"\n\
+                    0\n\
+                    0.5\n\
+                    1\n\
+                    0.25\n\
+                    \"click\"\n\
+                    \"IntervalTier\"\n\
+                    \"phones\"\n\
+                    0\n\
+                    0.5\n\
+                    3\n\
+                    0\n\
+                    0.1\n\
+                    \"\"\n\
+                    0.1\n\
+                    0.3\n\
+                    \"a\"\n\
+                    0.3\n\
+                    0.5\n\
+                    \"say \"\"hi\"\"\"\n";
+
+        let textgrid = TextGrid::parse(text).unwrap();
+
+        assert_eq!(textgrid.tiers.len(), 1);
+        let phonemes = textgrid.phonemes("phones").unwrap();
+        assert_eq!(phonemes.len(), 2);
+        assert_eq!(phonemes[0].phoneme(), "a");
+        assert_eq!(phonemes[1].phoneme(), "say \"hi\"");
+    }
+
+    #[test]
+    fn rejects_invalid_textgrid() {
+        assert!(matches!(
+            TextGrid::parse("File type = \"ooTextFile\"\nObject class = \"Pitch 1\"\n"),
+            Err(CevioAIError::InvalidTextGrid(_))
+        ));
+        assert!(matches!(
+            TextGrid::parse("File type = \"ooTextFile\"\nObject class = \"TextGrid\"\nxmin = 0\n"),
+            Err(CevioAIError::InvalidTextGrid(_))
+        ));
+        let textgrid = TextGrid::from_phonemes(&phonemes(), TextGridTiers::default());
+        assert!(matches!(
+            textgrid.phonemes(TextGrid::MORA_TIER),
+            Err(CevioAIError::InvalidTextGrid(_))
+        ));
+    }
+}
@@ -0,0 +1,402 @@
+//! 音素データの保存と読み込み
+//!
+//! このモジュールは、音素データに合成元のセリフ・キャスト・感情パラメータを添えた
+//! `PhonemeTimeline`を提供します。CSV形式と、`serde`フィーチャを有効にした場合は
+//! JSON形式で保存・読み込みができるため、キャッシュやCeVIO AIのバージョン間の比較に利用できます。
+//!
+//! ## JSON形式
+//!
+//! ```json
+//! {
+//!   "version": 1,
+//!   "text": "こんにちは",
+//!   "cast": { "cast": "さとうささら", "volume": 50, "speed": 50, "tone": 50, "tone_scale": 50, "alpha": 50 },
+//!   "components": [{ "id": "...", "name": "普通", "value": 100 }],
+//!   "phonemes": [
+//!     { "phoneme": "sil", "start_time": 0.0, "end_time": 0.1 },
+//!     { "phoneme": "k", "start_time": 0.1, "end_time": 0.15 }
+//!   ]
+//! }
+//! ```
+//!
+//! - `version`は形式のバージョン（現在は1）です。
+//! - `text`・`cast`は不明な場合`null`、`components`は空の配列になります。
+//! - 時間の単位は秒です。
+//!
+//! ## CSV形式
+//!
+//! ```text
+//! phoneme,start_time,end_time
+//! sil,0,0.1
+//! k,0.1,0.15
+//! ```
+//!
+//! CSV形式には音素データのみが含まれます。
+//!
+//! ```rust,no_run
+//! use cevio_ai::*;
+//!
+//! fn main() -> Result<()> {
+//!     let cevio = CevioAI::new()?;
+//!     let timeline = PhonemeTimeline::capture(&cevio, "こんにちは")?;
+//!     std::fs::write("hello.csv", timeline.to_csv())?;
+//!
+//!     let restored = PhonemeTimeline::from_csv(&std::fs::read_to_string("hello.csv")?)?;
+//!     assert_eq!(restored.phonemes(), timeline.phonemes());
+//!     Ok(())
+//! }
+//! ```
+
+use std::{fmt::Write, ops::Deref};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    backend::Backend,
+    cevio::{Cast, ComponentValue, PhonemeData},
+    error::{CevioAIError, Result},
+};
+
+/// 合成元の情報を添えた音素データ
+#[derive(Debug, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct PhonemeTimeline {
+    /// 合成したセリフ
+    pub text: Option<String>,
+
+    /// 合成に使用したキャストと音声パラメータ
+    pub cast: Option<Cast>,
+
+    /// 合成に使用した感情パラメータ
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub components: Vec<ComponentValue>,
+
+    /// 音素データ
+    pub phonemes: Vec<PhonemeData>,
+}
+
+/// JSON形式の文書（形式のバージョンを含む）
+#[cfg(feature = "serde")]
+#[derive(Serialize, Deserialize)]
+struct Document<T> {
+    version: u32,
+    #[serde(flatten)]
+    timeline: T,
+}
+
+impl PhonemeTimeline {
+    /// JSON形式のバージョン
+    pub const VERSION: u32 = 1;
+
+    /// 音素データから`PhonemeTimeline`を作成します。
+    #[must_use]
+    pub fn new(phonemes: Vec<PhonemeData>) -> Self {
+        Self {
+            phonemes,
+            ..Self::default()
+        }
+    }
+
+    /// セリフを合成し、現在のキャスト・感情パラメータとともに音素データを取得します。
+    ///
+    /// # Errors
+    ///
+    /// CeVIO AIの操作に失敗した場合はエラーを返します。
+    pub fn capture<B: Backend + ?Sized>(backend: &B, text: &str) -> Result<Self> {
+        Ok(Self {
+            text: Some(text.to_string()),
+            cast: Some(backend.current_cast()?),
+            components: backend.component_values()?,
+            phonemes: backend.phonemes(text)?,
+        })
+    }
+
+    /// 音素データを取得します。
+    #[must_use]
+    pub fn phonemes(&self) -> &[PhonemeData] {
+        &self.phonemes
+    }
+
+    /// 音素データを取り出します。
+    #[must_use]
+    pub fn into_phonemes(self) -> Vec<PhonemeData> {
+        self.phonemes
+    }
+
+    /// 最後の音素の終了時間（秒）を取得します。
+    #[must_use]
+    pub fn duration(&self) -> f64 {
+        self.phonemes.last().map_or(0.0, PhonemeData::end_time)
+    }
+
+    /// すべての音素の時間を`seconds`秒ずらします。
+    #[must_use]
+    pub fn shifted(mut self, seconds: f64) -> Self {
+        for phoneme in &mut self.phonemes {
+            *phoneme = PhonemeData::new(
+                phoneme.phoneme().to_string(),
+                phoneme.start_time() + seconds,
+                phoneme.end_time() + seconds,
+            );
+        }
+        self
+    }
+
+    /// すべての音素の時間を`factor`倍にします。
+    ///
+    /// # Errors
+    ///
+    /// `factor`が正の数でない場合は`InvalidParameter`エラーを返します。
+    pub fn scaled(mut self, factor: f64) -> Result<Self> {
+        if !(factor > 0.0 && factor.is_finite()) {
+            return Err(CevioAIError::InvalidParameter(format!(
+                "Scale factor must be positive, got {factor}"
+            )));
+        }
+        for phoneme in &mut self.phonemes {
+            *phoneme = PhonemeData::new(
+                phoneme.phoneme().to_string(),
+                phoneme.start_time() * factor,
+                phoneme.end_time() * factor,
+            );
+        }
+        Ok(self)
+    }
+
+    /// CSV形式に変換します。
+    ///
+    /// 時間は元の値に戻せる桁数で出力されます。
+    #[must_use]
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("phoneme,start_time,end_time\n");
+        for phoneme in &self.phonemes {
+            let _ = writeln!(
+                csv,
+                "{},{},{}",
+                csv_field(phoneme.phoneme()),
+                phoneme.start_time(),
+                phoneme.end_time()
+            );
+        }
+        csv
+    }
+
+    /// CSV形式から読み込みます。
+    ///
+    /// # Errors
+    ///
+    /// ヘッダーが`phoneme,start_time,end_time`でない場合や、
+    /// 値を解釈できない場合は`InvalidTimeline`エラーを返します。
+    pub fn from_csv(csv: &str) -> Result<Self> {
+        let mut lines = csv
+            .trim_start_matches('\u{feff}')
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty());
+
+        match lines.next() {
+            Some((_, header)) if header.trim() == "phoneme,start_time,end_time" => {}
+            _ => {
+                return Err(CevioAIError::InvalidTimeline(
+                    "missing header: phoneme,start_time,end_time".to_string(),
+                ))
+            }
+        }
+
+        let phonemes = lines
+            .map(|(index, line)| {
+                let invalid = |reason: &str| {
+                    CevioAIError::InvalidTimeline(format!("line {}: {reason}", index + 1))
+                };
+                let fields = split_csv(line).ok_or_else(|| invalid("unterminated quote"))?;
+                let [phoneme, start_time, end_time] =
+                    <[String; 3]>::try_from(fields).map_err(|_| invalid("expected 3 fields"))?;
+                let time = |value: &str| {
+                    value
+                        .trim()
+                        .parse::<f64>()
+                        .map_err(|_| invalid(&format!("invalid time: {value}")))
+                };
+                Ok(PhonemeData::new(
+                    phoneme,
+                    time(&start_time)?,
+                    time(&end_time)?,
+                ))
+            })
+            .collect::<Result<_>>()?;
+
+        Ok(Self::new(phonemes))
+    }
+}
+
+#[cfg(feature = "serde")]
+impl PhonemeTimeline {
+    /// JSON形式に変換します。
+    #[must_use]
+    pub fn to_json(&self) -> String {
+        let document = Document {
+            version: Self::VERSION,
+            timeline: self,
+        };
+        // 文字列のキーのみで構成されるため失敗しない
+        serde_json::to_string_pretty(&document).unwrap_or_default()
+    }
+
+    /// JSON形式から読み込みます。
+    ///
+    /// # Errors
+    ///
+    /// JSONとして解釈できない場合や、対応していないバージョンの場合は
+    /// `InvalidTimeline`エラーを返します。
+    pub fn from_json(json: &str) -> Result<Self> {
+        let document: Document<Self> = serde_json::from_str(json)
+            .map_err(|error| CevioAIError::InvalidTimeline(error.to_string()))?;
+        if document.version > Self::VERSION {
+            return Err(CevioAIError::InvalidTimeline(format!(
+                "unsupported version: {}",
+                document.version
+            )));
+        }
+        Ok(document.timeline)
+    }
+}
+
+impl Deref for PhonemeTimeline {
+    type Target = [PhonemeData];
+
+    fn deref(&self) -> &Self::Target {
+        &self.phonemes
+    }
+}
+
+impl From<Vec<PhonemeData>> for PhonemeTimeline {
+    fn from(phonemes: Vec<PhonemeData>) -> Self {
+        Self::new(phonemes)
+    }
+}
+
+impl IntoIterator for PhonemeTimeline {
+    type Item = PhonemeData;
+    type IntoIter = std::vec::IntoIter<PhonemeData>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.phonemes.into_iter()
+    }
+}
+
+/// 必要な場合は引用符で囲んだCSVのフィールド
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// CSVの1行をフィールドに分割します。
+///
+/// 引用符が閉じられていない場合は`None`を返します。
+fn split_csv(line: &str) -> Option<Vec<String>> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                chars.next();
+                field.push('"');
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => fields.push(std::mem::take(&mut field)),
+            c => field.push(c),
+        }
+    }
+    if quoted {
+        return None;
+    }
+    fields.push(field);
+    Some(fields)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn timeline() -> PhonemeTimeline {
+        let items = [
+            ("sil", 0.1),
+            ("k", 0.05),
+            ("o", 0.1),
+            ("N", 0.08),
+            ("sil", 0.1),
+        ];
+        PhonemeTimeline::new(PhonemeData::sequence(&items))
+    }
+
+    #[test]
+    fn round_trips_csv() {
+        let timeline = timeline();
+        let csv = timeline.to_csv();
+
+        assert!(csv.starts_with("phoneme,start_time,end_time\nsil,0,0.1\nk,0.1,"));
+        assert_eq!(PhonemeTimeline::from_csv(&csv).unwrap(), timeline);
+    }
+
+    #[test]
+    fn parses_quoted_csv() {
+        let csv = "phoneme,start_time,end_time\r\n\"a,\"\"b\"\"\", 0.5 ,1\r\n";
+
+        let timeline = PhonemeTimeline::from_csv(csv).unwrap();
+
+        assert_eq!(timeline[0].phoneme(), "a,\"b\"");
+        assert_eq!(timeline[0].start_time(), 0.5);
+        assert!(PhonemeTimeline::from_csv("a,b,c\n").is_err());
+        assert!(PhonemeTimeline::from_csv("phoneme,start_time,end_time\nsil,0\n").is_err());
+        assert!(PhonemeTimeline::from_csv("phoneme,start_time,end_time\nsil,x,1\n").is_err());
+    }
+
+    #[test]
+    fn shifts_and_scales() {
+        let timeline = timeline().shifted(1.0).scaled(2.0).unwrap();
+
+        assert!((timeline[0].start_time() - 2.0).abs() < 1e-9);
+        assert!((timeline.duration() - 2.0 * 1.43).abs() < 1e-9);
+        assert_eq!(timeline[1].phoneme(), "k");
+        assert!(timeline.scaled(0.0).is_err());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn round_trips_json() {
+        use crate::{cevio::CastBuilder, parameter::Speed};
+
+        let mut timeline = timeline();
+        timeline.text = Some("こん".to_string());
+        timeline.cast = Some(
+            CastBuilder::default()
+                .cast("さとうささら")
+                .speed(Speed::new(60).unwrap())
+                .build()
+                .unwrap(),
+        );
+        timeline.components = vec![ComponentValue {
+            id: "A".to_string(),
+            name: "普通".to_string(),
+            value: 100,
+        }];
+
+        let json = timeline.to_json();
+
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(value["version"], 1);
+        assert_eq!(value["cast"]["speed"], 60);
+        assert_eq!(value["phonemes"][1]["phoneme"], "k");
+        assert_eq!(value["phonemes"][1]["start_time"], 0.1);
+        assert_eq!(PhonemeTimeline::from_json(&json).unwrap(), timeline);
+        assert!(
+            PhonemeTimeline::from_json(&json.replace("\"version\": 1", "\"version\": 2")).is_err()
+        );
+    }
+}
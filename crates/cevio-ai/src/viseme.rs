@@ -0,0 +1,659 @@
+//! 口の形（ビゼーム）関連の型定義
+//!
+//! このモジュールは、`PhonemeData`の音素をリップシンク用の口の形に変換するための型を提供します。
+//!
+//! CeVIO AIの音素は次のとおりです。
+//!
+//! | 種類 | 音素 |
+//! |---|---|
+//! | 母音 | `a` `i` `u` `e` `o` |
+//! | 無声化した母音 | `A` `I` `U` `E` `O` |
+//! | 撥音・促音 | `N` `cl` |
+//! | ポーズ・無音 | `pau` `sil` |
+//! | 子音 | `k` `ky` `g` `gy` `s` `sh` `z` `j` `t` `ts` `ch` `d` `n` `ny` `h` `hy` `f` `b` `by` `p` `py` `m` `my` `y` `r` `ry` `w` `v` など |
+//!
+//! 既定の対応（`JapaneseVisemes`）は日本語の5つの母音の口の形に、
+//! 唇を閉じる形（`Closed`）と口を閉じて休んでいる形（`Rest`）を加えたものです。
+//! ほかに、Preston Blair（`PrestonBlair`）、Oculusの15種類（`Oculus`）、
+//! ARKitのブレンドシェイプ（`Arkit`）の対応を用意しています。
+//! `VisemeSet`を実装する（またはクロージャを使う）ことで、独自の対応も定義できます。
+//!
+//! ```rust
+//! use cevio_ai::{viseme_track, Coarticulation, JapaneseVisemes, Viseme};
+//! # fn example(phonemes: &[cevio_ai::PhonemeData]) {
+//! let track = viseme_track(phonemes, &JapaneseVisemes, &Coarticulation::default());
+//! for key in &track {
+//!     println!("{:.3}-{:.3}: {:?}", key.start_time, key.end_time, key.viseme);
+//! }
+//! # }
+//! ```
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::cevio::PhonemeData;
+
+/// 口の形
+///
+/// 日本語の5つの母音の口の形と、唇を閉じる形、休んでいる形を表します。
+///
+/// | 口の形 | 音素 |
+/// |---|---|
+/// | `A` | `a` `A` |
+/// | `I` | `i` `I` |
+/// | `U` | `u` `U` |
+/// | `E` | `e` `E` |
+/// | `O` | `o` `O` |
+/// | `Closed` | `N` `cl` `m` `my` `b` `by` `p` `py` |
+/// | `Rest` | `pau` `sil` とその他の子音 |
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum Viseme {
+    /// あ
+    A,
+    /// い
+    I,
+    /// う
+    U,
+    /// え
+    E,
+    /// お
+    O,
+    /// 唇を閉じる（ん・っ・ま行・ば行・ぱ行）
+    Closed,
+    /// 休止
+    #[default]
+    Rest,
+}
+
+impl Viseme {
+    /// すべての口の形
+    pub const ALL: [Self; 7] = [
+        Self::A,
+        Self::I,
+        Self::U,
+        Self::E,
+        Self::O,
+        Self::Closed,
+        Self::Rest,
+    ];
+
+    /// 音素に対応する口の形を取得します（既定の対応）。
+    #[must_use]
+    pub fn from_phoneme(phoneme: &str) -> Self {
+        match phoneme {
+            "a" | "A" => Self::A,
+            "i" | "I" => Self::I,
+            "u" | "U" => Self::U,
+            "e" | "E" => Self::E,
+            "o" | "O" => Self::O,
+            "N" | "cl" | "m" | "my" | "b" | "by" | "p" | "py" => Self::Closed,
+            _ => Self::Rest,
+        }
+    }
+
+    /// 母音の口の形かどうかを取得します。
+    #[must_use]
+    pub const fn is_vowel(self) -> bool {
+        matches!(self, Self::A | Self::I | Self::U | Self::E | Self::O)
+    }
+
+    /// 名前（`a`・`i`・`u`・`e`・`o`・`closed`・`rest`）を取得します。
+    #[must_use]
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::A => "a",
+            Self::I => "i",
+            Self::U => "u",
+            Self::E => "e",
+            Self::O => "o",
+            Self::Closed => "closed",
+            Self::Rest => "rest",
+        }
+    }
+}
+
+impl From<&PhonemeData> for Viseme {
+    fn from(phoneme: &PhonemeData) -> Self {
+        Self::from_phoneme(phoneme.phoneme())
+    }
+}
+
+/// Preston Blairの口の形
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum PrestonBlairViseme {
+    Ai,
+    E,
+    O,
+    U,
+    Etc,
+    L,
+    Wq,
+    Mbp,
+    Fv,
+    Rest,
+}
+
+impl PrestonBlairViseme {
+    /// Papagayoなどで使われる名前を取得します。
+    #[must_use]
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Ai => "AI",
+            Self::E => "E",
+            Self::O => "O",
+            Self::U => "U",
+            Self::Etc => "etc",
+            Self::L => "L",
+            Self::Wq => "WQ",
+            Self::Mbp => "MBP",
+            Self::Fv => "FV",
+            Self::Rest => "rest",
+        }
+    }
+}
+
+/// Oculus（Meta）Lipsyncの15種類の口の形
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum OculusViseme {
+    Sil,
+    Pp,
+    Ff,
+    Th,
+    Dd,
+    Kk,
+    Ch,
+    Ss,
+    Nn,
+    Rr,
+    Aa,
+    E,
+    Ih,
+    Oh,
+    Ou,
+}
+
+impl OculusViseme {
+    /// Oculus Lipsyncで使われる名前（`viseme_sil`などの接尾辞）を取得します。
+    #[must_use]
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Sil => "sil",
+            Self::Pp => "PP",
+            Self::Ff => "FF",
+            Self::Th => "TH",
+            Self::Dd => "DD",
+            Self::Kk => "kk",
+            Self::Ch => "CH",
+            Self::Ss => "SS",
+            Self::Nn => "nn",
+            Self::Rr => "RR",
+            Self::Aa => "aa",
+            Self::E => "E",
+            Self::Ih => "ih",
+            Self::Oh => "oh",
+            Self::Ou => "ou",
+        }
+    }
+}
+
+/// ARKitのブレンドシェイプの重み（0.0～1.0）
+///
+/// 左右対称のブレンドシェイプは1つの値にまとめています。
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ArkitWeights {
+    /// `jawOpen`
+    pub jaw_open: f32,
+    /// `mouthClose`
+    pub mouth_close: f32,
+    /// `mouthFunnel`
+    pub mouth_funnel: f32,
+    /// `mouthPucker`
+    pub mouth_pucker: f32,
+    /// `mouthStretchLeft`・`mouthStretchRight`
+    pub mouth_stretch: f32,
+    /// `mouthSmileLeft`・`mouthSmileRight`
+    pub mouth_smile: f32,
+    /// `mouthPressLeft`・`mouthPressRight`
+    pub mouth_press: f32,
+}
+
+impl ArkitWeights {
+    /// ARKitのブレンドシェイプ名と重みの組を取得します。
+    #[must_use]
+    pub const fn blendshapes(&self) -> [(&'static str, f32); 10] {
+        [
+            ("jawOpen", self.jaw_open),
+            ("mouthClose", self.mouth_close),
+            ("mouthFunnel", self.mouth_funnel),
+            ("mouthPucker", self.mouth_pucker),
+            ("mouthStretchLeft", self.mouth_stretch),
+            ("mouthStretchRight", self.mouth_stretch),
+            ("mouthSmileLeft", self.mouth_smile),
+            ("mouthSmileRight", self.mouth_smile),
+            ("mouthPressLeft", self.mouth_press),
+            ("mouthPressRight", self.mouth_press),
+        ]
+    }
+
+    /// 2つの重みを線形補間します。
+    ///
+    /// # Arguments
+    ///
+    /// * `other` - 補間先の重み
+    /// * `t` - 補間係数（0.0で`self`、1.0で`other`）
+    #[must_use]
+    pub fn lerp(&self, other: &Self, t: f32) -> Self {
+        let t = t.clamp(0.0, 1.0);
+        let mix = |a: f32, b: f32| a + (b - a) * t;
+        Self {
+            jaw_open: mix(self.jaw_open, other.jaw_open),
+            mouth_close: mix(self.mouth_close, other.mouth_close),
+            mouth_funnel: mix(self.mouth_funnel, other.mouth_funnel),
+            mouth_pucker: mix(self.mouth_pucker, other.mouth_pucker),
+            mouth_stretch: mix(self.mouth_stretch, other.mouth_stretch),
+            mouth_smile: mix(self.mouth_smile, other.mouth_smile),
+            mouth_press: mix(self.mouth_press, other.mouth_press),
+        }
+    }
+}
+
+impl From<Viseme> for ArkitWeights {
+    fn from(viseme: Viseme) -> Self {
+        let zero = Self::default();
+        match viseme {
+            Viseme::A => Self {
+                jaw_open: 0.7,
+                mouth_stretch: 0.1,
+                ..zero
+            },
+            Viseme::I => Self {
+                jaw_open: 0.2,
+                mouth_stretch: 0.4,
+                mouth_smile: 0.5,
+                ..zero
+            },
+            Viseme::U => Self {
+                jaw_open: 0.15,
+                mouth_funnel: 0.3,
+                mouth_pucker: 0.7,
+                ..zero
+            },
+            Viseme::E => Self {
+                jaw_open: 0.45,
+                mouth_stretch: 0.4,
+                mouth_smile: 0.2,
+                ..zero
+            },
+            Viseme::O => Self {
+                jaw_open: 0.5,
+                mouth_funnel: 0.6,
+                mouth_pucker: 0.2,
+                ..zero
+            },
+            Viseme::Closed => Self {
+                mouth_press: 0.5,
+                ..zero
+            },
+            Viseme::Rest => zero,
+        }
+    }
+}
+
+/// 音素と口の形の対応
+///
+/// `Fn(&str) -> V`を満たすクロージャも`VisemeSet`として使えます。
+pub trait VisemeSet {
+    /// 口の形の型
+    type Viseme: Clone + PartialEq;
+
+    /// 音素に対応する口の形を取得します。
+    fn viseme(&self, phoneme: &str) -> Self::Viseme;
+
+    /// 休止（無音）の口の形を取得します。
+    fn rest(&self) -> Self::Viseme {
+        self.viseme("sil")
+    }
+}
+
+impl<V: Clone + PartialEq, F: Fn(&str) -> V> VisemeSet for F {
+    type Viseme = V;
+
+    fn viseme(&self, phoneme: &str) -> V {
+        self(phoneme)
+    }
+}
+
+/// 既定の対応（`Viseme::from_phoneme`）
+#[derive(Debug, Clone, Copy, Default)]
+pub struct JapaneseVisemes;
+
+impl VisemeSet for JapaneseVisemes {
+    type Viseme = Viseme;
+
+    fn viseme(&self, phoneme: &str) -> Viseme {
+        Viseme::from_phoneme(phoneme)
+    }
+}
+
+/// Preston Blairの口の形への対応
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PrestonBlair;
+
+impl VisemeSet for PrestonBlair {
+    type Viseme = PrestonBlairViseme;
+
+    fn viseme(&self, phoneme: &str) -> PrestonBlairViseme {
+        match phoneme {
+            "a" | "A" => PrestonBlairViseme::Ai,
+            "i" | "I" | "e" | "E" => PrestonBlairViseme::E,
+            "o" | "O" => PrestonBlairViseme::O,
+            "u" | "U" => PrestonBlairViseme::U,
+            "w" => PrestonBlairViseme::Wq,
+            "r" | "ry" => PrestonBlairViseme::L,
+            "f" | "v" => PrestonBlairViseme::Fv,
+            "N" | "cl" | "m" | "my" | "b" | "by" | "p" | "py" => PrestonBlairViseme::Mbp,
+            "pau" | "sil" => PrestonBlairViseme::Rest,
+            _ => PrestonBlairViseme::Etc,
+        }
+    }
+}
+
+/// Oculus Lipsyncの口の形への対応
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Oculus;
+
+impl VisemeSet for Oculus {
+    type Viseme = OculusViseme;
+
+    fn viseme(&self, phoneme: &str) -> OculusViseme {
+        match phoneme {
+            "a" | "A" => OculusViseme::Aa,
+            "i" | "I" | "y" => OculusViseme::Ih,
+            "u" | "U" | "w" => OculusViseme::Ou,
+            "e" | "E" => OculusViseme::E,
+            "o" | "O" => OculusViseme::Oh,
+            "cl" | "m" | "my" | "b" | "by" | "p" | "py" => OculusViseme::Pp,
+            "f" | "v" => OculusViseme::Ff,
+            "t" | "ty" | "d" | "dy" => OculusViseme::Dd,
+            "k" | "ky" | "kw" | "g" | "gy" | "gw" => OculusViseme::Kk,
+            "ch" | "j" | "sh" => OculusViseme::Ch,
+            "s" | "z" | "ts" => OculusViseme::Ss,
+            "N" | "n" | "ny" => OculusViseme::Nn,
+            "r" | "ry" => OculusViseme::Rr,
+            _ => OculusViseme::Sil,
+        }
+    }
+}
+
+/// ARKitのブレンドシェイプの重みへの対応
+///
+/// 既定の対応で得た口の形を`ArkitWeights::from`で重みに変換します。
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Arkit;
+
+impl VisemeSet for Arkit {
+    type Viseme = ArkitWeights;
+
+    fn viseme(&self, phoneme: &str) -> ArkitWeights {
+        Viseme::from_phoneme(phoneme).into()
+    }
+}
+
+/// 口の形のキー
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct VisemeKey<V> {
+    /// 口の形
+    pub viseme: V,
+
+    /// 開始時間（秒）
+    pub start_time: f64,
+
+    /// 終了時間（秒）
+    pub end_time: f64,
+}
+
+impl<V> VisemeKey<V> {
+    /// 長さ（秒）を取得します。
+    #[must_use]
+    pub fn duration(&self) -> f64 {
+        self.end_time - self.start_time
+    }
+}
+
+/// 調音結合（前後の音素による口の形の変化）の設定
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Coarticulation {
+    /// 休止の口の形に対応する子音を、後続の音素の口の形で先取りするかどうか
+    ///
+    /// 「か」の`k`のように口の形を持たない子音の間、
+    /// 口が閉じてしまうのを防ぎます。
+    pub anticipate_consonants: bool,
+
+    /// キーの最小の長さ（秒）
+    ///
+    /// これより短いキーは直前のキーに統合されます。0.0で無効になります。
+    pub min_duration: f64,
+}
+
+impl Default for Coarticulation {
+    /// 子音の先取りを有効にし、最小の長さを30fpsの1フレームとします。
+    fn default() -> Self {
+        Self {
+            anticipate_consonants: true,
+            min_duration: 1.0 / 30.0,
+        }
+    }
+}
+
+impl Coarticulation {
+    /// 調音結合を行わない設定
+    pub const NONE: Self = Self {
+        anticipate_consonants: false,
+        min_duration: 0.0,
+    };
+}
+
+/// 音素データを口の形のトラックに変換します。
+///
+/// 同じ口の形が続く場合は1つのキーにまとめられます。
+///
+/// # Arguments
+///
+/// * `phonemes` - 音素データ
+/// * `set` - 音素と口の形の対応
+/// * `coarticulation` - 調音結合の設定
+pub fn viseme_track<S: VisemeSet + ?Sized>(
+    phonemes: &[PhonemeData],
+    set: &S,
+    coarticulation: &Coarticulation,
+) -> Vec<VisemeKey<S::Viseme>> {
+    let rest = set.rest();
+    let visemes: Vec<S::Viseme> = phonemes.iter().map(|p| set.viseme(p.phoneme())).collect();
+
+    let mut keys: Vec<VisemeKey<S::Viseme>> = Vec::with_capacity(phonemes.len());
+    for (index, phoneme) in phonemes.iter().enumerate() {
+        let mut viseme = visemes[index].clone();
+        if coarticulation.anticipate_consonants
+            && viseme == rest
+            && phoneme.to_phoneme().is_consonant()
+        {
+            if let Some(next) = visemes.get(index + 1) {
+                viseme = next.clone();
+            }
+        }
+
+        let key = VisemeKey {
+            viseme,
+            start_time: phoneme.start_time(),
+            end_time: phoneme.end_time(),
+        };
+        push_key(&mut keys, key, coarticulation.min_duration);
+    }
+
+    // 先頭のキーが短い場合は次のキーに統合する
+    if keys.len() >= 2 && keys[0].duration() < coarticulation.min_duration {
+        let first = keys.remove(0);
+        keys[0].start_time = first.start_time;
+    }
+
+    keys
+}
+
+fn push_key<V: PartialEq>(keys: &mut Vec<VisemeKey<V>>, key: VisemeKey<V>, min_duration: f64) {
+    if let Some(last) = keys.last_mut() {
+        if last.viseme == key.viseme || key.duration() < min_duration {
+            last.end_time = key.end_time;
+            return;
+        }
+    }
+    keys.push(key);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn visemes<V: Clone>(track: &[VisemeKey<V>]) -> Vec<V> {
+        track.iter().map(|key| key.viseme.clone()).collect()
+    }
+
+    #[test]
+    fn default_mapping() {
+        assert_eq!(Viseme::from_phoneme("a"), Viseme::A);
+        assert_eq!(Viseme::from_phoneme("U"), Viseme::U);
+        assert_eq!(Viseme::from_phoneme("N"), Viseme::Closed);
+        assert_eq!(Viseme::from_phoneme("cl"), Viseme::Closed);
+        assert_eq!(Viseme::from_phoneme("by"), Viseme::Closed);
+        assert_eq!(Viseme::from_phoneme("k"), Viseme::Rest);
+        assert_eq!(Viseme::from_phoneme("pau"), Viseme::Rest);
+        assert_eq!(Viseme::from_phoneme("unknown"), Viseme::Rest);
+    }
+
+    #[test]
+    fn raw_track_merges_repeated_visemes() {
+        // 「かあ」
+        let data = PhonemeData::sequence(&[
+            ("sil", 0.1),
+            ("k", 0.05),
+            ("a", 0.1),
+            ("a", 0.1),
+            ("sil", 0.1),
+        ]);
+
+        let track = viseme_track(&data, &JapaneseVisemes, &Coarticulation::NONE);
+
+        assert_eq!(visemes(&track), [Viseme::Rest, Viseme::A, Viseme::Rest]);
+        assert!((track[0].end_time - 0.15).abs() < 1e-9);
+        assert!((track[1].duration() - 0.2).abs() < 1e-9);
+    }
+
+    #[test]
+    fn anticipates_consonants() {
+        // 「かま」: kは後続のaを先取りし、mは唇を閉じる
+        let data = PhonemeData::sequence(&[
+            ("sil", 0.1),
+            ("k", 0.05),
+            ("a", 0.1),
+            ("m", 0.05),
+            ("a", 0.1),
+            ("sil", 0.1),
+        ]);
+        let coarticulation = Coarticulation {
+            min_duration: 0.0,
+            ..Default::default()
+        };
+
+        let track = viseme_track(&data, &JapaneseVisemes, &coarticulation);
+
+        assert_eq!(
+            visemes(&track),
+            [
+                Viseme::Rest,
+                Viseme::A,
+                Viseme::Closed,
+                Viseme::A,
+                Viseme::Rest
+            ]
+        );
+        assert!((track[1].start_time - 0.1).abs() < 1e-9);
+    }
+
+    #[test]
+    fn merges_short_keys() {
+        let data = PhonemeData::sequence(&[
+            ("sil", 0.01),
+            ("a", 0.1),
+            ("i", 0.01),
+            ("u", 0.1),
+            ("sil", 0.1),
+        ]);
+
+        let track = viseme_track(&data, &JapaneseVisemes, &Coarticulation::default());
+
+        assert_eq!(visemes(&track), [Viseme::A, Viseme::U, Viseme::Rest]);
+        assert_eq!(track[0].start_time, 0.0);
+        assert!((track[0].end_time - 0.12).abs() < 1e-9);
+    }
+
+    #[test]
+    fn alternate_sets() {
+        let data = PhonemeData::sequence(&[
+            ("sil", 0.1),
+            ("f", 0.05),
+            ("u", 0.1),
+            ("r", 0.05),
+            ("i", 0.1),
+            ("sil", 0.1),
+        ]);
+
+        let track = viseme_track(&data, &PrestonBlair, &Coarticulation::NONE);
+        assert_eq!(
+            visemes(&track)
+                .into_iter()
+                .map(PrestonBlairViseme::name)
+                .collect::<Vec<_>>(),
+            ["rest", "FV", "U", "L", "E", "rest"]
+        );
+
+        let track = viseme_track(&data, &Oculus, &Coarticulation::NONE);
+        assert_eq!(
+            visemes(&track)
+                .into_iter()
+                .map(OculusViseme::name)
+                .collect::<Vec<_>>(),
+            ["sil", "FF", "ou", "RR", "ih", "sil"]
+        );
+
+        let track = viseme_track(&data, &Arkit, &Coarticulation::default());
+        assert_eq!(track[1].viseme, ArkitWeights::from(Viseme::U));
+        assert_eq!(track[1].viseme.blendshapes()[3], ("mouthPucker", 0.7));
+    }
+
+    #[test]
+    fn custom_sets() {
+        let data = PhonemeData::sequence(&[("a", 0.1), ("N", 0.1)]);
+        let open = |phoneme: &str| phoneme.chars().all(|c| "aiueo".contains(c));
+
+        let track = viseme_track(&data, &open, &Coarticulation::NONE);
+
+        assert_eq!(visemes(&track), [true, false]);
+    }
+
+    #[test]
+    fn lerps_weights() {
+        let rest = ArkitWeights::from(Viseme::Rest);
+        let a = ArkitWeights::from(Viseme::A);
+
+        let half = rest.lerp(&a, 0.5);
+
+        assert!((half.jaw_open - 0.35).abs() < 1e-6);
+        assert_eq!(rest.lerp(&a, 2.0), a);
+    }
+}
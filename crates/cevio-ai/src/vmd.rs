@@ -0,0 +1,448 @@
+//! MikuMikuDanceのモーション（VMD）関連の型定義
+//!
+//! このモジュールは、音素データからあ・い・う・え・おのモーフのキーフレームを作成し、
+//! VMD形式のモーションファイルとして出力するための型を提供します。
+//!
+//! ```rust,no_run
+//! use cevio_ai::*;
+//!
+//! fn main() -> Result<()> {
+//!     let cevio = CevioAI::new()?;
+//!     let phonemes = cevio.phonemes("こんにちは")?;
+//!
+//!     let vmd = phonemes_to_vmd(&phonemes, "初音ミク", 30.0, &MorphMap::default())?;
+//!     std::fs::write("hello.vmd", vmd)?;
+//!     Ok(())
+//! }
+//! ```
+
+use std::collections::BTreeMap;
+
+use encoding_rs::SHIFT_JIS;
+
+use crate::{
+    cevio::PhonemeData,
+    error::{CevioAIError, Result},
+    viseme::{viseme_track, Coarticulation, JapaneseVisemes, Viseme},
+};
+
+/// VMDファイルのヘッダー
+const HEADER: &[u8; 30] = b"Vocaloid Motion Data 0002\0\0\0\0\0";
+
+/// モデル名の長さ（バイト）
+const MODEL_NAME_SIZE: usize = 20;
+
+/// モーフ名の長さ（バイト）
+const MORPH_NAME_SIZE: usize = 15;
+
+/// ボーンのキーフレーム1つの長さ（バイト）
+const BONE_KEY_SIZE: usize = 111;
+
+/// 口の形とモーフの対応
+///
+/// キーフレームの重みの最大値や、口を開き始める（閉じ終わる）までの時間も設定できます。
+#[derive(Debug, Clone, PartialEq)]
+pub struct MorphMap {
+    /// 「あ」のモーフ名
+    pub a: String,
+
+    /// 「い」のモーフ名
+    pub i: String,
+
+    /// 「う」のモーフ名
+    pub u: String,
+
+    /// 「え」のモーフ名
+    pub e: String,
+
+    /// 「お」のモーフ名
+    pub o: String,
+
+    /// 唇を閉じる形（ん・ま行など）のモーフ名（`None`の場合はすべてのモーフを0にします）
+    pub closed: Option<String>,
+
+    /// 重みの最大値（0.0～1.0）
+    pub max_weight: f32,
+
+    /// 音素の開始前にモーフを動かし始める時間（秒）
+    pub attack: f64,
+
+    /// 音素の終了後にモーフを戻し終える時間（秒）
+    pub release: f64,
+}
+
+impl Default for MorphMap {
+    /// 標準的なモデルのモーフ名（あ・い・う・え・お）を使用します。
+    fn default() -> Self {
+        Self {
+            a: "あ".to_string(),
+            i: "い".to_string(),
+            u: "う".to_string(),
+            e: "え".to_string(),
+            o: "お".to_string(),
+            closed: None,
+            max_weight: 1.0,
+            attack: 0.05,
+            release: 0.05,
+        }
+    }
+}
+
+impl MorphMap {
+    /// 口の形に対応するモーフ名を取得します。
+    #[must_use]
+    pub fn morph(&self, viseme: Viseme) -> Option<&str> {
+        match viseme {
+            Viseme::A => Some(&self.a),
+            Viseme::I => Some(&self.i),
+            Viseme::U => Some(&self.u),
+            Viseme::E => Some(&self.e),
+            Viseme::O => Some(&self.o),
+            Viseme::Closed => self.closed.as_deref(),
+            Viseme::Rest => None,
+        }
+    }
+}
+
+/// モーフのキーフレーム
+#[derive(Debug, Clone, PartialEq)]
+pub struct VmdMorphKey {
+    /// モーフ名
+    pub name: String,
+
+    /// フレーム番号
+    pub frame: u32,
+
+    /// 重み（0.0～1.0）
+    pub weight: f32,
+}
+
+/// VMD形式のモーション
+///
+/// モーフのキーフレームのみを扱います。
+/// 読み込み時、ボーン以外のキーフレーム（カメラ・照明など）は無視されます。
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct VmdMotion {
+    /// モデル名
+    pub model_name: String,
+
+    /// モーフのキーフレーム
+    pub morphs: Vec<VmdMorphKey>,
+}
+
+impl VmdMotion {
+    /// 音素データからモーションを作成します。
+    ///
+    /// # Arguments
+    ///
+    /// * `phonemes` - 音素データ
+    /// * `model_name` - モデル名
+    /// * `fps` - フレームレート（MikuMikuDanceは30fps）
+    /// * `map` - 口の形とモーフの対応
+    #[must_use]
+    pub fn from_phonemes(
+        phonemes: &[PhonemeData],
+        model_name: &str,
+        fps: f64,
+        map: &MorphMap,
+    ) -> Self {
+        let track = viseme_track(phonemes, &JapaneseVisemes, &Coarticulation::default());
+        let frame = |time: f64| (time.max(0.0) * fps).round() as u32;
+
+        // モーフごとに、重みが最大になる区間を集める
+        let mut segments: BTreeMap<&str, Vec<(f64, f64)>> = BTreeMap::new();
+        for key in &track {
+            if let Some(morph) = map.morph(key.viseme) {
+                segments
+                    .entry(morph)
+                    .or_default()
+                    .push((key.start_time, key.end_time));
+            }
+        }
+
+        let mut morphs = Vec::new();
+        for (name, segments) in segments {
+            // 同じフレームのキーは後のものを優先する
+            let mut keys: BTreeMap<u32, f32> = BTreeMap::new();
+            keys.insert(0, 0.0);
+            for (index, &(start, end)) in segments.iter().enumerate() {
+                // 同じモーフの区間が近い場合は、中間で重みを0にする
+                let mut rise = start - map.attack;
+                let mut fall = end + map.release;
+                if let Some(&(_, previous_end)) = index.checked_sub(1).map(|i| &segments[i]) {
+                    rise = rise.max((previous_end + start) / 2.0);
+                }
+                if let Some(&(next_start, _)) = segments.get(index + 1) {
+                    fall = fall.min((end + next_start) / 2.0);
+                }
+
+                keys.insert(frame(rise), 0.0);
+                keys.insert(frame(start), map.max_weight);
+                keys.insert(frame(end), map.max_weight);
+                keys.insert(frame(fall), 0.0);
+            }
+
+            morphs.extend(keys.into_iter().map(|(frame, weight)| VmdMorphKey {
+                name: name.to_string(),
+                frame,
+                weight,
+            }));
+        }
+
+        Self {
+            model_name: model_name.to_string(),
+            morphs,
+        }
+    }
+
+    /// VMD形式のバイト列に変換します。
+    ///
+    /// # Errors
+    ///
+    /// モデル名またはモーフ名をShift_JISで表現できない場合は `CevioAIError::InvalidParameter` を返します。
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        let mut bytes = Vec::with_capacity(HEADER.len() + 40 + self.morphs.len() * 23);
+        bytes.extend_from_slice(HEADER);
+        bytes.extend_from_slice(&encode_name(&self.model_name, MODEL_NAME_SIZE)?);
+
+        // ボーン
+        bytes.extend_from_slice(&0u32.to_le_bytes());
+
+        // モーフ
+        let count = u32::try_from(self.morphs.len())
+            .map_err(|_| CevioAIError::InvalidParameter("Too many morph keys".to_string()))?;
+        bytes.extend_from_slice(&count.to_le_bytes());
+        for key in &self.morphs {
+            bytes.extend_from_slice(&encode_name(&key.name, MORPH_NAME_SIZE)?);
+            bytes.extend_from_slice(&key.frame.to_le_bytes());
+            bytes.extend_from_slice(&key.weight.to_le_bytes());
+        }
+
+        // カメラ・照明・セルフ影・表示/IK
+        for _ in 0..4 {
+            bytes.extend_from_slice(&0u32.to_le_bytes());
+        }
+
+        Ok(bytes)
+    }
+
+    /// VMD形式のバイト列を読み込みます。
+    ///
+    /// # Errors
+    ///
+    /// VMD形式として解析できない場合は `CevioAIError::InvalidVmd` を返します。
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        let mut reader = Reader { bytes, offset: 0 };
+
+        if reader.take(HEADER.len())?[..25] != HEADER[..25] {
+            return Err(CevioAIError::InvalidVmd("not a VMD file".to_string()));
+        }
+        let model_name = decode_name(reader.take(MODEL_NAME_SIZE)?);
+
+        let bones = reader.u32()? as usize;
+        reader.take(bones.checked_mul(BONE_KEY_SIZE).ok_or_else(|| {
+            CevioAIError::InvalidVmd(format!("invalid bone key count: {bones}"))
+        })?)?;
+
+        let count = reader.u32()? as usize;
+        let mut morphs = Vec::with_capacity(count.min(bytes.len() / 23));
+        for _ in 0..count {
+            let name = decode_name(reader.take(MORPH_NAME_SIZE)?);
+            let frame = reader.u32()?;
+            let weight = f32::from_le_bytes(reader.array()?);
+            morphs.push(VmdMorphKey {
+                name,
+                frame,
+                weight,
+            });
+        }
+
+        Ok(Self { model_name, morphs })
+    }
+}
+
+/// 音素データからVMD形式のモーションファイルを作成します。
+///
+/// `VmdMotion::from_phonemes(..).to_bytes()`と同じです。
+///
+/// # Errors
+///
+/// モデル名またはモーフ名をShift_JISで表現できない場合は `CevioAIError::InvalidParameter` を返します。
+pub fn phonemes_to_vmd(
+    phonemes: &[PhonemeData],
+    model_name: &str,
+    fps: f64,
+    map: &MorphMap,
+) -> Result<Vec<u8>> {
+    VmdMotion::from_phonemes(phonemes, model_name, fps, map).to_bytes()
+}
+
+/// 名前をShift_JISの固定長のバイト列に変換します。
+///
+/// 長すぎる場合は文字の途中で切れないように切り詰めます。
+fn encode_name(name: &str, size: usize) -> Result<Vec<u8>> {
+    let mut encoded = Vec::with_capacity(size);
+    for c in name.chars() {
+        let mut buffer = [0; 4];
+        let (bytes, _, unmappable) = SHIFT_JIS.encode(c.encode_utf8(&mut buffer));
+        if unmappable {
+            return Err(CevioAIError::InvalidParameter(format!(
+                "Cannot encode {name:?} in Shift_JIS"
+            )));
+        }
+        if encoded.len() + bytes.len() > size {
+            break;
+        }
+        encoded.extend_from_slice(&bytes);
+    }
+    encoded.resize(size, 0);
+    Ok(encoded)
+}
+
+fn decode_name(bytes: &[u8]) -> String {
+    let end = bytes.iter().position(|&b| b == 0).unwrap_or(bytes.len());
+    SHIFT_JIS
+        .decode_without_bom_handling(&bytes[..end])
+        .0
+        .into_owned()
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, size: usize) -> Result<&'a [u8]> {
+        let end = self
+            .offset
+            .checked_add(size)
+            .filter(|&end| end <= self.bytes.len())
+            .ok_or_else(|| CevioAIError::InvalidVmd("unexpected end of data".to_string()))?;
+        let bytes = &self.bytes[self.offset..end];
+        self.offset = end;
+        Ok(bytes)
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N]> {
+        let mut array = [0; N];
+        array.copy_from_slice(self.take(N)?);
+        Ok(array)
+    }
+
+    fn u32(&mut self) -> Result<u32> {
+        Ok(u32::from_le_bytes(self.array()?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keys(motion: &VmdMotion, name: &str) -> Vec<(u32, f32)> {
+        motion
+            .morphs
+            .iter()
+            .filter(|key| key.name == name)
+            .map(|key| (key.frame, key.weight))
+            .collect()
+    }
+
+    #[test]
+    fn creates_morph_keys() {
+        // 「あいあ」
+        let data = PhonemeData::sequence(&[
+            ("sil", 0.5),
+            ("a", 0.5),
+            ("i", 0.5),
+            ("a", 0.5),
+            ("sil", 0.5),
+        ]);
+        let map = MorphMap {
+            max_weight: 0.8,
+            attack: 0.1,
+            release: 0.2,
+            ..Default::default()
+        };
+
+        let motion = VmdMotion::from_phonemes(&data, "model", 30.0, &map);
+
+        assert_eq!(
+            keys(&motion, "あ"),
+            [
+                (0, 0.0),
+                (12, 0.0),
+                (15, 0.8),
+                (30, 0.8),
+                (36, 0.0),
+                (42, 0.0),
+                (45, 0.8),
+                (60, 0.8),
+                (66, 0.0)
+            ]
+        );
+        assert_eq!(
+            keys(&motion, "い"),
+            [(0, 0.0), (27, 0.0), (30, 0.8), (45, 0.8), (51, 0.0)]
+        );
+        assert!(keys(&motion, "う").is_empty());
+    }
+
+    #[test]
+    fn round_trips_bytes() {
+        let data = PhonemeData::sequence(&[
+            ("sil", 0.1),
+            ("k", 0.05),
+            ("o", 0.2),
+            ("N", 0.1),
+            ("sil", 0.1),
+        ]);
+        let map = MorphMap {
+            closed: Some("ん".to_string()),
+            ..Default::default()
+        };
+        let motion = VmdMotion::from_phonemes(&data, "初音ミク", 30.0, &map);
+
+        let bytes = motion.to_bytes().unwrap();
+
+        assert_eq!(&bytes[..30], HEADER);
+        // ヘッダー + モデル名 + ボーン数 + モーフ数 + モーフ + カメラ・照明・影・IK
+        assert_eq!(bytes.len(), 30 + 20 + 4 + 4 + motion.morphs.len() * 23 + 16);
+        // 「お」のShift_JIS
+        assert!(bytes.windows(2).any(|pair| pair == [0x82, 0xa8]));
+        assert_eq!(VmdMotion::from_bytes(&bytes).unwrap(), motion);
+        assert!(!keys(&motion, "ん").is_empty());
+    }
+
+    #[test]
+    fn truncates_long_names() {
+        // 全角10文字 = 20バイト、11文字目は切り捨てられる
+        let name = "あいうえおかきくけこさ";
+        let encoded = encode_name(name, MODEL_NAME_SIZE).unwrap();
+        assert_eq!(decode_name(&encoded), "あいうえおかきくけこ");
+
+        // 1バイト文字の後の全角文字は途中で切れない
+        let encoded = encode_name("abcdefghijklmnopqrsあ", MODEL_NAME_SIZE).unwrap();
+        assert_eq!(decode_name(&encoded), "abcdefghijklmnopqrs");
+
+        assert!(matches!(
+            encode_name("😀", MODEL_NAME_SIZE),
+            Err(CevioAIError::InvalidParameter(_))
+        ));
+    }
+
+    #[test]
+    fn rejects_invalid_data() {
+        assert!(matches!(
+            VmdMotion::from_bytes(b"not a vmd"),
+            Err(CevioAIError::InvalidVmd(_))
+        ));
+
+        let mut bytes = VmdMotion::default().to_bytes().unwrap();
+        bytes.truncate(52);
+        assert!(matches!(
+            VmdMotion::from_bytes(&bytes),
+            Err(CevioAIError::InvalidVmd(_))
+        ));
+    }
+}
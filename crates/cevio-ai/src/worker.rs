@@ -0,0 +1,735 @@
+//! COMワーカースレッド
+//!
+//! CeVIO AIのCOMインターフェースはスレッド間で共有できないため、
+//! 専用のワーカースレッドがすべてのインターフェースを所有します。
+//! 他のスレッドからはチャネル経由で[`Request`]を送り、返信を待って結果を受け取ります。
+//! 感情パラメータや再生状態のCOMオブジェクトはワーカースレッド上の表に保持され、
+//! 呼び出し側には[`Handle`]だけが渡されます。
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use crate::{
+    cevio::{Capabilities, CloseMode, PhonemeData},
+    com_manager::{self, ApartmentModel},
+    error::{CevioAIError, Result},
+};
+
+/// ワーカースレッドの名前
+pub(crate) const WORKER_THREAD_NAME: &str = "cevio-ai-com";
+
+/// ホストの起動を伴う処理に適用する最低限のタイムアウト時間
+///
+/// ワーカースレッドの初期化（COMオブジェクトの作成でホストが起動される場合があります）と、
+/// ホストの起動を待つ`StartHost`に適用します。
+const HOST_STARTUP_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// 合成を伴うリクエストで、セリフ1文字ごとに加算するタイムアウト時間
+const SYNTHESIS_TIMEOUT_PER_CHAR: Duration = Duration::from_millis(200);
+
+/// STAのワーカースレッドがリクエストを待つ間にメッセージを処理する間隔
+const MESSAGE_PUMP_INTERVAL: Duration = Duration::from_millis(10);
+
+/// リクエストへの返信先
+pub(crate) type Reply<T> = Sender<Result<T>>;
+
+/// ワーカースレッド上のCOMオブジェクトを指すハンドル
+///
+/// 値はプロセス内で一意です。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) struct Handle(u64);
+
+impl Handle {
+    /// 新しいハンドルを払い出します。
+    pub(crate) fn next() -> Self {
+        static NEXT: AtomicU64 = AtomicU64::new(1);
+        Self(NEXT.fetch_add(1, Ordering::Relaxed))
+    }
+}
+
+/// ハンドルとオブジェクトの対応表
+///
+/// ワーカースレッド上でCOMオブジェクトを保持するために使用します。
+#[derive(Debug)]
+pub(crate) struct HandleTable<T> {
+    entries: HashMap<Handle, T>,
+}
+
+impl<T> Default for HandleTable<T> {
+    fn default() -> Self {
+        Self {
+            entries: HashMap::new(),
+        }
+    }
+}
+
+impl<T> HandleTable<T> {
+    /// オブジェクトを登録し、そのハンドルを返します。
+    pub(crate) fn insert(&mut self, value: T) -> Handle {
+        let handle = Handle::next();
+        self.entries.insert(handle, value);
+        handle
+    }
+
+    /// ハンドルが指すオブジェクトを取得します。
+    ///
+    /// # Errors
+    ///
+    /// 未登録または解放済みのハンドルの場合は `CevioAIError::InvalidParameter` を返します。
+    pub(crate) fn get(&self, handle: Handle) -> Result<&T> {
+        self.entries
+            .get(&handle)
+            .ok_or_else(|| CevioAIError::InvalidParameter(format!("Unknown handle: {}", handle.0)))
+    }
+
+    /// ハンドルが指すオブジェクトを解放します。
+    pub(crate) fn remove(&mut self, handle: Handle) -> Option<T> {
+        self.entries.remove(&handle)
+    }
+
+    /// 登録されているオブジェクトの数を返します。
+    #[cfg(test)]
+    pub(crate) fn len(&self) -> usize {
+        self.entries.len()
+    }
+}
+
+/// トークの音声パラメータの種類
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum TalkerParameter {
+    Volume,
+    Speed,
+    Tone,
+    ToneScale,
+    Alpha,
+}
+
+/// 感情パラメータの一覧の要素
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct ComponentEntry {
+    pub(crate) handle: Handle,
+    pub(crate) id: String,
+    pub(crate) name: String,
+}
+
+/// ワーカースレッドへのリクエスト
+///
+/// `Release`以外のリクエストは`reply`に結果を1回だけ返信します。
+#[derive(Debug)]
+pub(crate) enum Request {
+    Capabilities {
+        reply: Reply<Capabilities>,
+    },
+    StartHost {
+        no_wait: bool,
+        reply: Reply<i32>,
+    },
+    CloseHost {
+        mode: CloseMode,
+        reply: Reply<()>,
+    },
+    HostVersion {
+        reply: Reply<String>,
+    },
+    InterfaceVersion {
+        reply: Reply<String>,
+    },
+    IsHostStarted {
+        reply: Reply<bool>,
+    },
+    Parameter {
+        parameter: TalkerParameter,
+        reply: Reply<u32>,
+    },
+    SetParameter {
+        parameter: TalkerParameter,
+        value: u32,
+        reply: Reply<()>,
+    },
+    Cast {
+        reply: Reply<String>,
+    },
+    SetCast {
+        cast: String,
+        reply: Reply<()>,
+    },
+    AvailableCasts {
+        reply: Reply<Vec<String>>,
+    },
+    Components {
+        reply: Reply<Vec<ComponentEntry>>,
+    },
+    ComponentValue {
+        component: Handle,
+        reply: Reply<u32>,
+    },
+    SetComponentValue {
+        component: Handle,
+        value: u32,
+        reply: Reply<()>,
+    },
+    Speak {
+        text: String,
+        reply: Reply<Handle>,
+    },
+    Stop {
+        reply: Reply<bool>,
+    },
+    IsCompleted {
+        state: Handle,
+        reply: Reply<bool>,
+    },
+    IsSucceeded {
+        state: Handle,
+        reply: Reply<bool>,
+    },
+    TextDuration {
+        text: String,
+        reply: Reply<f64>,
+    },
+    Phonemes {
+        text: String,
+        reply: Reply<Vec<PhonemeData>>,
+    },
+    OutputWaveToFile {
+        text: String,
+        path: PathBuf,
+        reply: Reply<bool>,
+    },
+    /// ハンドルが指すオブジェクトを解放します（返信なし）。
+    Release {
+        handle: Handle,
+    },
+}
+
+impl Request {
+    /// エラーに添える操作名と引数を返します。
+    fn context(&self) -> (&'static str, Vec<String>) {
+        match self {
+            Request::Capabilities { .. } => ("Capabilities", Vec::new()),
+            Request::StartHost { no_wait, .. } => ("StartHost", vec![no_wait.to_string()]),
+            Request::CloseHost { mode, .. } => ("CloseHost", vec![format!("{mode:?}")]),
+            Request::HostVersion { .. } => ("HostVersion", Vec::new()),
+            Request::InterfaceVersion { .. } => ("InterfaceVersion", Vec::new()),
+            Request::IsHostStarted { .. } => ("IsHostStarted", Vec::new()),
+            Request::Parameter { parameter, .. } => ("Parameter", vec![format!("{parameter:?}")]),
+            Request::SetParameter {
+                parameter, value, ..
+            } => (
+                "SetParameter",
+                vec![format!("{parameter:?}"), value.to_string()],
+            ),
+            Request::Cast { .. } => ("Cast", Vec::new()),
+            Request::SetCast { cast, .. } => ("SetCast", vec![cast.clone()]),
+            Request::AvailableCasts { .. } => ("AvailableCasts", Vec::new()),
+            Request::Components { .. } => ("Components", Vec::new()),
+            Request::ComponentValue { .. } => ("ComponentValue", Vec::new()),
+            Request::SetComponentValue { value, .. } => {
+                ("SetComponentValue", vec![value.to_string()])
+            }
+            Request::Speak { text, .. } => ("Speak", vec![text.clone()]),
+            Request::Stop { .. } => ("Stop", Vec::new()),
+            Request::IsCompleted { .. } => ("IsCompleted", Vec::new()),
+            Request::IsSucceeded { .. } => ("IsSucceeded", Vec::new()),
+            Request::TextDuration { text, .. } => ("TextDuration", vec![text.clone()]),
+            Request::Phonemes { text, .. } => ("Phonemes", vec![text.clone()]),
+            Request::OutputWaveToFile { text, path, .. } => (
+                "OutputWaveToFile",
+                vec![text.clone(), path.display().to_string()],
+            ),
+            Request::Release { .. } => ("Release", Vec::new()),
+        }
+    }
+
+    /// 返信を待つ時間を返します。
+    ///
+    /// ホストの起動を待つ`StartHost`には[`HOST_STARTUP_TIMEOUT`]以上を適用します。
+    /// 合成を伴うリクエストには、セリフの長さに応じて[`SYNTHESIS_TIMEOUT_PER_CHAR`]を加算します。
+    fn timeout(&self, timeout: Duration) -> Duration {
+        match self {
+            Request::StartHost { no_wait: false, .. } => timeout.max(HOST_STARTUP_TIMEOUT),
+            Request::OutputWaveToFile { text, .. }
+            | Request::Phonemes { text, .. }
+            | Request::TextDuration { text, .. } => {
+                let chars = u32::try_from(text.chars().count()).unwrap_or(u32::MAX);
+                timeout.saturating_add(SYNTHESIS_TIMEOUT_PER_CHAR.saturating_mul(chars))
+            }
+            _ => timeout,
+        }
+    }
+}
+
+/// ワーカースレッド上でリクエストを処理するトレイト
+///
+/// 実装はワーカースレッド上で生成・使用・破棄されるため、`Send`である必要はありません。
+pub(crate) trait Handler {
+    /// リクエストを1件処理し、必要に応じて返信します。
+    fn handle(&mut self, request: Request);
+}
+
+/// 結果を返信します。
+///
+/// 呼び出し側が既に待機をやめている場合、結果は破棄されます。
+pub(crate) fn respond<T>(reply: Reply<T>, result: Result<T>) {
+    let _ = reply.send(result);
+}
+
+/// COMワーカースレッドへの窓口
+///
+/// 返信を待つ時間には上限があり、超えた場合はワーカースレッドがCOM呼び出しから戻らないものとして
+/// 使用不能（poisoned）になります。
+/// 最後の参照が破棄されるとチャネルを閉じ、ワーカースレッドの終了を待ちます（使用不能な場合は待ちません）。
+#[derive(Debug)]
+pub(crate) struct Worker {
+    sender: Option<Sender<Request>>,
+    thread: Option<JoinHandle<()>>,
+    timeout: Duration,
+    poisoned: AtomicBool,
+}
+
+impl Worker {
+    /// ワーカースレッドを起動します。
+    ///
+    /// `init`はワーカースレッド上で実行され、生成された[`Handler`]がリクエストを処理します。
+    /// `apartment`がSTAの場合、ワーカースレッドはリクエストを待つ間もメッセージを処理します。
+    /// 各リクエストの返信は`timeout`（リクエストによってはそれ以上）まで待ちます。
+    /// `init`はホストの起動を伴う場合があるため、[`HOST_STARTUP_TIMEOUT`]以上待ちます。
+    ///
+    /// # Errors
+    ///
+    /// - スレッドの起動に失敗した場合
+    /// - `init`がエラーを返した場合はそのエラー（COM呼び出しの失敗は`CevioAIError::Com`）
+    /// - `init`が時間内に終わらない場合は `CevioAIError::Timeout`
+    pub(crate) fn spawn<H, F>(timeout: Duration, apartment: ApartmentModel, init: F) -> Result<Self>
+    where
+        H: Handler,
+        F: FnOnce() -> Result<H> + Send + 'static,
+    {
+        let (sender, receiver) = mpsc::channel::<Request>();
+        let (ready, started) = mpsc::channel::<Result<()>>();
+
+        let thread = thread::Builder::new()
+            .name(WORKER_THREAD_NAME.to_string())
+            .spawn(move || {
+                let mut handler = match init() {
+                    Ok(handler) => {
+                        let _ = ready.send(Ok(()));
+                        handler
+                    }
+                    Err(e) => {
+                        let _ = ready.send(Err(e));
+                        return;
+                    }
+                };
+
+                match apartment {
+                    ApartmentModel::MultiThreaded => {
+                        for request in receiver {
+                            handler.handle(request);
+                        }
+                    }
+                    ApartmentModel::SingleThreaded => loop {
+                        com_manager::pump_messages();
+                        match receiver.recv_timeout(MESSAGE_PUMP_INTERVAL) {
+                            Ok(request) => handler.handle(request),
+                            Err(RecvTimeoutError::Timeout) => {}
+                            Err(RecvTimeoutError::Disconnected) => break,
+                        }
+                    },
+                }
+            })?;
+
+        let worker = Self {
+            sender: Some(sender),
+            thread: Some(thread),
+            timeout,
+            poisoned: AtomicBool::new(false),
+        };
+        let start = Instant::now();
+        match started.recv_timeout(timeout.max(HOST_STARTUP_TIMEOUT)) {
+            Ok(result) => result.map_err(|error| error.with_context("Initialize", Vec::new()))?,
+            Err(RecvTimeoutError::Timeout) => return Err(worker.poison("Initialize", start)),
+            Err(RecvTimeoutError::Disconnected) => return Err(CevioAIError::WorkerStopped),
+        }
+        Ok(worker)
+    }
+
+    /// タイムアウトにより使用不能になっているかどうかを返します。
+    pub(crate) fn is_poisoned(&self) -> bool {
+        self.poisoned.load(Ordering::Acquire)
+    }
+
+    /// 使用不能にし、タイムアウトのエラーを返します。
+    fn poison(&self, operation: &'static str, start: Instant) -> CevioAIError {
+        self.poisoned.store(true, Ordering::Release);
+        CevioAIError::Timeout {
+            operation,
+            elapsed: start.elapsed(),
+        }
+    }
+
+    /// リクエストを送信し、返信を待ちます。
+    ///
+    /// # Errors
+    ///
+    /// - ワーカースレッドが停止している場合は `CevioAIError::WorkerStopped`
+    /// - 以前のリクエストがタイムアウトしている場合は `CevioAIError::Poisoned`
+    /// - 返信がタイムアウト時間内に届かない場合は `CevioAIError::Timeout`（以降は使用不能）
+    /// - リクエストの処理に失敗した場合はそのエラー（COM呼び出しの失敗は操作名と引数を添えた
+    ///   `CevioAIError::Com`）
+    pub(crate) fn call<T>(&self, request: impl FnOnce(Reply<T>) -> Request) -> Result<T> {
+        if self.is_poisoned() {
+            return Err(CevioAIError::Poisoned);
+        }
+        let (reply, receiver) = mpsc::channel();
+        let request = request(reply);
+        let (operation, arguments) = request.context();
+        let timeout = request.timeout(self.timeout);
+        let start = Instant::now();
+        self.send(request)?;
+        match receiver.recv_timeout(timeout) {
+            Ok(result) => result.map_err(|error| error.with_context(operation, arguments)),
+            Err(RecvTimeoutError::Timeout) => Err(self.poison(operation, start)),
+            Err(RecvTimeoutError::Disconnected) => Err(CevioAIError::WorkerStopped),
+        }
+    }
+
+    /// ハンドルが指すオブジェクトの解放を要求します。
+    ///
+    /// ワーカースレッドが既に停止している場合は何もしません。
+    pub(crate) fn release(&self, handle: Handle) {
+        let _ = self.send(Request::Release { handle });
+    }
+
+    fn send(&self, request: Request) -> Result<()> {
+        self.sender
+            .as_ref()
+            .ok_or(CevioAIError::WorkerStopped)?
+            .send(request)
+            .map_err(|_| CevioAIError::WorkerStopped)
+    }
+}
+
+impl Drop for Worker {
+    fn drop(&mut self) {
+        self.sender.take();
+        if let Some(thread) = self.thread.take() {
+            // 使用不能なワーカースレッドはCOM呼び出しから戻らない可能性があるため、待たずに切り離す
+            if !self.is_poisoned() && thread.thread().id() != thread::current().id() {
+                let _ = thread.join();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use super::*;
+    use crate::ComErrorKind;
+
+    /// テスト用のワーカー
+    ///
+    /// 感情パラメータの値と、指定回数の問い合わせ後に完了する再生状態を保持します。
+    struct FakeHandler {
+        components: HandleTable<u32>,
+        states: HandleTable<u32>,
+        log: Arc<Mutex<Vec<String>>>,
+    }
+
+    impl FakeHandler {
+        fn new(log: Arc<Mutex<Vec<String>>>) -> Self {
+            Self {
+                components: HandleTable::default(),
+                states: HandleTable::default(),
+                log,
+            }
+        }
+
+        fn record(&self, entry: &str) {
+            self.log.lock().unwrap().push(entry.to_string());
+        }
+    }
+
+    impl Handler for FakeHandler {
+        fn handle(&mut self, request: Request) {
+            match request {
+                Request::HostVersion { reply } => {
+                    let name = thread::current().name().map(str::to_string);
+                    respond(reply, Ok(name.unwrap_or_default()));
+                }
+                Request::Components { reply } => {
+                    let entries = ["普通", "元気"]
+                        .iter()
+                        .enumerate()
+                        .map(|(i, name)| ComponentEntry {
+                            handle: self.components.insert(50),
+                            id: format!("id{i}"),
+                            name: (*name).to_string(),
+                        })
+                        .collect();
+                    respond(reply, Ok(entries));
+                }
+                Request::ComponentValue { component, reply } => {
+                    respond(reply, self.components.get(component).copied());
+                }
+                Request::SetComponentValue {
+                    component,
+                    value,
+                    reply,
+                } => {
+                    let result = self.components.get(component).map(|_| ());
+                    if result.is_ok() {
+                        self.components.entries.insert(component, value);
+                    }
+                    respond(reply, result);
+                }
+                Request::Speak { text, reply } => {
+                    self.record(&format!("speak {text}"));
+                    respond(reply, Ok(self.states.insert(2)));
+                }
+                Request::SetCast { reply, .. } => {
+                    let error =
+                        windows::core::Error::from(windows::Win32::Foundation::E_INVALIDARG);
+                    respond(reply, Err(error.into()));
+                }
+                Request::IsCompleted { state, reply } => {
+                    let result = match self.states.entries.get_mut(&state) {
+                        Some(remaining) => {
+                            *remaining = remaining.saturating_sub(1);
+                            Ok(*remaining == 0)
+                        }
+                        None => self.states.get(state).map(|_| false),
+                    };
+                    respond(reply, result);
+                }
+                Request::Release { handle } => {
+                    self.components.remove(handle);
+                    self.states.remove(handle);
+                    self.record(&format!(
+                        "release {} {}",
+                        self.components.len(),
+                        self.states.len()
+                    ));
+                }
+                other => panic!("unexpected request: {other:?}"),
+            }
+        }
+    }
+
+    impl Drop for FakeHandler {
+        fn drop(&mut self) {
+            let name = thread::current().name().map(str::to_string);
+            self.record(&format!("drop on {}", name.unwrap_or_default()));
+        }
+    }
+
+    fn spawn_fake() -> (Worker, Arc<Mutex<Vec<String>>>) {
+        spawn_fake_in(ApartmentModel::default())
+    }
+
+    fn spawn_fake_in(apartment: ApartmentModel) -> (Worker, Arc<Mutex<Vec<String>>>) {
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let handler_log = Arc::clone(&log);
+        let worker = Worker::spawn(Duration::from_secs(5), apartment, move || {
+            Ok(FakeHandler::new(handler_log))
+        })
+        .unwrap();
+        (worker, log)
+    }
+
+    #[test]
+    fn requests_run_on_worker_thread() -> Result<()> {
+        let (worker, _) = spawn_fake();
+        let worker = Arc::new(worker);
+
+        let threads: Vec<_> = (0..4)
+            .map(|_| {
+                let worker = Arc::clone(&worker);
+                thread::spawn(move || worker.call(|reply| Request::HostVersion { reply }))
+            })
+            .collect();
+
+        for thread in threads {
+            assert_eq!(thread.join().unwrap()?, WORKER_THREAD_NAME);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn single_threaded_worker_handles_requests_and_stops() -> Result<()> {
+        let (worker, log) = spawn_fake_in(ApartmentModel::SingleThreaded);
+
+        // メッセージを処理する間隔より長く待っても、リクエストを受け付ける
+        thread::sleep(MESSAGE_PUMP_INTERVAL * 3);
+        assert_eq!(
+            worker.call(|reply| Request::HostVersion { reply })?,
+            WORKER_THREAD_NAME
+        );
+
+        drop(worker);
+        assert_eq!(*log.lock().unwrap(), ["drop on cevio-ai-com"]);
+        Ok(())
+    }
+
+    #[test]
+    fn handles_resolve_on_worker_thread() -> Result<()> {
+        let (worker, log) = spawn_fake();
+
+        let components = worker.call(|reply| Request::Components { reply })?;
+        let names: Vec<_> = components.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, ["普通", "元気"]);
+
+        let handle = components[1].handle;
+        worker.call(|reply| Request::SetComponentValue {
+            component: handle,
+            value: 80,
+            reply,
+        })?;
+        assert_eq!(
+            worker.call(|reply| Request::ComponentValue {
+                component: handle,
+                reply
+            })?,
+            80
+        );
+
+        let state = worker.call(|reply| Request::Speak {
+            text: "こんにちは".to_string(),
+            reply,
+        })?;
+        assert!(!worker.call(|reply| Request::IsCompleted { state, reply })?);
+        assert!(worker.call(|reply| Request::IsCompleted { state, reply })?);
+
+        for entry in &components {
+            worker.release(entry.handle);
+        }
+        worker.release(state);
+        // 返信のあるリクエストで、先に送った解放が処理済みであることを保証する
+        worker.call(|reply| Request::HostVersion { reply })?;
+
+        let result = worker.call(|reply| Request::ComponentValue {
+            component: handle,
+            reply,
+        });
+        assert!(matches!(result, Err(CevioAIError::InvalidParameter(_))));
+
+        drop(worker);
+        let log = log.lock().unwrap();
+        assert_eq!(
+            *log,
+            [
+                "speak こんにちは",
+                "release 1 1",
+                "release 0 1",
+                "release 0 0",
+                "drop on cevio-ai-com",
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn init_error_is_returned() {
+        let result = Worker::spawn(
+            Duration::from_secs(5),
+            ApartmentModel::default(),
+            || -> Result<FakeHandler> { Err(CevioAIError::InvalidParameter("init".to_string())) },
+        );
+        assert!(matches!(result, Err(CevioAIError::InvalidParameter(_))));
+    }
+
+    #[test]
+    fn init_com_errors_are_classified() {
+        let result = Worker::spawn(
+            Duration::from_secs(5),
+            ApartmentModel::default(),
+            || -> Result<FakeHandler> {
+                Err(
+                    windows::core::Error::from(windows::Win32::Foundation::REGDB_E_CLASSNOTREG)
+                        .into(),
+                )
+            },
+        );
+        let Err(CevioAIError::Com(error)) = result else {
+            panic!("expected Com error");
+        };
+        assert_eq!(error.kind(), ComErrorKind::NotInstalled);
+        assert_eq!(error.operation(), "Initialize");
+    }
+
+    #[test]
+    fn stopped_worker_is_reported() {
+        struct Panicking;
+
+        impl Handler for Panicking {
+            fn handle(&mut self, _request: Request) {
+                panic!("worker failure");
+            }
+        }
+
+        let worker = Worker::spawn(Duration::from_secs(5), ApartmentModel::default(), || {
+            Ok(Panicking)
+        })
+        .unwrap();
+        let first = worker.call(|reply| Request::Stop { reply });
+        let second = worker.call(|reply| Request::Stop { reply });
+        assert!(matches!(first, Err(CevioAIError::WorkerStopped)));
+        assert!(matches!(second, Err(CevioAIError::WorkerStopped)));
+    }
+
+    #[test]
+    fn com_errors_carry_request_context() {
+        let (worker, _) = spawn_fake();
+
+        let result = worker.call(|reply| Request::SetCast {
+            cast: "存在しないキャスト".to_string(),
+            reply,
+        });
+        let Err(CevioAIError::Com(error)) = result else {
+            panic!("expected Com error, got {result:?}");
+        };
+        assert_eq!(error.kind(), ComErrorKind::CastNotFound);
+        assert_eq!(error.operation(), "SetCast");
+        assert_eq!(error.arguments(), ["存在しないキャスト"]);
+        assert_eq!(error.hresult(), windows::Win32::Foundation::E_INVALIDARG);
+    }
+
+    #[test]
+    fn long_operations_wait_longer() {
+        let timeout = Duration::from_secs(5);
+        let (reply, _) = mpsc::channel();
+        let waiting = Request::StartHost {
+            no_wait: false,
+            reply,
+        };
+        assert_eq!(waiting.timeout(timeout), HOST_STARTUP_TIMEOUT);
+
+        let (reply, _) = mpsc::channel();
+        let no_wait = Request::StartHost {
+            no_wait: true,
+            reply,
+        };
+        assert_eq!(no_wait.timeout(timeout), timeout);
+
+        let (reply, _) = mpsc::channel();
+        let output = Request::OutputWaveToFile {
+            text: "あ".repeat(100),
+            path: PathBuf::from("out.wav"),
+            reply,
+        };
+        assert_eq!(output.timeout(timeout), timeout + Duration::from_secs(20));
+
+        let (reply, _) = mpsc::channel();
+        let speak = Request::Speak {
+            text: "あ".repeat(100),
+            reply,
+        };
+        assert_eq!(speak.timeout(timeout), timeout);
+    }
+}
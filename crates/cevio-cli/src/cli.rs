@@ -0,0 +1,183 @@
+//! コマンドライン引数の定義
+
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use cevio_ai::{Alpha, Cast, Speed, Tone, ToneScale, Volume};
+use clap::{Args, Parser, Subcommand, ValueEnum};
+
+/// CeVIO AI コマンドラインツール
+#[derive(Debug, Parser)]
+#[command(name = "cevio", version, about)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Command,
+}
+
+/// サブコマンド
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// 利用可能なキャストを一覧表示します
+    Casts,
+
+    /// キャストの感情パラメータを表示します
+    Components {
+        /// キャスト名（省略時は現在のキャスト）
+        #[arg(long)]
+        cast: Option<String>,
+    },
+
+    /// セリフを再生します
+    Say {
+        /// セリフ
+        text: String,
+
+        #[command(flatten)]
+        voice: VoiceArgs,
+    },
+
+    /// テキストファイルを合成してWAVファイルに出力します
+    ///
+    /// 空行を除く各行を順に合成し、1つのWAVファイルに連結します。
+    Render {
+        /// 入力テキストファイル（`-`で標準入力）
+        input: PathBuf,
+
+        /// 出力WAVファイル
+        #[arg(short, long)]
+        output: PathBuf,
+
+        #[command(flatten)]
+        voice: VoiceArgs,
+    },
+
+    /// セリフの音素データを出力します
+    Phonemes {
+        /// セリフ
+        text: String,
+
+        /// 出力形式
+        #[arg(long, value_enum, default_value_t = PhonemeFormat::Json)]
+        format: PhonemeFormat,
+
+        #[command(flatten)]
+        voice: VoiceArgs,
+    },
+
+    /// セリフの長さ（秒）を表示します
+    Duration {
+        /// セリフ
+        text: String,
+
+        #[command(flatten)]
+        voice: VoiceArgs,
+    },
+
+    /// CeVIO AIを操作します
+    Host {
+        #[command(subcommand)]
+        command: HostCommand,
+    },
+}
+
+/// `host`サブコマンド
+#[derive(Debug, Subcommand)]
+pub enum HostCommand {
+    /// CeVIO AIを起動します
+    Start {
+        /// アクセス可能になるのを待たずに戻ります
+        #[arg(long)]
+        no_wait: bool,
+    },
+
+    /// CeVIO AIを終了します
+    Close {
+        /// 編集中の内容を破棄して強制的に終了します
+        #[arg(long)]
+        force: bool,
+    },
+
+    /// CeVIO AIが起動中かどうかを表示します
+    Status,
+
+    /// CeVIO AIとCOMインターフェースのバージョンを表示します
+    Version,
+}
+
+/// 音素データの出力形式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum PhonemeFormat {
+    /// JSON配列
+    Json,
+    /// ヘッダー付きCSV
+    Csv,
+}
+
+/// キャストと音声パラメータの指定
+#[derive(Debug, Clone, Default, Args)]
+pub struct VoiceArgs {
+    /// キャスト名
+    #[arg(long)]
+    pub cast: Option<String>,
+
+    /// 音の大きさ（0～100）
+    #[arg(long, value_parser = parse_parameter::<Volume>)]
+    pub volume: Option<Volume>,
+
+    /// 話す速さ（0～100）
+    #[arg(long, value_parser = parse_parameter::<Speed>)]
+    pub speed: Option<Speed>,
+
+    /// 音の高さ（0～100）
+    #[arg(long, value_parser = parse_parameter::<Tone>)]
+    pub tone: Option<Tone>,
+
+    /// 抑揚（0～100）
+    #[arg(long, value_parser = parse_parameter::<ToneScale>)]
+    pub tone_scale: Option<ToneScale>,
+
+    /// 声質（0～100）
+    #[arg(long, value_parser = parse_parameter::<Alpha>)]
+    pub alpha: Option<Alpha>,
+
+    /// 感情パラメータ（例：`--emotion 元気=80`、複数指定可）
+    #[arg(long = "emotion", value_name = "NAME=VALUE", value_parser = parse_emotion)]
+    pub emotions: Vec<(String, u8)>,
+}
+
+impl VoiceArgs {
+    /// キャスト設定に変換します。
+    #[must_use]
+    pub fn to_cast(&self) -> Cast {
+        Cast {
+            cast: self.cast.clone(),
+            volume: self.volume,
+            speed: self.speed,
+            tone: self.tone,
+            tone_scale: self.tone_scale,
+            alpha: self.alpha,
+        }
+    }
+}
+
+fn parse_parameter<T: FromStr>(s: &str) -> Result<T, String> {
+    s.parse()
+        .map_err(|_| format!("expected an integer between 0 and 100, got `{s}`"))
+}
+
+fn parse_emotion(s: &str) -> Result<(String, u8), String> {
+    let (name, value) = s
+        .split_once('=')
+        .ok_or_else(|| format!("expected NAME=VALUE, got `{s}`"))?;
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(format!("missing emotion name in `{s}`"));
+    }
+    let value = value
+        .trim()
+        .parse::<u8>()
+        .ok()
+        .filter(|value| *value <= 100)
+        .ok_or_else(|| format!("expected an integer between 0 and 100, got `{value}`"))?;
+    Ok((name.to_string(), value))
+}
@@ -0,0 +1,351 @@
+//! サブコマンドの実行
+//!
+//! 各サブコマンドは`Backend`を介してCeVIO AIを操作し、結果を`Write`に出力します。
+
+use std::io::{Read, Write};
+use std::path::Path;
+
+use cevio_ai::{AudioBuffer, Backend, CloseMode, ComponentValue, PhonemeData};
+
+use crate::{
+    cli::{Command, HostCommand, PhonemeFormat, VoiceArgs},
+    error::{Error, Result},
+};
+
+/// サブコマンドを実行します。
+pub fn run<B: Backend + ?Sized>(command: &Command, backend: &B, out: &mut dyn Write) -> Result<()> {
+    match command {
+        Command::Casts => {
+            for cast in backend.available_casts()? {
+                writeln!(out, "{cast}")?;
+            }
+        }
+        Command::Components { cast } => {
+            if let Some(cast) = cast {
+                backend.apply_cast(&cevio_ai::Cast {
+                    cast: Some(cast.clone()),
+                    ..Default::default()
+                })?;
+            }
+            write_components(out, &backend.component_values()?)?;
+        }
+        Command::Say { text, voice } => {
+            apply_voice(backend, voice)?;
+            if !backend.speak_and_wait(text)? {
+                return Err(Error::SpeakFailed);
+            }
+        }
+        Command::Render {
+            input,
+            output,
+            voice,
+        } => {
+            let text = read_input(input)?;
+            apply_voice(backend, voice)?;
+            let audio = render(backend, &text)?.ok_or_else(|| Error::EmptyInput(input.clone()))?;
+            audio.write_wav_file(output)?;
+            writeln!(out, "{}: {:.3}s", output.display(), audio.duration())?;
+        }
+        Command::Phonemes {
+            text,
+            format,
+            voice,
+        } => {
+            apply_voice(backend, voice)?;
+            write_phonemes(out, &backend.phonemes(text)?, *format)?;
+        }
+        Command::Duration { text, voice } => {
+            apply_voice(backend, voice)?;
+            writeln!(out, "{:.3}", backend.text_duration(text)?)?;
+        }
+        Command::Host { command } => run_host(command, backend, out)?,
+    }
+
+    Ok(())
+}
+
+fn run_host<B: Backend + ?Sized>(
+    command: &HostCommand,
+    backend: &B,
+    out: &mut dyn Write,
+) -> Result<()> {
+    match command {
+        HostCommand::Start { no_wait } => backend.start(*no_wait)?,
+        HostCommand::Close { force } => backend.close(if *force {
+            CloseMode::Force
+        } else {
+            CloseMode::Interactive
+        })?,
+        HostCommand::Status => {
+            let status = if backend.is_host_started()? {
+                "started"
+            } else {
+                "stopped"
+            };
+            writeln!(out, "{status}")?;
+        }
+        HostCommand::Version => {
+            writeln!(out, "host: {}", backend.host_version()?)?;
+            writeln!(out, "interface: {}", backend.interface_version()?)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn apply_voice<B: Backend + ?Sized>(backend: &B, voice: &VoiceArgs) -> Result<()> {
+    backend.apply_cast(&voice.to_cast())?;
+    backend.apply_components(&voice.emotions)?;
+    Ok(())
+}
+
+fn read_input(input: &Path) -> Result<String> {
+    if input.as_os_str() == "-" {
+        let mut text = String::new();
+        std::io::stdin().read_to_string(&mut text)?;
+        Ok(text)
+    } else {
+        Ok(std::fs::read_to_string(input)?)
+    }
+}
+
+/// 空行を除く各行を合成して連結します。
+///
+/// 合成する行がない場合は`None`を返します。
+fn render<B: Backend + ?Sized>(backend: &B, text: &str) -> Result<Option<AudioBuffer>> {
+    let mut audio: Option<AudioBuffer> = None;
+
+    for line in text.lines().map(str::trim).filter(|line| !line.is_empty()) {
+        let buffer = backend.synthesize(line)?;
+        match audio {
+            Some(ref mut audio) => audio.append(&buffer)?,
+            None => audio = Some(buffer),
+        }
+    }
+
+    Ok(audio)
+}
+
+fn write_components(out: &mut dyn Write, components: &[ComponentValue]) -> Result<()> {
+    for component in components {
+        writeln!(
+            out,
+            "{}\t{}\t{}",
+            component.name, component.value, component.id
+        )?;
+    }
+    Ok(())
+}
+
+fn write_phonemes(
+    out: &mut dyn Write,
+    phonemes: &[PhonemeData],
+    format: PhonemeFormat,
+) -> Result<()> {
+    match format {
+        PhonemeFormat::Json => {
+            serde_json::to_writer_pretty(&mut *out, phonemes)?;
+            writeln!(out)?;
+        }
+        PhonemeFormat::Csv => {
+            writeln!(out, "phoneme,start_time,end_time")?;
+            for phoneme in phonemes {
+                writeln!(
+                    out,
+                    "{},{},{}",
+                    phoneme.phoneme(),
+                    phoneme.start_time(),
+                    phoneme.end_time()
+                )?;
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use cevio_ai::FakeBackend;
+    use clap::Parser;
+
+    use super::*;
+    use crate::cli::Cli;
+
+    fn execute(backend: &FakeBackend, args: &[&str]) -> Result<String> {
+        let cli = Cli::try_parse_from(std::iter::once("cevio").chain(args.iter().copied()))
+            .expect("arguments should parse");
+        let mut out = Vec::new();
+        run(&cli.command, backend, &mut out)?;
+        Ok(String::from_utf8(out).unwrap())
+    }
+
+    #[test]
+    fn lists_casts() -> Result<()> {
+        let backend = FakeBackend::new();
+        assert_eq!(
+            execute(&backend, &["casts"])?,
+            "さとうささら\nすずきつづみ\nタカハシ\n"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn shows_components_of_requested_cast() -> Result<()> {
+        let backend = FakeBackend::new();
+        let output = execute(&backend, &["components", "--cast", "すずきつづみ"])?;
+        assert_eq!(output, "クール\t100\tfake-1-0\n照れ\t0\tfake-1-1\n");
+        Ok(())
+    }
+
+    #[test]
+    fn say_applies_voice_and_emotions() -> Result<()> {
+        let backend = FakeBackend::new();
+        execute(
+            &backend,
+            &[
+                "say",
+                "こんにちは",
+                "--cast",
+                "タカハシ",
+                "--volume",
+                "80",
+                "--tone-scale",
+                "30",
+                "--emotion",
+                "元気=80",
+            ],
+        )?;
+
+        assert_eq!(backend.spoken(), vec!["こんにちは".to_string()]);
+        let cast = backend.current_cast()?;
+        assert_eq!(cast.cast.as_deref(), Some("タカハシ"));
+        assert_eq!(cast.volume.map(|v| v.get()), Some(80));
+        assert_eq!(cast.tone_scale.map(|v| v.get()), Some(30));
+        let genki = backend
+            .component_values()?
+            .into_iter()
+            .find(|component| component.name == "元気")
+            .unwrap();
+        assert_eq!(genki.value, 80);
+        Ok(())
+    }
+
+    #[test]
+    fn unknown_emotion_is_an_error() {
+        let backend = FakeBackend::new();
+        let result = execute(&backend, &["say", "こんにちは", "--emotion", "眠気=10"]);
+        assert!(matches!(result, Err(Error::Cevio(_))));
+        assert!(backend.spoken().is_empty());
+    }
+
+    #[test]
+    fn rejects_invalid_arguments() {
+        for args in [
+            &["say", "a", "--volume", "101"][..],
+            &["say", "a", "--emotion", "元気"],
+            &["say", "a", "--emotion", "元気=120"],
+            &["say", "a", "--emotion", "=10"],
+            &["phonemes", "a", "--format", "xml"],
+        ] {
+            let result = Cli::try_parse_from(std::iter::once("cevio").chain(args.iter().copied()));
+            assert!(result.is_err(), "{args:?} should be rejected");
+        }
+    }
+
+    #[test]
+    fn phonemes_as_csv() -> Result<()> {
+        let backend = FakeBackend::new();
+        let output = execute(&backend, &["phonemes", "あ、", "--format", "csv"])?;
+        let lines: Vec<&str> = output.lines().collect();
+
+        assert_eq!(lines[0], "phoneme,start_time,end_time");
+        assert_eq!(lines.len(), 1 + 4);
+        assert!(lines[1].starts_with("sil,0,"));
+        assert!(lines[3].starts_with("pau,"));
+        Ok(())
+    }
+
+    #[test]
+    fn phonemes_as_json() -> Result<()> {
+        let backend = FakeBackend::new();
+        let output = execute(&backend, &["phonemes", "こんにちは"])?;
+        let value: serde_json::Value = serde_json::from_str(&output)?;
+
+        let array = value.as_array().unwrap();
+        assert_eq!(array.len(), 5 + 2);
+        assert_eq!(array[0]["phoneme"], "sil");
+        assert!(array[1]["start_time"].is_f64());
+        Ok(())
+    }
+
+    #[test]
+    fn duration_honours_speed() -> Result<()> {
+        let backend = FakeBackend::new();
+        let normal: f64 = execute(&backend, &["duration", "こんにちは"])?
+            .trim()
+            .parse()
+            .unwrap();
+        let fast: f64 = execute(&backend, &["duration", "こんにちは", "--speed", "100"])?
+            .trim()
+            .parse()
+            .unwrap();
+        assert!(fast < normal);
+        Ok(())
+    }
+
+    #[test]
+    fn render_concatenates_lines() -> Result<()> {
+        let dir = std::env::temp_dir().join(format!("cevio-cli-render-{}", std::process::id()));
+        std::fs::create_dir_all(&dir)?;
+        let input = dir.join("input.txt");
+        let output = dir.join("output.wav");
+        std::fs::write(&input, "こんにちは\n\nさようなら\n")?;
+
+        let backend = FakeBackend::new();
+        let message = execute(
+            &backend,
+            &[
+                "render",
+                input.to_str().unwrap(),
+                "-o",
+                output.to_str().unwrap(),
+            ],
+        )?;
+
+        let audio = AudioBuffer::from_wav_file(&output)?;
+        let expected = backend.synthesize("こんにちは")?.duration()
+            + backend.synthesize("さようなら")?.duration();
+        assert!((audio.duration() - expected).abs() < 1e-9);
+        assert!(message.ends_with(&format!("{:.3}s\n", audio.duration())));
+
+        std::fs::write(&input, "\n  \n")?;
+        let result = execute(
+            &backend,
+            &[
+                "render",
+                input.to_str().unwrap(),
+                "-o",
+                output.to_str().unwrap(),
+            ],
+        );
+        assert!(matches!(result, Err(Error::EmptyInput(_))));
+
+        std::fs::remove_dir_all(dir)?;
+        Ok(())
+    }
+
+    #[test]
+    fn host_commands() -> Result<()> {
+        let backend = FakeBackend::new();
+        assert_eq!(execute(&backend, &["host", "status"])?, "stopped\n");
+        execute(&backend, &["host", "start", "--no-wait"])?;
+        assert_eq!(execute(&backend, &["host", "status"])?, "started\n");
+        assert_eq!(
+            execute(&backend, &["host", "version"])?,
+            "host: 9.0.0.0\ninterface: 1.0.0.0\n"
+        );
+        execute(&backend, &["host", "close", "--force"])?;
+        assert_eq!(execute(&backend, &["host", "status"])?, "stopped\n");
+        Ok(())
+    }
+}
@@ -0,0 +1,22 @@
+//! エラー処理関連の型定義
+
+use std::path::PathBuf;
+
+use cevio_ai::CevioAIError;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error(transparent)]
+    Cevio(#[from] CevioAIError),
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("JSON error: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("Input has no text: {0}")]
+    EmptyInput(PathBuf),
+    #[error("Playback failed")]
+    SpeakFailed,
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
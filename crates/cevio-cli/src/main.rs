@@ -0,0 +1,40 @@
+//! # cevio
+//!
+//! CeVIO AIをコマンドラインから操作するためのツールです。
+//!
+//! ```text
+//! cevio casts
+//! cevio components --cast さとうささら
+//! cevio say "こんにちは" --cast さとうささら --volume 80 --emotion 元気=80
+//! cevio render input.txt -o output.wav
+//! cevio phonemes "はじめまして" --format csv
+//! cevio duration "こんにちは"
+//! cevio host start|close|status|version
+//! ```
+
+mod cli;
+mod commands;
+mod error;
+
+use std::process::ExitCode;
+
+use cevio_ai::CevioAI;
+use clap::Parser;
+
+use crate::cli::Cli;
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+
+    let result = CevioAI::new()
+        .map_err(error::Error::from)
+        .and_then(|cevio| commands::run(&cli.command, &cevio, &mut std::io::stdout().lock()));
+
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            eprintln!("error: {error}");
+            ExitCode::FAILURE
+        }
+    }
+}
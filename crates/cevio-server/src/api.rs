@@ -0,0 +1,248 @@
+//! REST API
+//!
+//! | メソッド | パス | 内容 |
+//! |---|---|---|
+//! | `GET` | `/casts` | 利用可能なキャスト名の配列 |
+//! | `GET` | `/casts/{name}/components` | キャストの感情パラメータの配列 |
+//! | `POST` | `/synthesize` | 合成した音声（`audio/wav`または`audio/ogg`） |
+//! | `POST` | `/phonemes` | 音素データの配列 |
+//! | `POST` | `/duration` | `{"duration": 秒}` |
+//! | `POST` | `/speak` | 再生終了後に`{"succeeded": bool}` |
+//!
+//! `POST`のリクエストボディは次の形式のJSONです。
+//!
+//! ```json
+//! {
+//!   "text": "こんにちは",
+//!   "cast": "さとうささら",
+//!   "params": { "volume": 80, "speed": 50, "tone": 50, "tone_scale": 50, "alpha": 50 },
+//!   "emotions": { "元気": 80 },
+//!   "format": "wav"
+//! }
+//! ```
+//!
+//! `text`以外は省略できます。省略した項目はCeVIO AIの現在の設定が使用されます。
+//! `format`は`/synthesize`でのみ使用され、`"wav"`（既定）または`"opus"`を指定できます。
+
+use std::collections::BTreeMap;
+
+use axum::{
+    extract::{Path, State},
+    http::header,
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Json, Router,
+};
+use cevio_ai::{
+    Alpha, AudioBuffer, Backend, Cast, ComponentValue, PhonemeData, Speed, Tone, ToneScale, Volume,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    error::{Error, Result},
+    queue::Queue,
+};
+
+/// 合成対象のセリフと音声設定
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct VoiceRequest {
+    /// セリフ
+    pub text: String,
+
+    /// キャスト名
+    #[serde(default)]
+    pub cast: Option<String>,
+
+    /// 音声パラメータ
+    #[serde(default)]
+    pub params: Params,
+
+    /// 感情パラメータ（名前と値）
+    #[serde(default)]
+    pub emotions: BTreeMap<String, u8>,
+
+    /// 出力形式（`/synthesize`のみ）
+    #[serde(default)]
+    pub format: AudioFormat,
+}
+
+/// 音声パラメータ
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+pub struct Params {
+    /// 音の大きさ（0～100）
+    pub volume: Option<Volume>,
+
+    /// 話す速さ（0～100）
+    pub speed: Option<Speed>,
+
+    /// 音の高さ（0～100）
+    pub tone: Option<Tone>,
+
+    /// 抑揚（0～100）
+    pub tone_scale: Option<ToneScale>,
+
+    /// 声質（0～100）
+    pub alpha: Option<Alpha>,
+}
+
+/// 音声の出力形式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AudioFormat {
+    /// 16bitリニアPCMのWAV
+    #[default]
+    Wav,
+    /// Ogg Opus（`opus`フィーチャが必要）
+    Opus,
+}
+
+impl AudioFormat {
+    /// このビルドで出力できる形式かどうかを確認します。
+    pub(crate) fn ensure_supported(self) -> Result<()> {
+        if self == Self::Opus && !cfg!(feature = "opus") {
+            return Err(Error::UnsupportedFormat("opus".to_string()));
+        }
+        Ok(())
+    }
+}
+
+impl VoiceRequest {
+    /// キャスト設定に変換します。
+    #[must_use]
+    pub fn to_cast(&self) -> Cast {
+        Cast {
+            cast: self.cast.clone(),
+            volume: self.params.volume,
+            speed: self.params.speed,
+            tone: self.params.tone,
+            tone_scale: self.params.tone_scale,
+            alpha: self.params.alpha,
+        }
+    }
+
+    /// キャスト設定と感情パラメータをバックエンドに適用します。
+    pub fn apply(&self, backend: &dyn Backend) -> cevio_ai::Result<()> {
+        backend.apply_cast(&self.to_cast())?;
+        let emotions: Vec<(String, u8)> = self
+            .emotions
+            .iter()
+            .map(|(name, value)| (name.clone(), *value))
+            .collect();
+        backend.apply_components(&emotions)
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct DurationResponse {
+    duration: f64,
+}
+
+#[derive(Debug, Serialize)]
+struct SpeakResponse {
+    succeeded: bool,
+}
+
+/// REST APIのルーターを作成します。
+pub fn router(queue: Queue) -> Router {
+    Router::new()
+        .route("/casts", get(casts))
+        .route("/casts/{name}/components", get(components))
+        .route("/synthesize", post(synthesize))
+        .route("/phonemes", post(phonemes))
+        .route("/duration", post(duration))
+        .route("/speak", post(speak))
+        .with_state(queue)
+}
+
+async fn casts(State(queue): State<Queue>) -> Result<Json<Vec<String>>> {
+    Ok(Json(queue.run(|backend| backend.available_casts()).await?))
+}
+
+async fn components(
+    State(queue): State<Queue>,
+    Path(name): Path<String>,
+) -> Result<Json<Vec<ComponentValue>>> {
+    let components = queue
+        .run(move |backend| {
+            backend.apply_cast(&Cast {
+                cast: Some(name),
+                ..Default::default()
+            })?;
+            backend.component_values()
+        })
+        .await?;
+    Ok(Json(components))
+}
+
+async fn synthesize(
+    State(queue): State<Queue>,
+    Json(request): Json<VoiceRequest>,
+) -> Result<Response> {
+    let format = request.format;
+    format.ensure_supported()?;
+
+    let audio = queue
+        .run(move |backend| {
+            request.apply(backend)?;
+            backend.synthesize(&request.text)
+        })
+        .await?;
+
+    encode_audio(&audio, format)
+}
+
+/// 音声を指定された形式のレスポンスに変換します。
+pub(crate) fn encode_audio(audio: &AudioBuffer, format: AudioFormat) -> Result<Response> {
+    match format {
+        AudioFormat::Wav => {
+            Ok(([(header::CONTENT_TYPE, "audio/wav")], audio.to_wav_bytes()).into_response())
+        }
+        #[cfg(feature = "opus")]
+        AudioFormat::Opus => Ok((
+            [(header::CONTENT_TYPE, "audio/ogg; codecs=opus")],
+            crate::opus::encode_ogg(audio)?,
+        )
+            .into_response()),
+        #[cfg(not(feature = "opus"))]
+        AudioFormat::Opus => Err(Error::UnsupportedFormat("opus".to_string())),
+    }
+}
+
+async fn phonemes(
+    State(queue): State<Queue>,
+    Json(request): Json<VoiceRequest>,
+) -> Result<Json<Vec<PhonemeData>>> {
+    let phonemes = queue
+        .run(move |backend| {
+            request.apply(backend)?;
+            backend.phonemes(&request.text)
+        })
+        .await?;
+    Ok(Json(phonemes))
+}
+
+async fn duration(
+    State(queue): State<Queue>,
+    Json(request): Json<VoiceRequest>,
+) -> Result<Json<DurationResponse>> {
+    let duration = queue
+        .run(move |backend| {
+            request.apply(backend)?;
+            backend.text_duration(&request.text)
+        })
+        .await?;
+    Ok(Json(DurationResponse { duration }))
+}
+
+async fn speak(
+    State(queue): State<Queue>,
+    Json(request): Json<VoiceRequest>,
+) -> Result<Json<SpeakResponse>> {
+    let succeeded = queue
+        .run(move |backend| {
+            request.apply(backend)?;
+            backend.speak_and_wait(&request.text)
+        })
+        .await?;
+    Ok(Json(SpeakResponse { succeeded }))
+}
@@ -0,0 +1,49 @@
+//! エラー処理関連の型定義
+//!
+//! すべてのエラーは`{"error": "..."}`形式のJSONとして返されます。
+
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use cevio_ai::CevioAIError;
+use serde_json::json;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error(transparent)]
+    Cevio(#[from] CevioAIError),
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Request queue is closed")]
+    QueueClosed,
+    #[error("Unsupported audio format: {0}")]
+    UnsupportedFormat(String),
+    #[error("Audio encoding failed: {0}")]
+    Encode(String),
+}
+
+impl Error {
+    /// エラーに対応するHTTPステータスコードを取得します。
+    #[must_use]
+    pub const fn status(&self) -> StatusCode {
+        match self {
+            Self::Cevio(CevioAIError::InvalidParameter(_) | CevioAIError::UnknownCast { .. }) => {
+                StatusCode::BAD_REQUEST
+            }
+            Self::UnsupportedFormat(_) => StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            Self::QueueClosed => StatusCode::SERVICE_UNAVAILABLE,
+            _ => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+impl IntoResponse for Error {
+    fn into_response(self) -> Response {
+        (self.status(), Json(json!({ "error": self.to_string() }))).into_response()
+    }
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
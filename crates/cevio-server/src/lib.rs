@@ -0,0 +1,34 @@
+//! # cevio-server
+//!
+//! CeVIO AIをローカルのHTTPサーバーとして公開するためのライブラリです。
+//!
+//! すべてのリクエストは`Queue`を通じて1つのバックエンドで順番に処理されるため、
+//! 異なるキャスト・パラメータのリクエストが同時に届いても設定が混ざることはありません。
+//!
+//! `router`の独自APIのほかに、VOICEVOX ENGINE互換のAPIを`voicevox::router`で提供します。
+//! また、`ws::router`は音声と音素イベントをWebSocketでストリーミングします。
+//! これらのルーターはパスが重ならないため、`Router::merge`で同じサーバーに同居できます。
+//!
+//! ```rust,no_run
+//! use cevio_ai::CevioAI;
+//! use cevio_server::{router, Queue};
+//!
+//! # async fn run() -> Result<(), Box<dyn std::error::Error>> {
+//! let queue = Queue::spawn(CevioAI::new)?;
+//! let listener = tokio::net::TcpListener::bind("127.0.0.1:50080").await?;
+//! axum::serve(listener, router(queue)).await?;
+//! # Ok(())
+//! # }
+//! ```
+
+mod api;
+mod error;
+#[cfg(feature = "opus")]
+pub mod opus;
+mod queue;
+pub mod voicevox;
+pub mod ws;
+
+pub use api::*;
+pub use error::*;
+pub use queue::*;
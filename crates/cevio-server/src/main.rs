@@ -0,0 +1,60 @@
+//! # cevio-server
+//!
+//! CeVIO AIをREST APIとして公開するHTTPサーバーです。
+//! VOICEVOX ENGINE互換のAPIとWebSocketストリーミング（`/stream`）も同じアドレスで提供します。
+//!
+//! ```text
+//! cevio-server --bind 127.0.0.1:50080 --start-host
+//! ```
+
+use std::net::SocketAddr;
+use std::process::ExitCode;
+
+use cevio_ai::{CevioAI, CevioAIConfig};
+use cevio_server::{router, voicevox, ws, Queue};
+use clap::Parser;
+
+/// CeVIO AI HTTPサーバー
+#[derive(Debug, Parser)]
+#[command(name = "cevio-server", version, about)]
+struct Args {
+    /// 待ち受けるアドレス
+    #[arg(long, default_value = "127.0.0.1:50080")]
+    bind: SocketAddr,
+
+    /// 起動時にCeVIO AIを起動します
+    #[arg(long)]
+    start_host: bool,
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let args = Args::parse();
+
+    match serve(args).await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            eprintln!("error: {error}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+async fn serve(args: Args) -> Result<(), Box<dyn std::error::Error>> {
+    let start_host = args.start_host;
+    let queue = Queue::spawn(move || {
+        CevioAI::with_config(CevioAIConfig {
+            start_host,
+            ..Default::default()
+        })
+    })?;
+
+    let listener = tokio::net::TcpListener::bind(args.bind).await?;
+    eprintln!("listening on http://{}", listener.local_addr()?);
+    let app = router(queue.clone())
+        .merge(voicevox::router(queue.clone()))
+        .merge(ws::router(queue));
+    axum::serve(listener, app).await?;
+
+    Ok(())
+}
@@ -0,0 +1,152 @@
+//! Opusエンコード
+//!
+//! `opus`フィーチャを有効にした場合のみ利用できます。
+//! 音声は20ms（48kHzで960サンプル）単位のパケットにエンコードされ、
+//! 必要に応じてOgg Opus（RFC 7845）形式にまとめられます。
+
+use std::borrow::Cow;
+
+use ::opus::{Application, Channels, Encoder};
+use cevio_ai::AudioBuffer;
+use ogg::writing::{PacketWriteEndInfo, PacketWriter};
+
+use crate::error::{Error, Result};
+
+/// Opusがサポートするサンプリングレート（Hz）
+const SAMPLE_RATE: u32 = 48_000;
+
+/// 1パケットあたりのフレーム数（20ms）
+pub const FRAME_SIZE: usize = 960;
+
+/// 1パケットの最大サイズ（バイト）
+const MAX_PACKET_SIZE: usize = 4000;
+
+/// Oggストリームのシリアル番号
+const SERIAL: u32 = 0x4345_5649;
+
+/// エンコード結果
+#[derive(Debug, Clone)]
+pub struct OpusPackets {
+    /// 20msごとのOpusパケット
+    pub packets: Vec<Vec<u8>>,
+
+    /// デコーダーが先頭で読み捨てるべきサンプル数
+    pub pre_skip: u16,
+
+    /// 元の音声のフレーム数
+    pub frames: usize,
+
+    /// チャンネル数
+    pub channels: u16,
+}
+
+/// 音声を20msごとのOpusパケットにエンコードします。
+///
+/// 最後のパケットに満たない部分は無音で埋められます。
+///
+/// # Errors
+///
+/// 48kHz以外、または1・2チャンネル以外の音声の場合は `Error::UnsupportedFormat` を、
+/// エンコードに失敗した場合は `Error::Encode` を返します。
+pub fn encode_packets(audio: &AudioBuffer) -> Result<OpusPackets> {
+    if audio.sample_rate() != SAMPLE_RATE {
+        return Err(Error::UnsupportedFormat(format!(
+            "opus requires {SAMPLE_RATE}Hz audio, got {}Hz",
+            audio.sample_rate()
+        )));
+    }
+    let opus_channels = match audio.channels() {
+        1 => Channels::Mono,
+        2 => Channels::Stereo,
+        channels => {
+            return Err(Error::UnsupportedFormat(format!(
+                "opus requires mono or stereo audio, got {channels} channels"
+            )))
+        }
+    };
+
+    let channels = usize::from(audio.channels());
+    let mut encoder =
+        Encoder::new(SAMPLE_RATE, opus_channels, Application::Voip).map_err(encode)?;
+    let pre_skip = u16::try_from(encoder.get_lookahead().map_err(encode)?).unwrap_or(0);
+
+    let mut packets = Vec::new();
+    let mut frame = vec![0i16; FRAME_SIZE * channels];
+    for chunk in audio.samples().chunks(FRAME_SIZE * channels) {
+        frame[..chunk.len()].copy_from_slice(chunk);
+        frame[chunk.len()..].fill(0);
+        packets.push(
+            encoder
+                .encode_vec(&frame, MAX_PACKET_SIZE)
+                .map_err(encode)?,
+        );
+    }
+    if packets.is_empty() {
+        // 空の音声でもストリームを終端できるよう、無音のパケットを1つ出力する
+        frame.fill(0);
+        packets.push(
+            encoder
+                .encode_vec(&frame, MAX_PACKET_SIZE)
+                .map_err(encode)?,
+        );
+    }
+
+    Ok(OpusPackets {
+        packets,
+        pre_skip,
+        frames: audio.frames(),
+        channels: audio.channels(),
+    })
+}
+
+/// 音声をOgg Opus形式にエンコードします。
+///
+/// # Errors
+///
+/// `encode_packets` と同じ条件でエラーを返します。
+pub fn encode_ogg(audio: &AudioBuffer) -> Result<Vec<u8>> {
+    let encoded = encode_packets(audio)?;
+    let mut writer = PacketWriter::new(Vec::new());
+
+    let mut head = Vec::with_capacity(19);
+    head.extend_from_slice(b"OpusHead");
+    head.push(1);
+    head.push(encoded.channels as u8);
+    head.extend_from_slice(&encoded.pre_skip.to_le_bytes());
+    head.extend_from_slice(&SAMPLE_RATE.to_le_bytes());
+    head.extend_from_slice(&0i16.to_le_bytes());
+    head.push(0);
+    writer.write_packet(head, SERIAL, PacketWriteEndInfo::EndPage, 0)?;
+
+    let vendor = concat!("cevio-server ", env!("CARGO_PKG_VERSION"));
+    let mut tags = Vec::new();
+    tags.extend_from_slice(b"OpusTags");
+    tags.extend_from_slice(&(vendor.len() as u32).to_le_bytes());
+    tags.extend_from_slice(vendor.as_bytes());
+    tags.extend_from_slice(&0u32.to_le_bytes());
+    writer.write_packet(tags, SERIAL, PacketWriteEndInfo::EndPage, 0)?;
+
+    let pre_skip = u64::from(encoded.pre_skip);
+    let last = encoded.packets.len().saturating_sub(1);
+    for (index, packet) in encoded.packets.into_iter().enumerate() {
+        let (info, granule) = if index == last {
+            // 最終パケットのグラニュール位置で末尾の埋め草を切り捨てる
+            (
+                PacketWriteEndInfo::EndStream,
+                pre_skip + encoded.frames as u64,
+            )
+        } else {
+            (
+                PacketWriteEndInfo::NormalPacket,
+                pre_skip + ((index + 1) * FRAME_SIZE) as u64,
+            )
+        };
+        writer.write_packet(Cow::Owned(packet), SERIAL, info, granule)?;
+    }
+
+    Ok(writer.into_inner())
+}
+
+fn encode(error: ::opus::Error) -> Error {
+    Error::Encode(error.to_string())
+}
@@ -0,0 +1,100 @@
+//! リクエストキュー
+//!
+//! CeVIO AIは1つのキャスト・パラメータ状態を共有するため、
+//! 複数のリクエストを同時に処理すると設定が混ざってしまいます。
+//! `Queue`は専用のスレッドでバックエンドを所有し、投入されたジョブを1つずつ順番に実行します。
+//! 操作のタイムアウトでバックエンドが使用不能になった場合は、次のジョブの前に作り直します。
+//! 結果を待つ側が破棄されたジョブ（中断されたセリフの合成など）は実行せずに読み飛ばします。
+
+use std::sync::mpsc;
+use std::thread;
+
+use cevio_ai::Backend;
+use tokio::sync::oneshot;
+
+use crate::error::{Error, Result};
+
+type Job = Box<dyn FnOnce(&dyn Backend) + Send>;
+
+/// バックエンドへのアクセスを直列化するキュー
+///
+/// `Clone`で複製したハンドルはすべて同じワーカースレッドを共有します。
+/// すべてのハンドルが破棄されるとワーカースレッドは終了します。
+#[derive(Debug, Clone)]
+pub struct Queue {
+    sender: mpsc::Sender<Job>,
+}
+
+impl Queue {
+    /// ワーカースレッドを起動します。
+    ///
+    /// バックエンドはワーカースレッド上で`factory`により作成されます。
+    ///
+    /// # Errors
+    ///
+    /// バックエンドの作成に失敗した場合は `Error::Cevio` を返します。
+    pub fn spawn<B, F>(factory: F) -> Result<Self>
+    where
+        B: Backend + 'static,
+        F: FnOnce() -> cevio_ai::Result<B> + Send + 'static,
+    {
+        let (sender, receiver) = mpsc::channel::<Job>();
+        let (ready_sender, ready_receiver) = mpsc::sync_channel(1);
+
+        thread::Builder::new()
+            .name("cevio-queue".to_string())
+            .spawn(move || {
+                let mut backend = match factory() {
+                    Ok(backend) => {
+                        let _ = ready_sender.send(Ok(()));
+                        backend
+                    }
+                    Err(error) => {
+                        let _ = ready_sender.send(Err(error));
+                        return;
+                    }
+                };
+
+                for job in receiver {
+                    // 作り直せなかった場合は使用不能なまま実行し、ジョブにエラーを返させる
+                    if backend.is_poisoned() {
+                        if let Ok(rebuilt) = backend.rebuild() {
+                            backend = rebuilt;
+                        }
+                    }
+                    job(&backend);
+                }
+            })?;
+
+        ready_receiver.recv().map_err(|_| Error::QueueClosed)??;
+
+        Ok(Self { sender })
+    }
+
+    /// ジョブを投入し、完了を待ちます。
+    ///
+    /// ジョブは先に投入されたジョブがすべて完了した後に、ワーカースレッド上で実行されます。
+    /// 実行される前に返された`Future`が破棄された場合、ジョブは実行されません。
+    ///
+    /// # Errors
+    ///
+    /// ジョブが失敗した場合は `Error::Cevio` を、
+    /// ワーカースレッドが終了している場合は `Error::QueueClosed` を返します。
+    pub async fn run<T, F>(&self, job: F) -> Result<T>
+    where
+        T: Send + 'static,
+        F: FnOnce(&dyn Backend) -> cevio_ai::Result<T> + Send + 'static,
+    {
+        let (sender, receiver) = oneshot::channel();
+
+        self.sender
+            .send(Box::new(move |backend| {
+                if !sender.is_closed() {
+                    let _ = sender.send(job(backend));
+                }
+            }))
+            .map_err(|_| Error::QueueClosed)?;
+
+        Ok(receiver.await.map_err(|_| Error::QueueClosed)??)
+    }
+}
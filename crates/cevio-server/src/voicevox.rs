@@ -0,0 +1,609 @@
+//! VOICEVOX ENGINE互換API
+//!
+//! VOICEVOX ENGINEのAPIを話すクライアント（読み上げソフトや動画編集ソフトなど）から、
+//! CeVIO AIのキャストをそのまま利用するための互換レイヤーです。
+//!
+//! | メソッド | パス | 内容 |
+//! |---|---|---|
+//! | `GET` | `/version` | エンジンのバージョン |
+//! | `GET` | `/speakers` | キャストと感情の一覧 |
+//! | `POST` | `/initialize_speaker` | 何もしません |
+//! | `GET` | `/is_initialized_speaker` | 常に`true` |
+//! | `POST` | `/audio_query` | 音素データと現在のパラメータから作成した`AudioQuery` |
+//! | `POST` | `/synthesis` | `AudioQuery`から合成したWAV |
+//!
+//! ## 話者とスタイル
+//!
+//! キャストが話者に、キャストの感情パラメータがスタイルに対応します。
+//! スタイルIDは`キャストの番号 × 100 + 感情の番号`です（番号は`available_casts()`と
+//! `components()`の順序で、0から数えます）。スタイルを指定して合成すると、
+//! その感情を100、それ以外の感情を0に設定します。
+//!
+//! ## パラメータの対応
+//!
+//! | `AudioQuery` | CeVIO AI | 対応 |
+//! |---|---|---|
+//! | `speedScale`（0.5～2.0） | `Speed` | `50 + 50 × log2(speedScale)` |
+//! | `pitchScale`（-0.15～0.15） | `Tone` | `50 + pitchScale / 0.15 × 50` |
+//! | `intonationScale`（0.0～2.0） | `ToneScale` | `intonationScale × 50` |
+//! | `volumeScale`（0.0～2.0） | `Volume` | `volumeScale × 50` |
+//!
+//! 範囲外の値は0～100に丸められます。
+//!
+//! `/synthesis`は`accent_phrases`のモーラからカタカナのセリフを組み立てて合成するため、
+//! モーラの`text`を編集すると読みに反映されます。一方、音素の長さや`pitch`、
+//! `prePhonemeLength`・`postPhonemeLength`は反映されません。
+//! `outputSamplingRate`と`outputStereo`は出力時に変換されます。
+//! `outputSamplingRate`は8000～192000Hzの範囲で指定してください（範囲外は`400 Bad Request`）。
+
+use axum::{
+    extract::{Query, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Json, Router,
+};
+use cevio_ai::{
+    AudioBuffer, Backend, Cast, CevioAIError, MoraSegmenter, Phoneme, PhonemeData, Speed, Tone,
+    ToneScale, Volume,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{error::Result, queue::Queue};
+
+/// 互換対象のVOICEVOX ENGINEのバージョン
+pub const VOICEVOX_VERSION: &str = "0.14.0";
+
+/// 1つのキャストに割り当てるスタイルIDの数
+const STYLES_PER_SPEAKER: u32 = 100;
+
+/// `outputSamplingRate`に指定できる範囲（Hz）
+const OUTPUT_SAMPLING_RATES: std::ops::RangeInclusive<u32> = 8_000..=192_000;
+
+/// 有声モーラの`pitch`（CeVIO AIは音高を公開しないため一定値）
+const VOICED_PITCH: f64 = 5.8;
+
+/// 話者
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Speaker {
+    pub name: String,
+    pub speaker_uuid: String,
+    pub styles: Vec<SpeakerStyle>,
+    pub version: String,
+}
+
+/// 話者のスタイル
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SpeakerStyle {
+    pub name: String,
+    pub id: u32,
+    #[serde(rename = "type")]
+    pub style_type: String,
+}
+
+/// モーラ
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Mora {
+    pub text: String,
+    pub consonant: Option<String>,
+    pub consonant_length: Option<f64>,
+    pub vowel: String,
+    pub vowel_length: f64,
+    pub pitch: f64,
+}
+
+/// アクセント句
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AccentPhrase {
+    pub moras: Vec<Mora>,
+    pub accent: usize,
+    pub pause_mora: Option<Mora>,
+    #[serde(default)]
+    pub is_interrogative: bool,
+}
+
+/// 音声合成用のクエリ
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AudioQuery {
+    pub accent_phrases: Vec<AccentPhrase>,
+    #[serde(rename = "speedScale")]
+    pub speed_scale: f64,
+    #[serde(rename = "pitchScale")]
+    pub pitch_scale: f64,
+    #[serde(rename = "intonationScale")]
+    pub intonation_scale: f64,
+    #[serde(rename = "volumeScale")]
+    pub volume_scale: f64,
+    #[serde(rename = "prePhonemeLength")]
+    pub pre_phoneme_length: f64,
+    #[serde(rename = "postPhonemeLength")]
+    pub post_phoneme_length: f64,
+    #[serde(rename = "outputSamplingRate")]
+    pub output_sampling_rate: u32,
+    #[serde(rename = "outputStereo")]
+    pub output_stereo: bool,
+    #[serde(default)]
+    pub kana: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AudioQueryParams {
+    text: String,
+    speaker: u32,
+}
+
+#[derive(Debug, Deserialize)]
+struct SpeakerParams {
+    speaker: u32,
+}
+
+/// VOICEVOX ENGINE互換APIのルーターを作成します。
+pub fn router(queue: Queue) -> Router {
+    Router::new()
+        .route("/version", get(version))
+        .route("/speakers", get(speakers))
+        .route("/initialize_speaker", post(initialize_speaker))
+        .route("/is_initialized_speaker", get(is_initialized_speaker))
+        .route("/audio_query", post(audio_query))
+        .route("/synthesis", post(synthesis))
+        .with_state(queue)
+}
+
+async fn version() -> Json<&'static str> {
+    Json(VOICEVOX_VERSION)
+}
+
+async fn speakers(State(queue): State<Queue>) -> Result<Json<Vec<Speaker>>> {
+    let speakers = queue
+        .run(|backend| {
+            let casts = backend.available_casts()?;
+            let mut speakers = Vec::with_capacity(casts.len());
+            for (cast_index, cast) in (0u32..).zip(casts) {
+                backend.apply_cast(&Cast {
+                    cast: Some(cast.clone()),
+                    ..Default::default()
+                })?;
+                let styles = (0u32..)
+                    .zip(backend.component_values()?)
+                    .map(|(index, component)| SpeakerStyle {
+                        name: component.name,
+                        id: cast_index * STYLES_PER_SPEAKER + index,
+                        style_type: "talk".to_string(),
+                    })
+                    .collect();
+                speakers.push(Speaker {
+                    speaker_uuid: speaker_uuid(&cast),
+                    name: cast,
+                    styles,
+                    version: VOICEVOX_VERSION.to_string(),
+                });
+            }
+            Ok(speakers)
+        })
+        .await?;
+    Ok(Json(speakers))
+}
+
+async fn initialize_speaker(Query(_): Query<SpeakerParams>) -> StatusCode {
+    StatusCode::NO_CONTENT
+}
+
+async fn is_initialized_speaker(Query(_): Query<SpeakerParams>) -> Json<bool> {
+    Json(true)
+}
+
+async fn audio_query(
+    State(queue): State<Queue>,
+    Query(params): Query<AudioQueryParams>,
+) -> Result<Json<AudioQuery>> {
+    let query = queue
+        .run(move |backend| {
+            select_style(backend, params.speaker)?;
+            let phonemes = backend.phonemes(&params.text)?;
+            let cast = backend.current_cast()?;
+            Ok(build_audio_query(&phonemes, &cast))
+        })
+        .await?;
+    Ok(Json(query))
+}
+
+async fn synthesis(
+    State(queue): State<Queue>,
+    Query(params): Query<SpeakerParams>,
+    Json(query): Json<AudioQuery>,
+) -> Result<Response> {
+    check_sampling_rate(query.output_sampling_rate)?;
+    let text = query_text(&query);
+    let cast = query_cast(&query);
+    let audio = queue
+        .run(move |backend| {
+            select_style(backend, params.speaker)?;
+            backend.apply_cast(&cast)?;
+            backend.synthesize(&text)
+        })
+        .await?;
+
+    let audio = convert(&audio, query.output_sampling_rate, query.output_stereo)?;
+    Ok(([(header::CONTENT_TYPE, "audio/wav")], audio.to_wav_bytes()).into_response())
+}
+
+/// スタイルIDに対応するキャストと感情を選択します。
+fn select_style(backend: &dyn Backend, style: u32) -> cevio_ai::Result<()> {
+    let unknown = || CevioAIError::InvalidParameter(format!("Unknown speaker: {style}"));
+
+    let cast = backend
+        .available_casts()?
+        .into_iter()
+        .nth((style / STYLES_PER_SPEAKER) as usize)
+        .ok_or_else(unknown)?;
+    backend.apply_cast(&Cast {
+        cast: Some(cast),
+        ..Default::default()
+    })?;
+
+    let components = backend.component_values()?;
+    let selected = (style % STYLES_PER_SPEAKER) as usize;
+    if selected >= components.len() {
+        return Err(unknown());
+    }
+    let values: Vec<(String, u8)> = components
+        .into_iter()
+        .enumerate()
+        .map(|(index, component)| (component.name, if index == selected { 100 } else { 0 }))
+        .collect();
+    backend.apply_components(&values)
+}
+
+/// キャスト名から決定的な話者UUIDを作成します。
+fn speaker_uuid(cast: &str) -> String {
+    // FNV-1a（128bit）
+    let mut hash: u128 = 0x6c62_272e_07bb_0142_62b8_2175_6295_c58d;
+    for byte in cast.bytes() {
+        hash ^= u128::from(byte);
+        hash = hash.wrapping_mul(0x0000_0000_0100_0000_0000_0000_0000_013b);
+    }
+    let hex = format!("{hash:032x}");
+    format!(
+        "{}-{}-{}-{}-{}",
+        &hex[0..8],
+        &hex[8..12],
+        &hex[12..16],
+        &hex[16..20],
+        &hex[20..32]
+    )
+}
+
+fn speed_scale(speed: Speed) -> f64 {
+    2f64.powf((f64::from(speed.get()) - 50.0) / 50.0)
+}
+
+fn pitch_scale(tone: Tone) -> f64 {
+    (f64::from(tone.get()) - 50.0) / 50.0 * 0.15
+}
+
+fn intonation_scale(tone_scale: ToneScale) -> f64 {
+    f64::from(tone_scale.get()) / 50.0
+}
+
+fn volume_scale(volume: Volume) -> f64 {
+    f64::from(volume.get()) / 50.0
+}
+
+fn to_parameter(value: f64) -> u8 {
+    if value.is_nan() {
+        50
+    } else {
+        value.round().clamp(0.0, 100.0) as u8
+    }
+}
+
+/// `AudioQuery`の各スケールをキャスト設定に変換します。
+fn query_cast(query: &AudioQuery) -> Cast {
+    Cast {
+        cast: None,
+        volume: Volume::new(to_parameter(query.volume_scale * 50.0)),
+        speed: Speed::new(to_parameter(50.0 + 50.0 * query.speed_scale.log2())),
+        tone: Tone::new(to_parameter(50.0 + query.pitch_scale / 0.15 * 50.0)),
+        tone_scale: ToneScale::new(to_parameter(query.intonation_scale * 50.0)),
+        alpha: None,
+    }
+}
+
+/// 音素データと現在のパラメータから`AudioQuery`を作成します。
+fn build_audio_query(phonemes: &[PhonemeData], cast: &Cast) -> AudioQuery {
+    let mut phonemes = phonemes;
+    let mut pre_phoneme_length = 0.0;
+    let mut post_phoneme_length = 0.0;
+    if let Some((first, rest)) = phonemes.split_first() {
+        if first.phoneme() == "sil" {
+            pre_phoneme_length = duration(first);
+            phonemes = rest;
+        }
+    }
+    if let Some((last, rest)) = phonemes.split_last() {
+        if last.phoneme() == "sil" {
+            post_phoneme_length = duration(last);
+            phonemes = rest;
+        }
+    }
+
+    let accent_phrases = accent_phrases(phonemes);
+    let kana = accent_phrases
+        .iter()
+        .map(|phrase| {
+            let mut kana: String = phrase.moras.iter().map(|mora| mora.text.as_str()).collect();
+            if phrase.pause_mora.is_some() {
+                kana.push('、');
+            }
+            kana
+        })
+        .collect::<Vec<_>>()
+        .join("/");
+
+    AudioQuery {
+        accent_phrases,
+        speed_scale: speed_scale(cast.speed.unwrap_or_default()),
+        pitch_scale: pitch_scale(cast.tone.unwrap_or_default()),
+        intonation_scale: intonation_scale(cast.tone_scale.unwrap_or_default()),
+        volume_scale: volume_scale(cast.volume.unwrap_or_default()),
+        pre_phoneme_length,
+        post_phoneme_length,
+        output_sampling_rate: 48_000,
+        output_stereo: false,
+        kana: Some(kana),
+    }
+}
+
+fn duration(phoneme: &PhonemeData) -> f64 {
+    phoneme.end_time() - phoneme.start_time()
+}
+
+/// 音素をモーラにまとめ、ポーズでアクセント句に区切ります。
+fn accent_phrases(phonemes: &[PhonemeData]) -> Vec<AccentPhrase> {
+    let mut phrases = Vec::new();
+    let mut moras = Vec::new();
+
+    for mora in MoraSegmenter::new().include_silence(true).segment(phonemes) {
+        if mora.is_silent() {
+            let pause = Mora {
+                text: "、".to_string(),
+                consonant: None,
+                consonant_length: None,
+                vowel: "pau".to_string(),
+                vowel_length: mora.duration(),
+                pitch: 0.0,
+            };
+            if !moras.is_empty() {
+                phrases.push(AccentPhrase {
+                    moras: std::mem::take(&mut moras),
+                    accent: 1,
+                    pause_mora: Some(pause),
+                    is_interrogative: false,
+                });
+            }
+            continue;
+        }
+
+        let (consonants, nucleus): (Vec<_>, Vec<_>) = mora
+            .phonemes
+            .iter()
+            .partition(|phoneme| phoneme.to_phoneme().is_consonant());
+        let Some(vowel) = nucleus.first() else {
+            continue;
+        };
+        let voiced = matches!(vowel.to_phoneme(), Phoneme::Vowel(_) | Phoneme::Nasal);
+        moras.push(Mora {
+            text: mora.kana(),
+            consonant: consonants.first().map(|c| c.phoneme().to_string()),
+            consonant_length: consonants.first().map(|c| duration(c)),
+            vowel: vowel.phoneme().to_string(),
+            vowel_length: duration(vowel),
+            pitch: if voiced { VOICED_PITCH } else { 0.0 },
+        });
+    }
+
+    if !moras.is_empty() {
+        phrases.push(AccentPhrase {
+            moras,
+            accent: 1,
+            pause_mora: None,
+            is_interrogative: false,
+        });
+    }
+
+    phrases
+}
+
+/// `AudioQuery`から合成するセリフを組み立てます。
+fn query_text(query: &AudioQuery) -> String {
+    let mut text = String::new();
+    for phrase in &query.accent_phrases {
+        text.extend(phrase.moras.iter().map(|mora| mora.text.as_str()));
+        if phrase.is_interrogative {
+            text.push('？');
+        }
+        if phrase.pause_mora.is_some() {
+            text.push('、');
+        }
+    }
+    text
+}
+
+/// 出力のサンプリングレートが[`OUTPUT_SAMPLING_RATES`]の範囲内か確認します。
+fn check_sampling_rate(sample_rate: u32) -> cevio_ai::Result<()> {
+    if OUTPUT_SAMPLING_RATES.contains(&sample_rate) {
+        Ok(())
+    } else {
+        Err(CevioAIError::InvalidParameter(format!(
+            "outputSamplingRate must be {}-{}, got {sample_rate}",
+            OUTPUT_SAMPLING_RATES.start(),
+            OUTPUT_SAMPLING_RATES.end()
+        )))
+    }
+}
+
+/// サンプリングレートとチャンネル数を変換します。
+///
+/// サンプリングレートの変換には線形補間を使用します。
+/// `sample_rate`が[`OUTPUT_SAMPLING_RATES`]の範囲外の場合は変換せずにエラーを返します。
+fn convert(audio: &AudioBuffer, sample_rate: u32, stereo: bool) -> Result<AudioBuffer> {
+    check_sampling_rate(sample_rate)?;
+    let channels = usize::from(audio.channels());
+    let mono: Vec<f64> = audio
+        .samples()
+        .chunks_exact(channels)
+        .map(|frame| frame.iter().map(|&s| f64::from(s)).sum::<f64>() / channels as f64)
+        .collect();
+
+    let resampled: Vec<i16> = if sample_rate == audio.sample_rate() || mono.is_empty() {
+        mono.iter().map(|&s| s as i16).collect()
+    } else {
+        let ratio = f64::from(audio.sample_rate()) / f64::from(sample_rate);
+        let frames = (mono.len() as f64 / ratio).round() as usize;
+        (0..frames)
+            .map(|index| {
+                let position = index as f64 * ratio;
+                let left = (position.floor() as usize).min(mono.len() - 1);
+                let right = (left + 1).min(mono.len() - 1);
+                let fraction = position - left as f64;
+                (mono[left] * (1.0 - fraction) + mono[right] * fraction) as i16
+            })
+            .collect()
+    };
+
+    let samples = if stereo {
+        resampled.iter().flat_map(|&s| [s, s]).collect()
+    } else {
+        resampled
+    };
+
+    Ok(AudioBuffer::new(
+        sample_rate,
+        if stereo { 2 } else { 1 },
+        samples,
+    )?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn phoneme(phoneme: &str, start_time: f64, end_time: f64) -> PhonemeData {
+        serde_json::from_value(serde_json::json!({
+            "phoneme": phoneme,
+            "start_time": start_time,
+            "end_time": end_time,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn scales_round_trip() {
+        for value in 0..=100 {
+            let query = AudioQuery {
+                accent_phrases: Vec::new(),
+                speed_scale: speed_scale(Speed::new(value).unwrap()),
+                pitch_scale: pitch_scale(Tone::new(value).unwrap()),
+                intonation_scale: intonation_scale(ToneScale::new(value).unwrap()),
+                volume_scale: volume_scale(Volume::new(value).unwrap()),
+                pre_phoneme_length: 0.0,
+                post_phoneme_length: 0.0,
+                output_sampling_rate: 48_000,
+                output_stereo: false,
+                kana: None,
+            };
+            let cast = query_cast(&query);
+            assert_eq!(cast.speed.map(|v| v.get()), Some(value));
+            assert_eq!(cast.tone.map(|v| v.get()), Some(value));
+            assert_eq!(cast.tone_scale.map(|v| v.get()), Some(value));
+            assert_eq!(cast.volume.map(|v| v.get()), Some(value));
+        }
+
+        assert_eq!(speed_scale(Speed::new(50).unwrap()), 1.0);
+        assert_eq!(pitch_scale(Tone::new(50).unwrap()), 0.0);
+        assert_eq!(to_parameter(f64::NEG_INFINITY), 0);
+        assert_eq!(to_parameter(250.0), 100);
+    }
+
+    #[test]
+    fn groups_phonemes_into_moras_and_phrases() {
+        // 「こんにちは、ってっ」
+        let phonemes = [
+            phoneme("sil", 0.0, 0.1),
+            phoneme("k", 0.1, 0.15),
+            phoneme("o", 0.15, 0.2),
+            phoneme("N", 0.2, 0.3),
+            phoneme("n", 0.3, 0.35),
+            phoneme("i", 0.35, 0.4),
+            phoneme("ch", 0.4, 0.45),
+            phoneme("i", 0.45, 0.5),
+            phoneme("w", 0.5, 0.55),
+            phoneme("a", 0.55, 0.6),
+            phoneme("pau", 0.6, 0.8),
+            phoneme("cl", 0.8, 0.85),
+            phoneme("t", 0.85, 0.9),
+            phoneme("E", 0.9, 0.95),
+            phoneme("sil", 0.95, 1.2),
+        ];
+        let cast = Cast {
+            speed: Some(Speed::new(50).unwrap()),
+            ..Default::default()
+        };
+
+        let query = build_audio_query(&phonemes, &cast);
+
+        assert!((query.pre_phoneme_length - 0.1).abs() < 1e-9);
+        assert!((query.post_phoneme_length - 0.25).abs() < 1e-9);
+        assert_eq!(query.accent_phrases.len(), 2);
+        assert_eq!(query.kana.as_deref(), Some("コンニチワ、/ッテ"));
+
+        let first = &query.accent_phrases[0];
+        assert_eq!(first.moras.len(), 5);
+        assert_eq!(first.moras[0].consonant.as_deref(), Some("k"));
+        assert_eq!(first.moras[1].text, "ン");
+        assert_eq!(first.moras[1].consonant, None);
+        assert_eq!(first.pause_mora.as_ref().unwrap().vowel, "pau");
+
+        let second = &query.accent_phrases[1];
+        assert_eq!(second.moras[0].pitch, 0.0);
+        assert_eq!(second.moras[1].pitch, 0.0);
+        assert_eq!(first.moras[0].pitch, VOICED_PITCH);
+        assert!(second.pause_mora.is_none());
+
+        assert_eq!(query_text(&query), "コンニチワ、ッテ");
+    }
+
+    #[test]
+    fn converts_sample_rate_and_channels() {
+        let audio = AudioBuffer::new(48_000, 1, vec![0, 100, 200, 300]).unwrap();
+
+        let converted = convert(&audio, 24_000, true).unwrap();
+        assert_eq!(converted.sample_rate(), 24_000);
+        assert_eq!(converted.channels(), 2);
+        assert_eq!(converted.samples(), &[0, 0, 200, 200]);
+
+        assert_eq!(convert(&audio, 48_000, false).unwrap(), audio);
+    }
+
+    #[test]
+    fn rejects_unreasonable_sample_rates() {
+        let audio = AudioBuffer::new(48_000, 1, vec![0, 100, 200, 300]).unwrap();
+        for sample_rate in [0, 7_999, 192_001, u32::MAX] {
+            assert!(matches!(
+                convert(&audio, sample_rate, false),
+                Err(crate::error::Error::Cevio(CevioAIError::InvalidParameter(
+                    _
+                )))
+            ));
+        }
+        assert!(convert(&audio, 192_000, false).is_ok());
+    }
+
+    #[test]
+    fn speaker_uuid_is_stable() {
+        let uuid = speaker_uuid("さとうささら");
+        assert_eq!(uuid.len(), 36);
+        assert_eq!(uuid, speaker_uuid("さとうささら"));
+        assert_ne!(uuid, speaker_uuid("すずきつづみ"));
+    }
+}
@@ -0,0 +1,469 @@
+//! WebSocketストリーミング
+//!
+//! `GET /stream`でWebSocketに接続すると、セリフを合成した音声を約20msごとのチャンクで、
+//! 音素のタイミングと口の形をJSONのイベントとして、再生速度に合わせて順に受け取れます。
+//!
+//! ## クライアントからのメッセージ（テキスト）
+//!
+//! `/synthesize`と同じ形式のJSONを送ると合成を開始します。
+//! 合成中または再生中のセリフがある場合は、そのセリフを中断してから開始します。
+//!
+//! ```json
+//! { "text": "こんにちは", "cast": "さとうささら", "emotions": { "元気": 80 }, "format": "opus" }
+//! ```
+//!
+//! `{"type": "cancel"}`を送ると合成中または再生中のセリフを中断します。
+//! 中断されたセリフには`cancelled`が`true`の`end`イベントが送信されます。
+//! 解釈できないメッセージには`error`イベントを返し、合成中または再生中のセリフはそのまま続けます。
+//!
+//! ## サーバーからのメッセージ
+//!
+//! - バイナリ: 音声のチャンク。`format`が`"wav"`の場合は16bitリトルエンディアンのリニアPCM、
+//!   `"opus"`の場合は1つのOpusパケットです。
+//! - テキスト: 次のいずれかのイベント
+//!
+//! ```json
+//! {"type": "start", "utterance": 1, "format": "wav", "sample_rate": 48000, "channels": 1, "duration": 1.2}
+//! {"type": "phoneme", "utterance": 1, "phoneme": "a", "viseme": "a", "start_time": 0.1, "end_time": 0.2}
+//! {"type": "end", "utterance": 1, "cancelled": false}
+//! {"type": "error", "message": "..."}
+//! ```
+//!
+//! `viseme`は`cevio_ai::Viseme`の既定の対応による口の形（`a` `i` `u` `e` `o` `closed` `rest`）です。
+//! 音素イベントは、その音素が始まるチャンクの直前に送信されます。
+
+use std::time::Duration;
+
+use axum::{
+    extract::{
+        ws::{Message, WebSocket},
+        State, WebSocketUpgrade,
+    },
+    response::Response,
+    routing::get,
+    Router,
+};
+use cevio_ai::{AudioBuffer, PhonemeData, Viseme};
+use serde::{Deserialize, Serialize};
+use tokio::{
+    task::JoinHandle,
+    time::{interval, Instant, MissedTickBehavior},
+};
+
+use crate::{
+    api::{AudioFormat, VoiceRequest},
+    error::Result,
+    queue::Queue,
+};
+
+/// チャンクの長さ（秒）
+pub const CHUNK_DURATION: f64 = 0.02;
+
+/// 再生位置より先行して送信する時間（秒）
+///
+/// クライアント側のジッターバッファとして使われます。
+const LEAD_TIME: f64 = 0.2;
+
+/// クライアントから送信されるメッセージ
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum ClientMessage {
+    /// 制御メッセージ
+    Control(Control),
+    /// セリフの合成
+    Speak(VoiceRequest),
+}
+
+/// 制御メッセージ
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Control {
+    /// 再生中のセリフを中断します。
+    Cancel,
+}
+
+/// サーバーから送信されるイベント
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ServerEvent {
+    /// セリフの開始
+    Start {
+        utterance: u64,
+        format: AudioFormat,
+        sample_rate: u32,
+        channels: u16,
+        duration: f64,
+    },
+    /// 音素
+    Phoneme {
+        utterance: u64,
+        phoneme: String,
+        viseme: Viseme,
+        start_time: f64,
+        end_time: f64,
+    },
+    /// セリフの終了
+    End { utterance: u64, cancelled: bool },
+    /// エラー
+    Error { message: String },
+}
+
+/// 送信するメッセージ
+#[derive(Debug, Clone, PartialEq)]
+pub enum Outgoing {
+    /// イベント
+    Event(ServerEvent),
+    /// 音声のチャンク
+    Audio(Vec<u8>),
+}
+
+/// 送信時刻付きのメッセージ
+#[derive(Debug, Clone, PartialEq)]
+pub struct Timed {
+    /// セリフの先頭からの送信時刻（秒）
+    pub at: f64,
+    /// メッセージ
+    pub message: Outgoing,
+}
+
+/// WebSocketストリーミングのルーターを作成します。
+pub fn router(queue: Queue) -> Router {
+    Router::new()
+        .route("/stream", get(upgrade))
+        .with_state(queue)
+}
+
+async fn upgrade(State(queue): State<Queue>, ws: WebSocketUpgrade) -> Response {
+    ws.on_upgrade(move |socket| stream(socket, queue))
+}
+
+/// 合成中のセリフ
+///
+/// 破棄すると合成の完了を待たずに中断します。
+struct Rendering {
+    utterance: u64,
+    task: JoinHandle<Result<Vec<Timed>>>,
+}
+
+impl Drop for Rendering {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+/// 再生中のセリフ
+struct Playback {
+    utterance: u64,
+    messages: std::vec::IntoIter<Timed>,
+    pending: Option<Timed>,
+    started: Instant,
+}
+
+impl Playback {
+    /// 現在の再生位置までに送信すべきメッセージを取り出します。
+    fn due(&mut self) -> Vec<Outgoing> {
+        let position = self.started.elapsed().as_secs_f64() + LEAD_TIME;
+        let mut due = Vec::new();
+        while let Some(timed) = self.pending.take().or_else(|| self.messages.next()) {
+            if timed.at > position {
+                self.pending = Some(timed);
+                break;
+            }
+            due.push(timed.message);
+        }
+        due
+    }
+
+    fn is_finished(&self) -> bool {
+        self.pending.is_none() && self.messages.len() == 0
+    }
+}
+
+async fn stream(mut socket: WebSocket, queue: Queue) {
+    let mut ticker = interval(Duration::from_secs_f64(CHUNK_DURATION));
+    ticker.set_missed_tick_behavior(MissedTickBehavior::Skip);
+
+    let mut utterance = 0;
+    let mut rendering: Option<Rendering> = None;
+    let mut playback: Option<Playback> = None;
+
+    loop {
+        tokio::select! {
+            received = socket.recv() => {
+                let text = match received {
+                    Some(Ok(Message::Text(text))) => text,
+                    Some(Ok(Message::Close(_)) | Err(_)) | None => break,
+                    Some(Ok(_)) => continue,
+                };
+
+                let message = match serde_json::from_str::<ClientMessage>(text.as_str()) {
+                    Ok(message) => message,
+                    Err(error) => {
+                        if send_error(&mut socket, error.to_string()).await.is_err() {
+                            break;
+                        }
+                        continue;
+                    }
+                };
+
+                // 新しいセリフまたは中断の前に、合成中または再生中のセリフを終了させる
+                let interrupted = rendering
+                    .take()
+                    .map(|current| current.utterance)
+                    .or_else(|| playback.take().map(|current| current.utterance));
+                if let Some(interrupted) = interrupted {
+                    let end = ServerEvent::End { utterance: interrupted, cancelled: true };
+                    if send(&mut socket, Outgoing::Event(end)).await.is_err() {
+                        break;
+                    }
+                }
+
+                match message {
+                    ClientMessage::Speak(request) => {
+                        utterance += 1;
+                        rendering = Some(Rendering {
+                            utterance,
+                            task: tokio::spawn(render(queue.clone(), request, utterance)),
+                        });
+                    }
+                    // 再生の完了を待たずに次のメッセージを処理する
+                    ClientMessage::Control(Control::Cancel) => {
+                        let queue = queue.clone();
+                        tokio::spawn(async move { queue.run(|backend| backend.stop()).await });
+                    }
+                }
+            }
+            joined = async { (&mut rendering.as_mut().expect("enabled while rendering").task).await },
+                if rendering.is_some() =>
+            {
+                let Some(current) = rendering.take() else { continue };
+                let sent = match joined {
+                    Ok(Ok(messages)) => {
+                        playback = Some(Playback {
+                            utterance: current.utterance,
+                            messages: messages.into_iter(),
+                            pending: None,
+                            started: Instant::now(),
+                        });
+                        Ok(())
+                    }
+                    Ok(Err(error)) => send_error(&mut socket, error.to_string()).await,
+                    Err(error) => send_error(&mut socket, error.to_string()).await,
+                };
+                if sent.is_err() {
+                    break;
+                }
+            }
+            _ = ticker.tick(), if playback.is_some() => {
+                let Some(current) = playback.as_mut() else { continue };
+                let mut failed = false;
+                for message in current.due() {
+                    if send(&mut socket, message).await.is_err() {
+                        failed = true;
+                        break;
+                    }
+                }
+                if failed {
+                    break;
+                }
+                if current.is_finished() {
+                    playback = None;
+                }
+            }
+        }
+    }
+}
+
+async fn send(socket: &mut WebSocket, message: Outgoing) -> std::result::Result<(), axum::Error> {
+    let message = match message {
+        Outgoing::Event(event) => Message::Text(
+            serde_json::to_string(&event)
+                .expect("events are always serializable")
+                .into(),
+        ),
+        Outgoing::Audio(bytes) => Message::Binary(bytes.into()),
+    };
+    socket.send(message).await
+}
+
+async fn send_error(
+    socket: &mut WebSocket,
+    message: String,
+) -> std::result::Result<(), axum::Error> {
+    send(socket, Outgoing::Event(ServerEvent::Error { message })).await
+}
+
+/// セリフを合成し、送信するメッセージの列を作成します。
+async fn render(queue: Queue, request: VoiceRequest, utterance: u64) -> Result<Vec<Timed>> {
+    let format = request.format;
+    format.ensure_supported()?;
+
+    let (audio, phonemes) = queue
+        .run(move |backend| {
+            request.apply(backend)?;
+            let audio = backend.synthesize(&request.text)?;
+            let phonemes = backend.phonemes(&request.text)?;
+            Ok((audio, phonemes))
+        })
+        .await?;
+
+    schedule(utterance, &audio, &phonemes, format)
+}
+
+/// 音声をチャンクに分割し、音素イベントと時刻順に並べます。
+///
+/// # Errors
+///
+/// 音声を指定された形式にエンコードできない場合はエラーを返します。
+pub fn schedule(
+    utterance: u64,
+    audio: &AudioBuffer,
+    phonemes: &[PhonemeData],
+    format: AudioFormat,
+) -> Result<Vec<Timed>> {
+    let chunks = chunks(audio, format)?;
+
+    let mut messages = Vec::with_capacity(chunks.len() + phonemes.len() + 2);
+    messages.push(Timed {
+        at: 0.0,
+        message: Outgoing::Event(ServerEvent::Start {
+            utterance,
+            format,
+            sample_rate: audio.sample_rate(),
+            channels: audio.channels(),
+            duration: audio.duration(),
+        }),
+    });
+
+    let mut phonemes = phonemes.iter().peekable();
+    for (index, chunk) in chunks.into_iter().enumerate() {
+        let at = index as f64 * CHUNK_DURATION;
+        let next = (index + 1) as f64 * CHUNK_DURATION;
+        while let Some(phoneme) = phonemes.next_if(|p| p.start_time() < next) {
+            messages.push(Timed {
+                at,
+                message: Outgoing::Event(phoneme_event(utterance, phoneme)),
+            });
+        }
+        messages.push(Timed {
+            at,
+            message: Outgoing::Audio(chunk),
+        });
+    }
+
+    let end = audio.duration();
+    messages.extend(phonemes.map(|phoneme| Timed {
+        at: end,
+        message: Outgoing::Event(phoneme_event(utterance, phoneme)),
+    }));
+    messages.push(Timed {
+        at: end,
+        message: Outgoing::Event(ServerEvent::End {
+            utterance,
+            cancelled: false,
+        }),
+    });
+
+    Ok(messages)
+}
+
+fn phoneme_event(utterance: u64, phoneme: &PhonemeData) -> ServerEvent {
+    ServerEvent::Phoneme {
+        utterance,
+        phoneme: phoneme.phoneme().to_string(),
+        viseme: Viseme::from(phoneme),
+        start_time: phoneme.start_time(),
+        end_time: phoneme.end_time(),
+    }
+}
+
+/// 音声を約20msのチャンクに分割します。
+fn chunks(audio: &AudioBuffer, format: AudioFormat) -> Result<Vec<Vec<u8>>> {
+    match format {
+        AudioFormat::Wav => {
+            let frames = (f64::from(audio.sample_rate()) * CHUNK_DURATION).round() as usize;
+            let size = frames.max(1) * usize::from(audio.channels());
+            Ok(audio
+                .samples()
+                .chunks(size)
+                .map(|chunk| chunk.iter().flat_map(|s| s.to_le_bytes()).collect())
+                .collect())
+        }
+        #[cfg(feature = "opus")]
+        AudioFormat::Opus => Ok(crate::opus::encode_packets(audio)?.packets),
+        #[cfg(not(feature = "opus"))]
+        AudioFormat::Opus => Err(crate::error::Error::UnsupportedFormat("opus".to_string())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cevio_ai::{Backend, Cast, FakeBackend, Speed};
+
+    use super::*;
+
+    #[test]
+    fn parses_client_messages() {
+        let message: ClientMessage = serde_json::from_str(r#"{"type": "cancel"}"#).unwrap();
+        assert!(matches!(message, ClientMessage::Control(Control::Cancel)));
+
+        let message: ClientMessage =
+            serde_json::from_str(r#"{"text": "こんにちは", "emotions": {"元気": 80}}"#).unwrap();
+        let ClientMessage::Speak(request) = message else {
+            panic!("expected speak");
+        };
+        assert_eq!(request.text, "こんにちは");
+        assert_eq!(request.emotions["元気"], 80);
+    }
+
+    #[test]
+    fn interleaves_phonemes_with_chunks() {
+        let backend = FakeBackend::new();
+        backend
+            .apply_cast(&Cast {
+                speed: Speed::new(50),
+                ..Default::default()
+            })
+            .unwrap();
+        let audio = backend.synthesize("あい").unwrap();
+        let phonemes = backend.phonemes("あい").unwrap();
+
+        let messages = schedule(7, &audio, &phonemes, AudioFormat::Wav).unwrap();
+
+        // 0.4秒 = 20チャンク
+        let chunks: Vec<_> = messages
+            .iter()
+            .filter_map(|timed| match &timed.message {
+                Outgoing::Audio(bytes) => Some(bytes),
+                Outgoing::Event(_) => None,
+            })
+            .collect();
+        assert_eq!(chunks.len(), 20);
+        assert!(chunks.iter().all(|chunk| chunk.len() == 960 * 2));
+
+        // 送信時刻は単調増加
+        assert!(messages.windows(2).all(|pair| pair[0].at <= pair[1].at));
+
+        // 2番目の音素（0.1秒開始）は6番目のチャンク（0.1～0.12秒）の直前
+        let position = messages
+            .iter()
+            .position(|timed| {
+                matches!(&timed.message, Outgoing::Event(ServerEvent::Phoneme { start_time, .. }) if *start_time > 0.05)
+            })
+            .unwrap();
+        assert!((messages[position].at - 0.1).abs() < 1e-9);
+        assert!(matches!(messages[position + 1].message, Outgoing::Audio(_)));
+
+        assert!(matches!(
+            messages.first().unwrap().message,
+            Outgoing::Event(ServerEvent::Start { utterance: 7, .. })
+        ));
+        assert_eq!(
+            messages.last().unwrap().message,
+            Outgoing::Event(ServerEvent::End {
+                utterance: 7,
+                cancelled: false
+            })
+        );
+    }
+}
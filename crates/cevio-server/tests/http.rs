@@ -0,0 +1,236 @@
+use axum::{
+    body::Body,
+    http::{header, Request, StatusCode},
+    Router,
+};
+use cevio_ai::{AudioBuffer, FakeBackend};
+use cevio_server::{router, Queue};
+use http_body_util::BodyExt;
+use serde_json::{json, Value};
+use tower::ServiceExt;
+
+fn app() -> Router {
+    router(Queue::spawn(|| Ok(FakeBackend::new())).unwrap())
+}
+
+async fn send(app: &Router, request: Request<Body>) -> (StatusCode, Option<String>, Vec<u8>) {
+    let response = app.clone().oneshot(request).await.unwrap();
+    let status = response.status();
+    let content_type = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .map(|value| value.to_str().unwrap().to_string());
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    (status, content_type, body.to_vec())
+}
+
+async fn get(app: &Router, uri: &str) -> (StatusCode, Value) {
+    let request = Request::get(uri).body(Body::empty()).unwrap();
+    let (status, _, body) = send(app, request).await;
+    (status, serde_json::from_slice(&body).unwrap())
+}
+
+async fn post(app: &Router, uri: &str, body: Value) -> (StatusCode, Option<String>, Vec<u8>) {
+    let request = Request::post(uri)
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(Body::from(body.to_string()))
+        .unwrap();
+    send(app, request).await
+}
+
+#[tokio::test]
+async fn lists_casts() {
+    let app = app();
+    let (status, body) = get(&app, "/casts").await;
+
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body, json!(["さとうささら", "すずきつづみ", "タカハシ"]));
+}
+
+#[tokio::test]
+async fn lists_components_of_cast() {
+    let app = app();
+    let uri = format!(
+        "/casts/{}/components",
+        "%E3%81%99%E3%81%9A%E3%81%8D%E3%81%A4%E3%81%A5%E3%81%BF"
+    );
+    let (status, body) = get(&app, &uri).await;
+
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body[0]["name"], "クール");
+    assert_eq!(body[0]["value"], 100);
+    assert_eq!(body[1]["name"], "照れ");
+}
+
+#[tokio::test]
+async fn unknown_cast_is_a_bad_request() {
+    let app = app();
+    let (status, body) = get(&app, "/casts/unknown/components").await;
+
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert!(body["error"].as_str().unwrap().contains("unknown"));
+}
+
+#[tokio::test]
+async fn poisoned_backend_is_rebuilt() {
+    let app = router(
+        Queue::spawn(|| {
+            let backend = FakeBackend::new();
+            backend.poison();
+            Ok(backend)
+        })
+        .unwrap(),
+    );
+    let (status, body) = get(&app, "/casts").await;
+
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body[0], "さとうささら");
+}
+
+#[tokio::test]
+async fn synthesizes_wav() {
+    let app = app();
+    let (status, content_type, body) = post(
+        &app,
+        "/synthesize",
+        json!({
+            "text": "こんにちは",
+            "cast": "さとうささら",
+            "params": { "volume": 80, "speed": 100 },
+            "emotions": { "元気": 80 }
+        }),
+    )
+    .await;
+
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(content_type.as_deref(), Some("audio/wav"));
+    let audio = AudioBuffer::from_wav_bytes(&body).unwrap();
+    assert_eq!(audio.sample_rate(), 48_000);
+    // 速さ100では1文字0.05秒
+    assert!((audio.duration() - (0.1 + 5.0 * 0.05 + 0.1)).abs() < 1e-3);
+}
+
+#[cfg(not(feature = "opus"))]
+#[tokio::test]
+async fn opus_requires_feature() {
+    let app = app();
+    let (status, _, _) = post(
+        &app,
+        "/synthesize",
+        json!({ "text": "こんにちは", "format": "opus" }),
+    )
+    .await;
+
+    assert_eq!(status, StatusCode::UNSUPPORTED_MEDIA_TYPE);
+}
+
+#[cfg(feature = "opus")]
+#[tokio::test]
+async fn synthesizes_ogg_opus() {
+    let app = app();
+    let (status, content_type, body) = post(
+        &app,
+        "/synthesize",
+        json!({ "text": "こんにちは", "format": "opus" }),
+    )
+    .await;
+
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(content_type.as_deref(), Some("audio/ogg; codecs=opus"));
+    assert_eq!(&body[0..4], b"OggS");
+    assert_eq!(&body[28..36], b"OpusHead");
+}
+
+#[tokio::test]
+async fn returns_phonemes_and_duration() {
+    let app = app();
+    let request = json!({ "text": "あい", "params": { "speed": 50 } });
+
+    let (status, _, body) = post(&app, "/phonemes", request.clone()).await;
+    assert_eq!(status, StatusCode::OK);
+    let phonemes: Value = serde_json::from_slice(&body).unwrap();
+    let phonemes = phonemes.as_array().unwrap();
+    assert_eq!(phonemes.len(), 4);
+    assert_eq!(phonemes[0]["phoneme"], "sil");
+
+    let (status, _, body) = post(&app, "/duration", request).await;
+    assert_eq!(status, StatusCode::OK);
+    let duration: Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(duration["duration"], phonemes[3]["end_time"]);
+}
+
+#[tokio::test]
+async fn speaks() {
+    let app = app();
+    let (status, _, body) = post(&app, "/speak", json!({ "text": "こんにちは" })).await;
+
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(
+        serde_json::from_slice::<Value>(&body).unwrap(),
+        json!({ "succeeded": true })
+    );
+}
+
+#[tokio::test]
+async fn rejects_invalid_requests() {
+    let app = app();
+
+    // 範囲外のパラメータはJSONの段階で拒否される
+    let (status, _, _) = post(
+        &app,
+        "/synthesize",
+        json!({ "text": "a", "params": { "volume": 101 } }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+
+    let (status, _, body) = post(
+        &app,
+        "/synthesize",
+        json!({ "text": "a", "emotions": { "眠気": 10 } }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let body: Value = serde_json::from_slice(&body).unwrap();
+    assert!(body["error"].as_str().unwrap().contains("眠気"));
+}
+
+#[tokio::test]
+async fn serializes_concurrent_requests() {
+    let app = app();
+
+    // 異なるキャスト・速さのリクエストを同時に送っても、それぞれの設定で処理される
+    let requests = (0..16).map(|index| {
+        let app = app.clone();
+        let speed = if index % 2 == 0 { 0 } else { 100 };
+        async move {
+            let (_, _, body) = post(
+                &app,
+                "/duration",
+                json!({ "text": "あいうえお", "params": { "speed": speed } }),
+            )
+            .await;
+            let body: Value = serde_json::from_slice(&body).unwrap();
+            (speed, body["duration"].as_f64().unwrap())
+        }
+    });
+
+    for (speed, duration) in futures_join_all(requests).await {
+        let expected = if speed == 0 { 0.2 + 1.0 } else { 0.2 + 0.25 };
+        assert!((duration - expected).abs() < 1e-9, "{speed}: {duration}");
+    }
+}
+
+async fn futures_join_all<F: std::future::Future + Send + 'static>(
+    futures: impl Iterator<Item = F>,
+) -> Vec<F::Output>
+where
+    F::Output: Send + 'static,
+{
+    let handles: Vec<_> = futures.map(tokio::spawn).collect();
+    let mut outputs = Vec::with_capacity(handles.len());
+    for handle in handles {
+        outputs.push(handle.await.unwrap());
+    }
+    outputs
+}
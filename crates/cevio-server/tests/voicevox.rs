@@ -0,0 +1,115 @@
+use axum::{
+    body::Body,
+    http::{header, Request, StatusCode},
+    Router,
+};
+use cevio_ai::{AudioBuffer, FakeBackend};
+use cevio_server::{voicevox, Queue};
+use http_body_util::BodyExt;
+use serde_json::Value;
+use tower::ServiceExt;
+
+fn app() -> Router {
+    voicevox::router(Queue::spawn(|| Ok(FakeBackend::new())).unwrap())
+}
+
+async fn send(app: &Router, request: Request<Body>) -> (StatusCode, Vec<u8>) {
+    let response = app.clone().oneshot(request).await.unwrap();
+    let status = response.status();
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    (status, body.to_vec())
+}
+
+async fn audio_query(app: &Router, text: &str, speaker: u32) -> (StatusCode, Value) {
+    let uri = format!(
+        "/audio_query?text={}&speaker={speaker}",
+        text.bytes()
+            .map(|b| format!("%{b:02X}"))
+            .collect::<String>()
+    );
+    let request = Request::post(uri).body(Body::empty()).unwrap();
+    let (status, body) = send(app, request).await;
+    (status, serde_json::from_slice(&body).unwrap_or(Value::Null))
+}
+
+async fn synthesis(app: &Router, query: &Value, speaker: u32) -> (StatusCode, Vec<u8>) {
+    let request = Request::post(format!("/synthesis?speaker={speaker}"))
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(Body::from(query.to_string()))
+        .unwrap();
+    send(app, request).await
+}
+
+#[tokio::test]
+async fn lists_speakers_with_emotion_styles() {
+    let app = app();
+    let request = Request::get("/speakers").body(Body::empty()).unwrap();
+    let (status, body) = send(&app, request).await;
+    let speakers: Value = serde_json::from_slice(&body).unwrap();
+
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(speakers.as_array().unwrap().len(), 3);
+    assert_eq!(speakers[1]["name"], "すずきつづみ");
+    assert_eq!(speakers[1]["styles"][0]["name"], "クール");
+    assert_eq!(speakers[1]["styles"][0]["id"], 100);
+    assert_eq!(speakers[1]["styles"][1]["name"], "照れ");
+    assert_eq!(speakers[1]["styles"][1]["id"], 101);
+    assert_ne!(speakers[0]["speaker_uuid"], speakers[1]["speaker_uuid"]);
+}
+
+#[tokio::test]
+async fn builds_audio_query_from_phonemes() {
+    let app = app();
+    let (status, query) = audio_query(&app, "あい、う", 0).await;
+
+    assert_eq!(status, StatusCode::OK);
+    let phrases = query["accent_phrases"].as_array().unwrap();
+    assert_eq!(phrases.len(), 2);
+    assert_eq!(phrases[0]["moras"].as_array().unwrap().len(), 2);
+    assert_eq!(phrases[0]["pause_mora"]["vowel"], "pau");
+    assert_eq!(phrases[1]["moras"].as_array().unwrap().len(), 1);
+    assert!(query["speedScale"].is_number());
+    assert_eq!(query["prePhonemeLength"], 0.1);
+}
+
+#[tokio::test]
+async fn synthesizes_audio_query() {
+    let app = app();
+    let (_, mut query) = audio_query(&app, "あいう", 0).await;
+    query["speedScale"] = 2.0.into();
+    query["outputSamplingRate"] = 24_000.into();
+    query["outputStereo"] = true.into();
+
+    let (status, body) = synthesis(&app, &query, 0).await;
+
+    assert_eq!(status, StatusCode::OK);
+    let audio = AudioBuffer::from_wav_bytes(&body).unwrap();
+    assert_eq!(audio.sample_rate(), 24_000);
+    assert_eq!(audio.channels(), 2);
+    // speedScale 2.0は速さ100（1文字0.05秒）
+    assert!((audio.duration() - (0.1 + 3.0 * 0.05 + 0.1)).abs() < 1e-3);
+}
+
+#[tokio::test]
+async fn unreasonable_sampling_rate_is_a_bad_request() {
+    let app = app();
+    let (_, mut query) = audio_query(&app, "あいう", 0).await;
+
+    for rate in [0, u32::MAX] {
+        query["outputSamplingRate"] = rate.into();
+        let (status, _) = synthesis(&app, &query, 0).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+}
+
+#[tokio::test]
+async fn unknown_speaker_is_a_bad_request() {
+    let app = app();
+
+    let (status, _) = audio_query(&app, "あ", 300).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    // すずきつづみの感情は2つだけ
+    let (status, _) = audio_query(&app, "あ", 102).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
@@ -0,0 +1,191 @@
+use std::time::{Duration, Instant};
+
+use cevio_ai::FakeBackend;
+use cevio_server::{ws, Queue};
+use futures_util::{SinkExt, StreamExt};
+use serde_json::{json, Value};
+use tokio::net::{TcpListener, TcpStream};
+use tokio_tungstenite::{connect_async, tungstenite::Message, MaybeTlsStream, WebSocketStream};
+
+type Client = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+async fn connect() -> Client {
+    connect_with(FakeBackend::new()).await
+}
+
+async fn connect_with(backend: FakeBackend) -> Client {
+    let app = ws::router(Queue::spawn(move || Ok(backend)).unwrap());
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+    let (client, _) = connect_async(format!("ws://{address}/stream"))
+        .await
+        .unwrap();
+    client
+}
+
+async fn send(client: &mut Client, message: Value) {
+    client
+        .send(Message::Text(message.to_string().into()))
+        .await
+        .unwrap();
+}
+
+/// 次のイベントまでに受信した音声チャンクの数とイベントを返します。
+async fn next_event(client: &mut Client) -> (usize, Value) {
+    let mut chunks = 0;
+    loop {
+        match client.next().await.unwrap().unwrap() {
+            Message::Text(text) => return (chunks, serde_json::from_str(&text).unwrap()),
+            Message::Binary(_) => chunks += 1,
+            _ => {}
+        }
+    }
+}
+
+#[tokio::test]
+async fn streams_audio_and_phonemes() {
+    let mut client = connect().await;
+    let started = Instant::now();
+    send(
+        &mut client,
+        json!({ "text": "あい", "params": { "speed": 50 } }),
+    )
+    .await;
+
+    let (_, start) = next_event(&mut client).await;
+    assert_eq!(start["type"], "start");
+    assert_eq!(start["utterance"], 1);
+    assert_eq!(start["format"], "wav");
+    assert_eq!(start["sample_rate"], 48_000);
+
+    let mut chunks = 0;
+    let mut phonemes = Vec::new();
+    let end = loop {
+        let (received, event) = next_event(&mut client).await;
+        chunks += received;
+        match event["type"].as_str().unwrap() {
+            "phoneme" => phonemes.push(event),
+            _ => break event,
+        }
+    };
+
+    assert_eq!(
+        end,
+        json!({ "type": "end", "utterance": 1, "cancelled": false })
+    );
+    assert_eq!(chunks, 20);
+    assert_eq!(phonemes.len(), 4);
+    assert_eq!(phonemes[0]["phoneme"], "sil");
+    assert_eq!(phonemes[0]["viseme"], "rest");
+    // 0.4秒の音声は先行送信分を除いて再生速度に合わせて送られる
+    assert!(started.elapsed() >= Duration::from_millis(150));
+}
+
+#[tokio::test]
+async fn cancels_playback() {
+    let mut client = connect().await;
+    send(
+        &mut client,
+        json!({ "text": "あいうえおかきくけこ", "params": { "speed": 0 } }),
+    )
+    .await;
+
+    let (_, start) = next_event(&mut client).await;
+    assert_eq!(start["type"], "start");
+
+    send(&mut client, json!({ "type": "cancel" })).await;
+    let end = loop {
+        let (_, event) = next_event(&mut client).await;
+        if event["type"] == "end" {
+            break event;
+        }
+    };
+    assert_eq!(
+        end,
+        json!({ "type": "end", "utterance": 1, "cancelled": true })
+    );
+
+    // 中断後も続けて合成できる
+    send(&mut client, json!({ "text": "あ" })).await;
+    let (_, start) = next_event(&mut client).await;
+    assert_eq!(start["utterance"], 2);
+}
+
+#[tokio::test]
+async fn cancels_synthesis() {
+    let backend = FakeBackend::new();
+    backend.set_latency(Duration::from_millis(500));
+    let mut client = connect_with(backend).await;
+    let started = Instant::now();
+
+    send(&mut client, json!({ "text": "あいうえお" })).await;
+    send(&mut client, json!({ "type": "cancel" })).await;
+    let (_, end) = next_event(&mut client).await;
+    assert_eq!(
+        end,
+        json!({ "type": "end", "utterance": 1, "cancelled": true })
+    );
+    assert!(started.elapsed() < Duration::from_millis(500));
+}
+
+#[tokio::test]
+async fn interrupted_synthesis_is_skipped() {
+    let backend = FakeBackend::new();
+    backend.set_latency(Duration::from_millis(300));
+    let mut client = connect_with(backend).await;
+    let started = Instant::now();
+
+    // 1つ目の合成中に2つ目を中断すると、2つ目の合成は実行されない
+    for text in ["あ", "い", "う"] {
+        send(&mut client, json!({ "text": text })).await;
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    let start = loop {
+        let (_, event) = next_event(&mut client).await;
+        if event["type"] == "start" {
+            break event;
+        }
+    };
+    assert_eq!(start["utterance"], 3);
+    assert!(started.elapsed() < Duration::from_millis(800));
+}
+
+#[tokio::test]
+async fn invalid_messages_do_not_interrupt_playback() {
+    let mut client = connect().await;
+    send(
+        &mut client,
+        json!({ "text": "あいうえお", "params": { "speed": 50 } }),
+    )
+    .await;
+    let (_, start) = next_event(&mut client).await;
+    assert_eq!(start["type"], "start");
+
+    client.send(Message::Text("not json".into())).await.unwrap();
+    let end = loop {
+        let (_, event) = next_event(&mut client).await;
+        match event["type"].as_str().unwrap() {
+            "phoneme" | "error" => {}
+            _ => break event,
+        }
+    };
+    assert_eq!(
+        end,
+        json!({ "type": "end", "utterance": 1, "cancelled": false })
+    );
+}
+
+#[tokio::test]
+async fn reports_errors() {
+    let mut client = connect().await;
+
+    send(&mut client, json!({ "text": "あ", "cast": "unknown" })).await;
+    let (_, event) = next_event(&mut client).await;
+    assert_eq!(event["type"], "error");
+
+    send(&mut client, json!({ "unexpected": true })).await;
+    let (_, event) = next_event(&mut client).await;
+    assert_eq!(event["type"], "error");
+}